@@ -1,127 +1,525 @@
 //! Trolley animation implementation.
 
 use crate::components::*;
+use crate::easing::*;
 use crate::resources::*;
+use crate::spline::*;
 use crate::states::*;
 use crate::util::*;
-use bevy::ecs::schedule::SystemConfigs;
+use bevy::audio::PlaybackMode;
+use bevy::ecs::schedule::{ScheduleLabel, SystemConfigs};
 use bevy::prelude::*;
+use serde::de::{self, Deserialize, Deserializer};
+use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// A linear animation transformation. This is the default animation function.
+///
+/// The translation, including the z coordinate, and the scale of each axis are
+/// interpolated linearly, and the rotation is interpolated spherically along
+/// the shortest arc.
 pub fn linear_animation(
     start_transform: Transform,
     end_transform: Transform,
     progress: f32,
 ) -> Transform {
-    let start_point_x = start_transform.translation.x;
-    let start_point_y = start_transform.translation.y;
-    let start_rotation = start_transform.rotation.z;
-    let start_scale = start_transform.scale.x;
-    let end_point_x = end_transform.translation.x;
-    let end_point_y = end_transform.translation.y;
-    let end_rotation = end_transform.rotation.z;
-    let end_scale = end_transform.scale.x;
-
-    let transformed_x = point_between(start_point_x, end_point_x, progress);
-    let transformed_y = point_between(start_point_y, end_point_y, progress);
-    let transformed_rotation = point_between(start_rotation, end_rotation, progress);
-    let transformed_scale = point_between(start_scale, end_scale, progress);
+    interpolate_transform(start_transform, end_transform, progress)
+}
+
+/// The transform function for the loop-da-loop animation. The loop starts and
+/// ends at the starting transformation, and the trolley slows down as it climbs
+/// to the top.
+pub fn loop_animation(
+    start_transform: Transform,
+    _end_transform: Transform,
+    progress: f32,
+) -> Transform {
+    let loop_radius = 70.0;
+    let loop_slowdown = 0.6;
+    let progress = loop_progress(progress, loop_slowdown);
+    let loop_center = Vec2::new(
+        start_transform.translation.x,
+        start_transform.translation.y - loop_radius,
+    );
+    let progress_angle = -std::f32::consts::TAU * (progress - 0.25);
 
     Transform::IDENTITY
-        .with_translation(Vec3::new(transformed_x, transformed_y, 0.0))
-        .with_rotation(Quat::from_rotation_z(transformed_rotation))
-        .with_scale(Vec3::new(transformed_scale, transformed_scale, 1.0))
+        .with_translation(Vec3::new(
+            loop_center.x + loop_radius * progress_angle.cos(),
+            loop_center.y + loop_radius * progress_angle.sin(),
+            0.0,
+        ))
+        .with_rotation(Quat::from_rotation_z(std::f32::consts::TAU * progress))
+}
+
+/// The entity moved by an animation.
+#[derive(serde::Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub enum AnimationTarget {
+    /// The trolley.
+    #[default]
+    Trolley,
+    /// The lever/player texture, given the index of the lever.
+    Lever(usize),
+    /// The hostages texture, given the index of the track the hostages are on.
+    Hostages(usize),
+    /// Every entity with the given [`AnimationTag`].
+    Tagged(String),
+}
+
+/// The components that identify the target of an animation.
+pub type AnimationTargetData = (
+    Has<TrolleyTexture>,
+    Option<&'static LeverPlayerTexture>,
+    Option<&'static HostagesTexture>,
+    Option<&'static AnimationTag>,
+);
+
+impl AnimationTarget {
+    /// Checks whether an entity is targeted, given the components that
+    /// identify it.
+    pub fn matches(
+        &self,
+        (trolley, lever, hostages, tag): (
+            bool,
+            Option<&LeverPlayerTexture>,
+            Option<&HostagesTexture>,
+            Option<&AnimationTag>,
+        ),
+    ) -> bool {
+        match self {
+            Self::Trolley => trolley,
+            Self::Lever(lever_index) => lever.is_some_and(|lever| **lever == *lever_index),
+            Self::Hostages(track_index) => {
+                hostages.is_some_and(|hostages| **hostages == *track_index)
+            }
+            Self::Tagged(name) => tag.is_some_and(|tag| **tag == *name),
+        }
+    }
+}
+
+/// An event which starts the animations with the given cue in the current
+/// scenario, such as when the player does something before the trolley runs.
+#[derive(Event, Debug, Clone)]
+pub struct AnimationCue(pub String);
+
+/// Something that happens at a point in the timeline of an animation.
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+pub enum TimelineEvent {
+    /// Plays a sound effect, given the name of the sound.
+    PlaySound(String),
+    /// Swaps the texture of the target of the animation, given the name of the
+    /// texture.
+    SetTexture(String),
+    /// Raises a flag in [`AnimationFlagsRes`], given its name.
+    SetFlag(String),
+    /// Shakes the camera.
+    ShakeCamera {
+        /// The furthest the camera moves from its resting position at the
+        /// start of the shake, in pixels.
+        intensity: f32,
+        /// The duration of the shake in seconds.
+        duration: f32,
+    },
+    /// Sends an [`AnimationCue`] with the given name, which starts the cued
+    /// animations and frame animations of the scenario.
+    Cue(String),
+}
+
+/// An event sent whenever an event in the timeline of an animation happens.
+#[derive(Event, Debug, Clone)]
+pub struct TimelineEventFired {
+    /// The index of the animation within the scenario.
+    pub animation_index: usize,
+    /// What happened.
+    pub event: TimelineEvent,
+}
+
+/// When an action of an animation runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum AnimationActionKind {
+    /// At the start of the animation.
+    Start,
+    /// At the end of a node, given the index of the node.
+    NodeEnd(usize),
+    /// At a point in the timeline, given the index of the timed action.
+    Timed(usize),
 }
 
-/// Sets the animation index state once [`AnimationState::Running`] is entered.
-fn set_animation_index_state(
-    scenario_index_state: Res<State<ScenarioIndexState>>,
-    mut next_animation_index_state: ResMut<NextState<AnimationIndexState>>,
-    mut next_animation_node_index_state: ResMut<NextState<AnimationNodeIndexState>>,
+/// The schedule containing an action of an animation.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct AnimationAction {
+    /// The index of the scenario.
+    scenario_index: usize,
+    /// The index of the animation within the scenario.
+    animation_index: usize,
+    /// When the action runs.
+    kind: AnimationActionKind,
+}
+
+/// Resource containing the animation actions that have a schedule, and those
+/// waiting to run.
+#[derive(Resource, Default)]
+struct AnimationActionsRes {
+    /// The actions with a schedule, as not every animation and node has an
+    /// action.
+    registered: HashSet<AnimationAction>,
+    /// The actions waiting to run, in the order they were queued.
+    queued: Vec<AnimationAction>,
+}
+
+impl AnimationActionsRes {
+    /// Queues an action to run, unless it has no schedule.
+    fn queue(&mut self, action: AnimationAction) {
+        if self.registered.contains(&action) {
+            self.queued.push(action);
+        }
+    }
+}
+
+/// Starts every animation in a scenario with the given cue whose track and
+/// lever state conditions match, queuing their start actions.
+fn start_matching_animations(
+    scenario_index: usize,
+    animations: &[AnimationConfig],
+    cue: Option<&str>,
+    track: TrackState,
+    lever_states: &[LeverState],
+    running_animations: &mut RunningAnimationsRes,
+    actions: &mut AnimationActionsRes,
+) {
+    for (animation_index, animation) in animations.iter().enumerate() {
+        if animation.cue.as_deref() == cue
+            && animation
+                .track_condition
+                .is_none_or(|condition| condition == track)
+            && lever_states_match(&animation.lever_states_condition, lever_states)
+        {
+            actions.queue(AnimationAction {
+                scenario_index,
+                animation_index,
+                kind: AnimationActionKind::Start,
+            });
+
+            if let Some(node) = animation.nodes.first() {
+                running_animations.push(RunningAnimation {
+                    animation_index,
+                    node_index: 0,
+                    timer: Timer::from_seconds(node.duration, TimerMode::Once),
+                });
+            }
+        }
+    }
+}
+
+/// Starts the animations without a cue once [`AnimationState::Running`] is
+/// entered.
+fn start_trolley_animations(
+    scenario_index: Res<State<ScenarioIndexState>>,
     animation_config: Res<AnimationConfigRes>,
-    lever_state: Res<State<LeverState>>,
+    track: Res<State<TrackState>>,
+    lever_states: Res<LeverStatesRes>,
+    mut running_animations: ResMut<RunningAnimationsRes>,
+    mut actions: ResMut<AnimationActionsRes>,
 ) {
-    let scenario_index = scenario_index_state.unwrap();
+    let scenario_index = scenario_index.unwrap();
 
-    next_animation_node_index_state.set(AnimationNodeIndexState(Some(0)));
+    start_matching_animations(
+        scenario_index,
+        &animation_config[scenario_index],
+        None,
+        **track,
+        &lever_states,
+        &mut running_animations,
+        &mut actions,
+    );
+}
 
-    for (animation_index, animation) in animation_config.0[scenario_index].iter().enumerate() {
-        if match animation.lever_state_condition {
-            Some(desired_state) => desired_state == **lever_state,
-            None => true,
-        } {
-            next_animation_index_state.set(AnimationIndexState(Some(animation_index)));
-        }
+/// Starts the animations with a cue whenever that cue is sent.
+fn start_cued_animations(
+    mut cues: EventReader<AnimationCue>,
+    scenario_index: Res<State<ScenarioIndexState>>,
+    animation_config: Res<AnimationConfigRes>,
+    track: Res<State<TrackState>>,
+    lever_states: Option<Res<LeverStatesRes>>,
+    mut running_animations: ResMut<RunningAnimationsRes>,
+    mut actions: ResMut<AnimationActionsRes>,
+) {
+    let Some((scenario_index, this_scenario_animations, lever_states)) = scenario_index
+        .0
+        .and_then(|index| Some((index, animation_config.get(index)?, lever_states?)))
+    else {
+        cues.clear();
+        return;
+    };
+
+    for cue in cues.read() {
+        start_matching_animations(
+            scenario_index,
+            this_scenario_animations,
+            Some(&cue.0),
+            **track,
+            &lever_states,
+            &mut running_animations,
+            &mut actions,
+        );
     }
 }
 
-/// Unsets the animation index state once [`AnimationState::Running`] is exited.
-fn unset_animation_index_state(
-    mut commands: Commands,
-    mut next_animation_index_state: ResMut<NextState<AnimationIndexState>>,
-    mut next_animation_node_index_state: ResMut<NextState<AnimationNodeIndexState>>,
+/// Stops every animation and lowers every flag once [`AnimationState::Waiting`]
+/// is entered, ready for the next scenario.
+fn clear_animations(
+    mut running_animations: ResMut<RunningAnimationsRes>,
+    mut flags: ResMut<AnimationFlagsRes>,
 ) {
-    next_animation_index_state.set(AnimationIndexState(None));
-    next_animation_node_index_state.set(AnimationNodeIndexState(None));
-    commands.remove_resource::<AnimationSectionTimer>();
+    running_animations.clear();
+    flags.clear();
 }
 
-/// Immediately sets the animation state to [`AnimationState::Complete`].
-fn goto_end_animation(mut next_animation_state: ResMut<NextState<AnimationState>>) {
-    next_animation_state.set(AnimationState::Complete);
+/// What happened while stepping a running animation forwards.
+#[derive(Debug, PartialEq)]
+struct AnimationStep {
+    /// The transform of the target, before normalizing it to the canvas.
+    transform: Transform,
+    /// The indices of the timeline events passed, in timeline order.
+    events: Vec<usize>,
+    /// The indices of the timed actions passed, in the order they were added.
+    timed_actions: Vec<usize>,
+    /// The indices of the nodes that ended, in order.
+    ended_nodes: Vec<usize>,
+    /// Whether the last node ended, finishing the animation.
+    finished: bool,
 }
 
-/// Sets the animation section timer resource.
-fn set_animation_section_timer(
-    mut commands: Commands,
+/// Steps a running animation forwards by `delta`. Time left over once a node
+/// ends carries over into the next, so an animation takes the same time
+/// however the ticks fall. Timeline events and timed actions are passed once
+/// the time since the start of the animation reaches theirs, except that
+/// those at the very end are passed as it finishes.
+fn step_animation(
+    animation: &AnimationConfig,
+    running_animation: &mut RunningAnimation,
+    delta: Duration,
+) -> AnimationStep {
+    let time_in_animation = |running_animation: &RunningAnimation| {
+        animation.nodes[..running_animation.node_index]
+            .iter()
+            .map(|node| node.duration)
+            .sum::<f32>()
+            + running_animation.timer.elapsed_secs()
+    };
+
+    // Step 1: Run down the timers of as many nodes as the delta covers
+    let previous_time = time_in_animation(running_animation);
+    let mut remaining = delta;
+    let mut ended_nodes = Vec::new();
+    let mut finished = false;
+
+    loop {
+        let left_in_node = running_animation.timer.remaining();
+
+        if !running_animation.timer.tick(remaining).finished() {
+            break;
+        }
+
+        ended_nodes.push(running_animation.node_index);
+        remaining = remaining.saturating_sub(left_in_node);
+
+        let Some(next_node) = animation.nodes.get(running_animation.node_index + 1) else {
+            finished = true;
+            break;
+        };
+
+        running_animation.node_index += 1;
+        running_animation.timer = Timer::from_seconds(next_node.duration, TimerMode::Once);
+    }
+
+    // Step 2: Place the target in the node the animation has reached
+    let node_index = running_animation.node_index;
+    let from_transform = match node_index {
+        0 => animation.start_transform,
+        _ => animation.nodes[node_index - 1].transform,
+    };
+    let node = &animation.nodes[node_index];
+    let transform = (node.animation_fn)(
+        from_transform,
+        node.transform,
+        running_animation.timer.fraction(),
+    );
+
+    // Step 3: Find the timeline events and timed actions passed
+    let current_time = time_in_animation(running_animation);
+    let passed = |time: f32| time >= previous_time && (time < current_time || finished);
+    let events = animation
+        .timeline
+        .iter()
+        .enumerate()
+        .filter(|(_, timed_event)| passed(timed_event.time))
+        .map(|(index, _)| index)
+        .collect();
+    let timed_actions = animation
+        .action_times
+        .iter()
+        .enumerate()
+        .filter(|(_, &time)| passed(time))
+        .map(|(index, _)| index)
+        .collect();
+
+    AnimationStep {
+        transform,
+        events,
+        timed_actions,
+        ended_nodes,
+        finished,
+    }
+}
+
+/// Updates the running animations every tick.
+fn animations_update(
     animation_config: Res<AnimationConfigRes>,
     scenario_index: Res<State<ScenarioIndexState>>,
-    animation_index: Res<State<AnimationIndexState>>,
-    animation_node_index: Res<State<AnimationNodeIndexState>>,
+    animation_state: Res<State<AnimationState>>,
+    mut next_animation_state: ResMut<NextState<AnimationState>>,
+    time: Res<Time>,
+    mut running_animations: ResMut<RunningAnimationsRes>,
+    mut actions: ResMut<AnimationActionsRes>,
+    mut timeline_events: EventWriter<TimelineEventFired>,
+    mut targets: Query<(&mut Transform, AnimationTargetData)>,
 ) {
-    let this_scenario_animations = &animation_config[scenario_index.unwrap()];
-    let this_animation = &this_scenario_animations[animation_index.unwrap()];
-    let this_node = &this_animation.nodes[animation_node_index.unwrap()];
-    let duration = this_node.duration;
+    // There is no scenario between games or after the last scenario
+    let Some((scenario_index, this_scenario_animations)) = scenario_index
+        .0
+        .and_then(|index| Some((index, animation_config.get(index)?)))
+    else {
+        return;
+    };
 
-    commands.insert_resource(AnimationSectionTimer(Timer::from_seconds(
-        duration,
-        TimerMode::Once,
-    )));
+    running_animations.retain_mut(|running_animation| {
+        let animation_index = running_animation.animation_index;
+        let this_animation = &this_scenario_animations[animation_index];
+        let step = step_animation(this_animation, running_animation, time.delta());
+
+        // Move the targets of the animation
+        let new_transform = normalize_transform_to_canvas(step.transform);
+
+        for (mut transform, target_data) in &mut targets {
+            if this_animation.target.matches(target_data) {
+                *transform = new_transform;
+            }
+        }
+
+        // Fire the timeline events, then queue the timed actions and the
+        // actions of the nodes that ended
+        for event_index in step.events {
+            timeline_events.send(TimelineEventFired {
+                animation_index,
+                event: this_animation.timeline[event_index].event.clone(),
+            });
+        }
+
+        let timed_actions = step
+            .timed_actions
+            .into_iter()
+            .map(AnimationActionKind::Timed);
+        let node_actions = step
+            .ended_nodes
+            .into_iter()
+            .map(AnimationActionKind::NodeEnd);
+
+        for kind in timed_actions.chain(node_actions) {
+            actions.queue(AnimationAction {
+                scenario_index,
+                animation_index,
+                kind,
+            });
+        }
+
+        // Stop the animation after its last node
+        !step.finished
+    });
+
+    // Complete the trolley run once every animation without a cue has
+    // finished
+    if *animation_state.get() == AnimationState::Running
+        && running_animations.iter().all(|running_animation| {
+            this_scenario_animations[running_animation.animation_index]
+                .cue
+                .is_some()
+        })
+    {
+        next_animation_state.set(AnimationState::Complete);
+    }
 }
 
-/// Updates an animation every tick.
-fn animation_update(
+/// Handles the timeline events that concern animations and their targets. The
+/// camera is shaken by the game plugin, as there is no camera when headless.
+fn handle_timeline_events(
+    mut commands: Commands,
+    mut timeline_events: EventReader<TimelineEventFired>,
     animation_config: Res<AnimationConfigRes>,
     scenario_index: Res<State<ScenarioIndexState>>,
-    animation_index: Res<State<AnimationIndexState>>,
-    animation_node_index: Res<State<AnimationNodeIndexState>>,
-    mut next_animation_node_index_state: ResMut<NextState<AnimationNodeIndexState>>,
-    time: Res<Time>,
-    mut animation_section_timer: ResMut<AnimationSectionTimer>,
-    mut trolley_transform: Query<&mut Transform, With<TrolleyTexture>>,
+    image_assets: Res<ImageAssetMap>,
+    audio_assets: Res<AudioAssetMap>,
+    settings: Res<SettingsRes>,
+    mut flags: ResMut<AnimationFlagsRes>,
+    mut cues: EventWriter<AnimationCue>,
+    mut targets: Query<(&mut Handle<Image>, AnimationTargetData)>,
 ) {
-    if animation_section_timer.tick(time.delta()).just_finished() {
-        next_animation_node_index_state.set(AnimationNodeIndexState(Some(
-            animation_node_index.unwrap() + 1,
-        )));
+    for fired in timeline_events.read() {
+        match &fired.event {
+            TimelineEvent::PlaySound(sound) => {
+                commands.spawn((
+                    AudioBundle {
+                        source: audio_assets.get_by_name(sound),
+                        settings: PlaybackSettings {
+                            mode: PlaybackMode::Despawn,
+                            volume: settings.volume(AudioCategory::Sfx),
+                            ..default()
+                        },
+                    },
+                    AudioCategory::Sfx,
+                ));
+            }
+            TimelineEvent::SetTexture(texture) => {
+                let Some(this_animation) = scenario_index
+                    .0
+                    .and_then(|index| animation_config.get(index)?.get(fired.animation_index))
+                else {
+                    continue;
+                };
+
+                for (mut target_texture, target_data) in &mut targets {
+                    if this_animation.target.matches(target_data) {
+                        *target_texture = image_assets.get_by_name(texture);
+                    }
+                }
+            }
+            TimelineEvent::SetFlag(flag) => {
+                flags.insert(flag.clone());
+            }
+            TimelineEvent::Cue(name) => {
+                cues.send(AnimationCue(name.clone()));
+            }
+            TimelineEvent::ShakeCamera { .. } => {}
+        }
     }
+}
 
-    let this_scenario_animations = &animation_config[scenario_index.unwrap()];
-    let this_animation = &this_scenario_animations[animation_index.unwrap()];
-    let this_node = &this_animation.nodes[animation_node_index.unwrap()];
+/// Runs the queued actions of animations, with the index of the animation
+/// available as [`CurrentAnimationRes`].
+fn run_animation_actions(world: &mut World) {
+    let actions = std::mem::take(&mut world.resource_mut::<AnimationActionsRes>().queued);
 
-    let from_transform = if animation_node_index.unwrap() == 0 {
-        this_animation.start_transform
-    } else {
-        this_animation.nodes[animation_node_index.unwrap() - 1].transform
-    };
+    for action in actions {
+        let animation_index = action.animation_index;
+        world.insert_resource(CurrentAnimationRes(animation_index));
 
-    let progress = 1.0 - (animation_section_timer.remaining_secs() / this_node.duration);
+        if let Err(error) = world.try_run_schedule(action) {
+            warn!("failed to run animation action: {}", error);
+        }
 
-    let new_transform = (this_node.animation_fn)(from_transform, this_node.transform, progress);
-    *trolley_transform.single_mut() = normalize_transform_to_canvas(new_transform);
+        world.remove_resource::<CurrentAnimationRes>();
+    }
 }
 
 /// A wrapper around an animation function.
@@ -145,6 +543,80 @@ where
     }
 }
 
+impl From<Easing> for AnimationFn {
+    fn from(easing: Easing) -> Self {
+        (move |start_transform, end_transform, progress| {
+            linear_animation(start_transform, end_transform, easing.apply(progress))
+        })
+        .into()
+    }
+}
+
+impl From<SplinePath> for AnimationFn {
+    fn from(path: SplinePath) -> Self {
+        (move |start_transform, end_transform, progress| {
+            path.transform(start_transform, end_transform, progress)
+        })
+        .into()
+    }
+}
+
+impl AnimationFn {
+    /// Gets a built-in animation function by its name, as used in scenario
+    /// files. Besides the loop, these are the easings named in
+    /// [`Easing::from_name`].
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(linear_animation.into()),
+            "loop" => Some(loop_animation.into()),
+            _ => Easing::from_name(name).map(Into::into),
+        }
+    }
+}
+
+impl Default for AnimationFn {
+    fn default() -> Self {
+        linear_animation.into()
+    }
+}
+
+impl<'de> Deserialize<'de> for AnimationFn {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        /// Visits an animation function as written in a scenario file, either
+        /// the name of a built-in function or a path to follow.
+        struct AnimationFnVisitor;
+
+        impl<'de> de::Visitor<'de> for AnimationFnVisitor {
+            type Value = AnimationFn;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("the name of an animation function, or a path")
+            }
+
+            fn visit_str<E>(self, name: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                AnimationFn::builtin(name).ok_or_else(|| {
+                    de::Error::custom(format!("unknown animation function `{}`", name))
+                })
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                SplinePath::deserialize(de::value::MapAccessDeserializer::new(map)).map(Into::into)
+            }
+        }
+
+        deserializer.deserialize_any(AnimationFnVisitor)
+    }
+}
+
 /// A single node in an animation. Construct this using the builder pattern.
 pub struct AnimationNode {
     /// The duration in seconds of this section of the animation.
@@ -169,7 +641,9 @@ impl AnimationNode {
         }
     }
 
-    /// Configures the animation function for this node.
+    /// Configures the animation function for this node. An [`Easing`] can be
+    /// given to ease a linear transformation, or a [`SplinePath`] to follow a
+    /// curve.
     ///
     /// Animation functions must take the following parameters:
     ///  - The starting `Transform`.
@@ -193,15 +667,27 @@ impl AnimationNode {
 /// A trolley animation within a scenario. Construct this using the builder
 /// pattern.
 pub struct Animation {
-    /// An optional lever-state-related condition to decide whether to run the
+    /// An optional condition on the track taken to decide whether to run the
     /// animation.
-    lever_state_condition: Option<LeverState>,
+    track_condition: Option<TrackState>,
+    /// A condition on the combination of lever states to decide whether to
+    /// run the animation.
+    lever_states_condition: Vec<Option<LeverState>>,
+    /// The entity moved by the animation.
+    target: AnimationTarget,
+    /// The optional name of the cue that starts the animation.
+    cue: Option<String>,
     /// The animation start transformation.
     start_transform: Transform,
     /// An optional system to run at the start of the animation.
     start_action: Option<SystemConfigs>,
     /// The collection of animation nodes.
     nodes: Vec<AnimationNode>,
+    /// The events at points in the timeline of the animation.
+    timeline: Vec<TimedEvent>,
+    /// The systems to run at points in the timeline of the animation, with
+    /// their times.
+    timed_actions: Vec<(f32, SystemConfigs)>,
     /// The optional wounded texture.
     wounded_texture: Option<String>,
 }
@@ -210,10 +696,15 @@ impl Animation {
     /// Creates a new animation, given the starting transformation.
     pub fn new(start_transform: Transform) -> Self {
         Self {
-            lever_state_condition: None,
+            track_condition: None,
+            lever_states_condition: Vec::new(),
+            target: AnimationTarget::Trolley,
+            cue: None,
             start_transform,
             start_action: None,
             nodes: Vec::new(),
+            timeline: Vec::new(),
+            timed_actions: Vec::new(),
             wounded_texture: None,
         }
     }
@@ -224,10 +715,34 @@ impl Animation {
         self
     }
 
-    /// Configures the lever state condition that will decide whether to run the
-    /// animation.
-    pub const fn on_lever_state(mut self, lever_state_condition: LeverState) -> Self {
-        self.lever_state_condition = Some(lever_state_condition);
+    /// Configures the track that the trolley must take for the animation to
+    /// run.
+    pub const fn on_track(mut self, track_condition: TrackState) -> Self {
+        self.track_condition = Some(track_condition);
+        self
+    }
+
+    /// Configures the combination of lever states that will decide whether to
+    /// run the animation, one for each lever. `None` matches any state.
+    pub fn on_lever_states(
+        mut self,
+        lever_states_condition: impl IntoIterator<Item = Option<LeverState>>,
+    ) -> Self {
+        self.lever_states_condition = lever_states_condition.into_iter().collect();
+        self
+    }
+
+    /// Configures the entity moved by the animation. The trolley is moved by
+    /// default.
+    pub fn target(mut self, target: AnimationTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Configures the animation to start whenever the given [`AnimationCue`]
+    /// is sent, instead of when the trolley runs.
+    pub fn on_cue(mut self, cue: &str) -> Self {
+        self.cue = Some(cue.to_owned());
         self
     }
 
@@ -237,11 +752,48 @@ impl Animation {
         self
     }
 
+    /// Adds an event at the given time in seconds since the start of the
+    /// animation.
+    pub fn event_at(mut self, time: f32, event: TimelineEvent) -> Self {
+        self.timeline.push(TimedEvent { time, event });
+        self
+    }
+
+    /// Configures a system to run at the given time in seconds since the
+    /// start of the animation.
+    pub fn action_at<M>(mut self, time: f32, action: impl IntoSystemConfigs<M>) -> Self {
+        self.timed_actions.push((time, action.into_configs()));
+        self
+    }
+
     /// Configures the wounded texture.
     pub fn with_wounded_texture(mut self, wounded_texture: &str) -> Self {
         self.wounded_texture = Some(wounded_texture.to_owned());
         self
     }
+
+    /// Gets the configuration of this animation, without its systems.
+    pub fn config(&self) -> AnimationConfig {
+        AnimationConfig {
+            track_condition: self.track_condition,
+            lever_states_condition: self.lever_states_condition.clone(),
+            target: self.target.clone(),
+            cue: self.cue.clone(),
+            start_transform: self.start_transform,
+            nodes: self
+                .nodes
+                .iter()
+                .map(|node| AnimationNodeConfig {
+                    duration: node.duration,
+                    transform: node.transform,
+                    animation_fn: node.animation_fn.clone(),
+                })
+                .collect(),
+            timeline: self.timeline.clone(),
+            action_times: self.timed_actions.iter().map(|(time, _)| *time).collect(),
+            wounded_texture: self.wounded_texture.clone(),
+        }
+    }
 }
 
 /// A plugin to simplify the configuration of trolley animations.
@@ -273,102 +825,305 @@ impl Plugin for AnimationCollectionPlugin {
                 .map(|animations| {
                     animations
                         .into_iter()
-                        .map(|animation| {
-                            (
-                                AnimationConfig {
-                                    lever_state_condition: animation.lever_state_condition,
-                                    start_transform: animation.start_transform,
-                                    nodes: animation
-                                        .nodes
-                                        .iter()
-                                        .map(|node| AnimationNodeConfig {
-                                            duration: node.duration,
-                                            transform: node.transform,
-                                            animation_fn: node.animation_fn.clone(),
-                                        })
-                                        .collect(),
-                                    wounded_texture: animation.wounded_texture.clone(),
-                                },
-                                animation,
-                            )
-                        })
+                        .map(|animation| (animation.config(), animation))
                         .unzip::<_, _, Vec<_>, Vec<_>>()
                 })
                 .unzip::<_, _, Vec<_>, Vec<_>>();
 
-            // Insert animation state, animation index state, and animation node
-            // index state
+            // Insert animation state and the running animations
             app.insert_state(AnimationState::Waiting);
-            app.insert_state(AnimationIndexState(None));
-            app.insert_state(AnimationNodeIndexState(None));
+            app.init_resource::<RunningAnimationsRes>();
+            app.init_resource::<AnimationFlagsRes>();
+            app.add_event::<AnimationCue>();
+            app.add_event::<TimelineEventFired>();
 
             // Insert animation config resource
             app.insert_resource(AnimationConfigRes(animation_config));
 
-            // Add set and unset animation index systems, which will also clean
-            // up the animation section timer resource
-            app.add_systems(OnEnter(AnimationState::Running), set_animation_index_state);
-            app.add_systems(OnExit(AnimationState::Running), unset_animation_index_state);
+            // Add systems to start and stop animations, run them every tick,
+            // and run their actions. Start actions run as soon as their
+            // animation starts, before its first tick
+            app.add_systems(
+                OnEnter(AnimationState::Running),
+                (start_trolley_animations, run_animation_actions).chain(),
+            );
+            app.add_systems(OnEnter(AnimationState::Waiting), clear_animations);
+            app.add_systems(
+                Update,
+                (
+                    start_cued_animations,
+                    run_animation_actions,
+                    animations_update,
+                    handle_timeline_events,
+                    run_animation_actions,
+                )
+                    .chain(),
+            );
+
+            let mut registered_actions = HashSet::new();
 
             for (scenario_index, scenario) in animations.into_iter().enumerate() {
                 for (animation_index, animation) in scenario.into_iter().enumerate() {
-                    let num_nodes = animation.nodes.len();
-
                     // Add animation start action system
                     if let Some(start_action) = animation.start_action {
-                        app.add_systems(
-                            OnEnter(AnimationIndexState(Some(animation_index))),
-                            start_action.run_if(in_state(ScenarioIndexState(Some(scenario_index)))),
-                        );
+                        let action = AnimationAction {
+                            scenario_index,
+                            animation_index,
+                            kind: AnimationActionKind::Start,
+                        };
+                        registered_actions.insert(action.clone());
+                        app.add_systems(action, start_action);
                     }
 
+                    // Add animation timed action systems
+                    for (action_index, (_, action)) in
+                        animation.timed_actions.into_iter().enumerate()
+                    {
+                        let label = AnimationAction {
+                            scenario_index,
+                            animation_index,
+                            kind: AnimationActionKind::Timed(action_index),
+                        };
+                        registered_actions.insert(label.clone());
+                        app.add_systems(label, action);
+                    }
+
+                    // Add animation node action systems
                     for (node_index, node) in animation.nodes.into_iter().enumerate() {
-                        // Add system to set new animation section timer
-                        // resource with `OnEnter` transition
-                        app.add_systems(
-                            OnEnter(AnimationNodeIndexState(Some(node_index))),
-                            set_animation_section_timer.run_if(
-                                in_state(ScenarioIndexState(Some(scenario_index)))
-                                    .and_then(in_state(AnimationIndexState(Some(animation_index)))),
-                            ),
-                        );
-
-                        // Add system to move the trolley and set next animation
-                        // node index state when timer finishes
-                        app.add_systems(
-                            Update,
-                            animation_update.run_if(
-                                in_state(ScenarioIndexState(Some(scenario_index))).and_then(
-                                    in_state(AnimationIndexState(Some(animation_index))).and_then(
-                                        in_state(AnimationNodeIndexState(Some(node_index))),
-                                    ),
-                                ),
-                            ),
-                        );
-
-                        // Add animation node action system
                         if let Some(end_action) = node.end_action {
-                            app.add_systems(
-                                OnExit(AnimationNodeIndexState(Some(node_index))),
-                                end_action.run_if(
-                                    in_state(ScenarioIndexState(Some(scenario_index))).and_then(
-                                        in_state(AnimationIndexState(Some(animation_index))),
-                                    ),
-                                ),
-                            );
+                            let action = AnimationAction {
+                                scenario_index,
+                                animation_index,
+                                kind: AnimationActionKind::NodeEnd(node_index),
+                            };
+                            registered_actions.insert(action.clone());
+                            app.add_systems(action, end_action);
                         }
-
-                        // Add goto end system which sets the animation state
-                        app.add_systems(
-                            OnEnter(AnimationNodeIndexState(Some(num_nodes))),
-                            goto_end_animation.run_if(
-                                in_state(ScenarioIndexState(Some(scenario_index)))
-                                    .and_then(in_state(AnimationIndexState(Some(animation_index)))),
-                            ),
-                        );
                     }
                 }
             }
+
+            app.insert_resource(AnimationActionsRes {
+                registered: registered_actions,
+                queued: Vec::new(),
+            });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_animation_preserves_z() {
+        let start = Transform::from_xyz(0.0, 0.0, 15.0);
+        let end = Transform::from_xyz(100.0, 0.0, 15.0);
+
+        let transform = linear_animation(start, end, 0.3);
+
+        assert!(transform
+            .translation
+            .abs_diff_eq(Vec3::new(30.0, 0.0, 15.0), EPSILON));
+    }
+
+    /// Creates the configuration of a one-second animation of the trolley,
+    /// optionally started by a cue.
+    fn animation(cue: Option<&str>) -> AnimationConfig {
+        let animation = Animation::new(Transform::IDENTITY).node(AnimationNode::new(
+            1.0,
+            Transform::from_xyz(100.0, 0.0, 0.0),
+        ));
+
+        match cue {
+            Some(cue) => animation.on_cue(cue).config(),
+            None => animation.config(),
+        }
+    }
+
+    /// Starts the animations with the given cue, returning the indices of the
+    /// animations started and of those whose start action was queued.
+    fn start(
+        animations: &[AnimationConfig],
+        cue: Option<&str>,
+        registered: &[usize],
+    ) -> (Vec<usize>, Vec<usize>) {
+        let mut running_animations = RunningAnimationsRes::default();
+        let mut actions = AnimationActionsRes {
+            registered: registered
+                .iter()
+                .map(|&animation_index| AnimationAction {
+                    scenario_index: 0,
+                    animation_index,
+                    kind: AnimationActionKind::Start,
+                })
+                .collect(),
+            queued: Vec::new(),
+        };
+
+        start_matching_animations(
+            0,
+            animations,
+            cue,
+            TrackState(0),
+            &[LeverState(0)],
+            &mut running_animations,
+            &mut actions,
+        );
+
+        (
+            running_animations
+                .iter()
+                .map(|running_animation| running_animation.animation_index)
+                .collect(),
+            actions
+                .queued
+                .iter()
+                .map(|action| action.animation_index)
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn animations_without_a_cue_start_together() {
+        let animations = [animation(None), animation(Some("jump")), animation(None)];
+
+        assert_eq!(
+            start(&animations, None, &[0, 1, 2]),
+            (vec![0, 2], vec![0, 2])
+        );
+    }
+
+    #[test]
+    fn cued_animations_start_on_their_cue() {
+        let animations = [
+            animation(None),
+            animation(Some("jump")),
+            animation(Some("fall")),
+        ];
+
+        assert_eq!(start(&animations, Some("jump"), &[1]), (vec![1], vec![1]));
+        assert_eq!(start(&animations, Some("fall"), &[1]), (vec![2], vec![]));
+        assert_eq!(start(&animations, Some("wave"), &[1]), (vec![], vec![]));
+    }
+
+    #[test]
+    fn track_and_lever_state_conditions_choose_the_animations() {
+        let animations = [
+            Animation::new(Transform::IDENTITY)
+                .on_track(TrackState(1))
+                .config(),
+            Animation::new(Transform::IDENTITY)
+                .on_lever_states([Some(LeverState(0))])
+                .node(AnimationNode::new(1.0, Transform::IDENTITY))
+                .config(),
+        ];
+
+        assert_eq!(start(&animations, None, &[0, 1]), (vec![1], vec![1]));
+    }
+
+    #[test]
+    fn targets_match_their_entities() {
+        let lever = LeverPlayerTexture(1);
+        let hostages = HostagesTexture(0);
+        let tag = AnimationTag("hat".to_owned());
+
+        assert!(AnimationTarget::Trolley.matches((true, None, None, None)));
+        assert!(!AnimationTarget::Trolley.matches((false, Some(&lever), None, None)));
+        assert!(AnimationTarget::Lever(1).matches((false, Some(&lever), None, None)));
+        assert!(!AnimationTarget::Lever(0).matches((false, Some(&lever), None, None)));
+        assert!(AnimationTarget::Hostages(0).matches((false, None, Some(&hostages), None)));
+        assert!(!AnimationTarget::Hostages(1).matches((false, None, Some(&hostages), None)));
+        assert!(AnimationTarget::Tagged("hat".to_owned()).matches((false, None, None, Some(&tag))));
+        assert!(!AnimationTarget::Tagged("cart".to_owned()).matches((
+            true,
+            None,
+            None,
+            Some(&tag)
+        )));
+    }
+
+    /// Creates the configuration of a two-second animation of the trolley
+    /// over two nodes, raising a flag at the start, at the node boundary and
+    /// at the end.
+    fn timeline_animation() -> AnimationConfig {
+        Animation::new(Transform::IDENTITY)
+            .node(AnimationNode::new(
+                1.0,
+                Transform::from_xyz(100.0, 0.0, 0.0),
+            ))
+            .node(AnimationNode::new(
+                1.0,
+                Transform::from_xyz(200.0, 0.0, 0.0),
+            ))
+            .event_at(0.0, TimelineEvent::SetFlag("start".to_owned()))
+            .event_at(1.0, TimelineEvent::SetFlag("boundary".to_owned()))
+            .event_at(2.0, TimelineEvent::SetFlag("end".to_owned()))
+            .config()
+    }
+
+    /// Steps the animation from its start by each of the given deltas in
+    /// seconds, returning every step.
+    fn steps(animation: &AnimationConfig, deltas: &[f32]) -> Vec<AnimationStep> {
+        let mut running_animation = RunningAnimation {
+            animation_index: 0,
+            node_index: 0,
+            timer: Timer::from_seconds(animation.nodes[0].duration, TimerMode::Once),
+        };
+
+        deltas
+            .iter()
+            .map(|&delta| {
+                step_animation(
+                    animation,
+                    &mut running_animation,
+                    Duration::from_secs_f32(delta),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn timeline_events_fire_once_at_the_start_boundary_and_end() {
+        let animation = timeline_animation();
+        let steps = steps(&animation, &[0.5, 0.5, 0.5, 0.5]);
+
+        let events = steps
+            .iter()
+            .map(|step| step.events.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(events, [vec![0], vec![], vec![1], vec![2]]);
+
+        let ended_nodes = steps
+            .iter()
+            .map(|step| step.ended_nodes.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ended_nodes, [vec![], vec![0], vec![], vec![1]]);
+
+        let finished = steps.iter().map(|step| step.finished).collect::<Vec<_>>();
+        assert_eq!(finished, [false, false, false, true]);
+    }
+
+    #[test]
+    fn leftover_time_carries_over_into_the_next_node() {
+        let animation = timeline_animation();
+
+        let [step] = steps(&animation, &[1.5]).try_into().unwrap();
+        assert_eq!(step.events, [0, 1]);
+        assert_eq!(step.ended_nodes, [0]);
+        assert!(!step.finished);
+        assert!(step
+            .transform
+            .translation
+            .abs_diff_eq(Vec3::new(150.0, 0.0, 0.0), EPSILON));
+
+        let [step] = steps(&animation, &[3.0]).try_into().unwrap();
+        assert_eq!(step.events, [0, 1, 2]);
+        assert_eq!(step.ended_nodes, [0, 1]);
+        assert!(step.finished);
+        assert!(step
+            .transform
+            .translation
+            .abs_diff_eq(Vec3::new(200.0, 0.0, 0.0), EPSILON));
+    }
+}
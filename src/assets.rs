@@ -124,6 +124,7 @@ impl Plugin for EmbeddedAssetPlugin {
             "../assets/images/original-hostage-1.png",
             "../assets/images/original-hostage-5-wounded.png",
             "../assets/images/original-hostage-5.png",
+            "../assets/images/original-lever-moving.png",
             "../assets/images/original-lever-normal.png",
             "../assets/images/original-lever-switched.png",
             "../assets/images/original-tracks-normal.png",
@@ -0,0 +1,323 @@
+//! Aggregate statistics on the choices other players made.
+
+use crate::constants::*;
+use crate::resources::*;
+use crate::states::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// The choices made in a single run, as stored on one line of a choice
+/// statistics file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunChoices {
+    /// The index of the track taken, by scenario ID.
+    pub choices: BTreeMap<String, usize>,
+}
+
+/// The aggregate choices made across every recorded run.
+#[derive(Debug, Clone, Default)]
+pub struct ChoiceStats {
+    /// The number of runs in which each track was taken, by scenario ID and
+    /// track index.
+    tracks: HashMap<String, HashMap<usize, usize>>,
+}
+
+impl ChoiceStats {
+    /// Adds the choices made in a run.
+    pub fn add_run(&mut self, run: &RunChoices) {
+        for (scenario_id, track) in &run.choices {
+            *self
+                .tracks
+                .entry(scenario_id.clone())
+                .or_default()
+                .entry(*track)
+                .or_default() += 1;
+        }
+    }
+
+    /// Gets the percentage of runs in which the levers were pulled in a
+    /// scenario, sending the trolley away from the track it takes before any
+    /// lever is pulled, or `None` if the scenario has never been played.
+    pub fn pulled_percentage(&self, scenario_id: &str, default_track: TrackState) -> Option<f32> {
+        let tracks = self.tracks.get(scenario_id)?;
+        let total = tracks.values().sum::<usize>();
+        let pulled = tracks
+            .iter()
+            .filter(|(track, _)| **track != *default_track)
+            .map(|(_, count)| count)
+            .sum::<usize>();
+
+        (total > 0).then(|| pulled as f32 / total as f32 * 100.0)
+    }
+}
+
+/// Reads every choice statistics file in the directory. Lines that cannot be
+/// parsed are skipped.
+fn read_choice_stats(dir: &PathBuf) -> ChoiceStats {
+    let mut stats = ChoiceStats::default();
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return stats;
+    };
+
+    let paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "jsonl")
+        });
+
+    for path in paths {
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) => {
+                warn!("failed to read `{}`: {}", path.display(), error);
+                continue;
+            }
+        };
+
+        for (line_index, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<RunChoices>(line) {
+                Ok(run) => stats.add_run(&run),
+                Err(error) => warn!(
+                    "skipping line {} of `{}`: {}",
+                    line_index + 1,
+                    path.display(),
+                    error
+                ),
+            }
+        }
+    }
+
+    stats
+}
+
+/// Appends the choices made in a run to the local choice statistics file.
+fn append_run_choices(dir: &PathBuf, run: &RunChoices) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(LOCAL_CHOICE_STATS_FILE))?;
+    let line = serde_json::to_string(run)?;
+
+    writeln!(file, "{}", line)
+}
+
+/// Starts recording the choices made in a new run.
+fn start_run_choices(mut commands: Commands) {
+    commands.insert_resource(RunChoicesRes(RunChoices::default()));
+}
+
+/// Records the track taken in the active scenario.
+fn record_run_choice(
+    mut run_choices: ResMut<RunChoicesRes>,
+    scenarios_config: Res<ScenariosConfigRes>,
+    scenario_index_state: Res<State<ScenarioIndexState>>,
+    track: Res<State<TrackState>>,
+) {
+    let scenario_index = scenario_index_state.0.unwrap();
+    let scenario = scenarios_config.get_scenario(scenario_index);
+
+    if let Some(scenario_id) = &scenario.id {
+        run_choices
+            .choices
+            .insert(scenario_id.clone(), **track.get());
+    }
+}
+
+/// Stores the choices made in the completed run.
+fn finish_run_choices(
+    mut commands: Commands,
+    run_choices: Res<RunChoicesRes>,
+    mut choice_stats: ResMut<ChoiceStatsRes>,
+    dir: Option<Res<ChoiceStatsDirRes>>,
+) {
+    if let Some(dir) = dir {
+        if let Err(error) = append_run_choices(&dir, &run_choices) {
+            warn!("failed to record choice statistics: {}", error);
+        }
+    }
+
+    choice_stats.add_run(&run_choices);
+    commands.remove_resource::<RunChoicesRes>();
+}
+
+/// Shows how other players chose once the trolley animation completes.
+fn show_choice_stats_overlay(
+    mut commands: Commands,
+    scenarios_config: Res<ScenariosConfigRes>,
+    scenario_index_state: Res<State<ScenarioIndexState>>,
+    choice_stats: Res<ChoiceStatsRes>,
+    mut scenario_entities: ResMut<ScenarioEntitiesRes>,
+    locales: Res<LocalesRes>,
+) {
+    let scenario_index = scenario_index_state.0.unwrap();
+    let scenario = scenarios_config.get_scenario(scenario_index);
+
+    // There is nothing to compare if the lever cannot change the track
+    if scenario.tracks.len() < 2 {
+        return;
+    }
+
+    let (Some(scenario_id), Some(default_track)) = (&scenario.id, scenario.default_track()) else {
+        return;
+    };
+
+    let text = match choice_stats.pulled_percentage(scenario_id, default_track) {
+        Some(percentage) => locales.format(
+            "choice_stats.pulled",
+            &[("percentage", &format!("{:.0}", percentage))],
+        ),
+        None => locales.text("choice_stats.none").to_owned(),
+    };
+
+    // Spawn the overlay text
+    let overlay_entity = commands
+        .spawn(NodeBundle {
+            style: Style {
+                padding: UiRect::all(Val::Px(24.0)),
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                left: Val::Px(0.0),
+                width: Val::Vw(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 0.0, 10.0),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    text,
+                    TextStyle {
+                        font_size: 32.0,
+                        color: Color::BLACK,
+                        ..default()
+                    },
+                )
+                .with_text_justify(JustifyText::Center),
+            );
+        })
+        .id();
+    scenario_entities.push(overlay_entity);
+}
+
+/// A plugin that records the choices made in each run and shows how other
+/// players chose.
+///
+/// Every `.jsonl` file in the choice statistics directory is read when the
+/// game starts, one run per line, so a dataset exported by other players is
+/// imported by copying it into the directory. Runs played locally are
+/// appended to [`LOCAL_CHOICE_STATS_FILE`] in the same directory.
+pub struct ChoiceStatsPlugin {
+    /// The directory containing the choice statistics files, or `None` to
+    /// keep the choices of the runs played in memory only.
+    dir: Option<PathBuf>,
+}
+
+impl ChoiceStatsPlugin {
+    /// Creates a new choice statistics plugin, given the directory containing
+    /// the choice statistics files.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: Some(dir.into()),
+        }
+    }
+
+    /// Creates a new choice statistics plugin that only keeps the choices of
+    /// the runs played in memory, for where there is no filesystem.
+    pub const fn in_memory() -> Self {
+        Self { dir: None }
+    }
+}
+
+impl Plugin for ChoiceStatsPlugin {
+    fn build(&self, app: &mut App) {
+        match &self.dir {
+            Some(dir) => {
+                app.insert_resource(ChoiceStatsRes(read_choice_stats(dir)));
+                app.insert_resource(ChoiceStatsDirRes(dir.clone()));
+            }
+            None => {
+                app.insert_resource(ChoiceStatsRes(ChoiceStats::default()));
+            }
+        }
+
+        app.add_systems(OnEnter(GameState::Playing), start_run_choices);
+        app.add_systems(
+            OnEnter(AnimationState::Complete),
+            (record_run_choice, show_choice_stats_overlay).run_if(in_state(GameState::Playing)),
+        );
+        app.add_systems(OnEnter(GameState::EndScreen), finish_run_choices);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates the choices of a run that took the given track in every given
+    /// scenario.
+    fn run(choices: &[(&str, usize)]) -> RunChoices {
+        RunChoices {
+            choices: choices
+                .iter()
+                .map(|&(scenario_id, track)| (scenario_id.to_owned(), track))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn runs_are_counted_by_scenario_and_track() {
+        let mut stats = ChoiceStats::default();
+        stats.add_run(&run(&[("original", 0), ("age", 1)]));
+        stats.add_run(&run(&[("original", 0)]));
+
+        assert_eq!(stats.tracks["original"], HashMap::from([(0, 2)]));
+        assert_eq!(stats.tracks["age"], HashMap::from([(1, 1)]));
+    }
+
+    #[test]
+    fn pulled_percentage_is_measured_from_the_default_track() {
+        let mut stats = ChoiceStats::default();
+        stats.add_run(&run(&[("original", 0), ("loop", 1)]));
+        stats.add_run(&run(&[("original", 0), ("loop", 2)]));
+        stats.add_run(&run(&[("original", 0), ("loop", 1)]));
+        stats.add_run(&run(&[("original", 0), ("loop", 0)]));
+
+        assert_eq!(stats.pulled_percentage("age", TrackState(0)), None);
+        assert_eq!(
+            stats.pulled_percentage("original", TrackState(0)),
+            Some(0.0)
+        );
+        assert_eq!(stats.pulled_percentage("loop", TrackState(0)), Some(75.0));
+        assert_eq!(stats.pulled_percentage("loop", TrackState(1)), Some(50.0));
+    }
+
+    #[test]
+    fn unparseable_lines_and_other_files_are_skipped() {
+        let dir = std::env::temp_dir().join(format!("choice-stats-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("exported.jsonl"),
+            "{\"choices\":{\"original\":1}}\nnot json\n\n{\"choices\":{\"original\":0}}\n",
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "{\"choices\":{\"original\":1}}\n").unwrap();
+        let stats = read_choice_stats(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(stats.tracks["original"], HashMap::from([(0, 1), (1, 1)]));
+    }
+}
@@ -1,31 +1,53 @@
 //! Application components.
 
+use crate::frame_animation::FrameAnimation;
+use crate::input::InputAction;
+use crate::save::Setting;
 use bevy::prelude::*;
 
 /// The track texture component.
 #[derive(Component)]
 pub struct TrackTexture;
 
-/// The lever/player texture component.
-#[derive(Component)]
-pub struct LeverPlayerTexture;
-
-/// The hostages on track A texture component.
-#[derive(Component)]
-pub struct HostagesTrackATexture;
+/// The lever/player texture component, given the index of the lever.
+#[derive(Component, Deref)]
+pub struct LeverPlayerTexture(pub usize);
 
-/// The hostages on track B texture component.
-#[derive(Component)]
-pub struct HostagesTrackBTexture;
+/// The hostages texture component, given the index of the track the hostages
+/// are on.
+#[derive(Component, Deref)]
+pub struct HostagesTexture(pub usize);
 
 /// The trolley texture component.
 #[derive(Component)]
 pub struct TrolleyTexture;
 
+/// A tag naming an entity, so that animations can target it.
+#[derive(Component, Deref)]
+pub struct AnimationTag(pub String);
+
+/// The frame animator component, which steps a sprite through the frames of
+/// a sprite sheet.
+#[derive(Component)]
+pub struct FrameAnimator {
+    /// The animation being played.
+    pub animation: FrameAnimation,
+    /// The sprite sheet texture.
+    pub sheet: Handle<Image>,
+    /// The texture shown before the animation started.
+    pub previous_texture: Handle<Image>,
+    /// The time in seconds since the animation started.
+    pub elapsed: f32,
+}
+
 /// The scenario timer text component.
 #[derive(Component)]
 pub struct TimerText;
 
+/// The lock-in button component.
+#[derive(Component)]
+pub struct LockInButton;
+
 /// The Youtube tripod texture component.
 #[derive(Component)]
 pub struct TripodTexture;
@@ -50,14 +72,101 @@ pub struct NextPersonTexture;
 #[derive(Component)]
 pub struct OtherHostagesTexture;
 
+/// The canvas camera component, for the camera that shows the canvas and the
+/// UI.
+#[derive(Component)]
+pub struct CanvasCamera;
+
 /// The game music component.
 #[derive(Component)]
 pub struct GameMusic;
 
+/// The paused audio component, marking audio that was playing when the game
+/// was paused.
+#[derive(Component)]
+pub struct PausedAudio;
+
 /// The trolley approaching audio component.
 #[derive(Component)]
 pub struct TrolleyApproachingAudio;
 
+/// The audio category component, deciding which volume setting audio is
+/// played at.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum AudioCategory {
+    /// Music.
+    Music,
+    /// Sound effects.
+    Sfx,
+    /// Screams.
+    Scream,
+}
+
 /// The cool hat texture component.
 #[derive(Component)]
 pub struct CoolHatTexture;
+
+/// The button action component, deciding what happens when a button is
+/// pressed.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonAction {
+    /// Start playing the game.
+    Play,
+    /// Show the stats screen.
+    ShowStats,
+    /// Show the controls screen.
+    ShowControls,
+    /// Show the settings screen.
+    ShowSettings,
+    /// Go back to the menu screen.
+    BackToMenu,
+}
+
+/// The binding button component, deciding what happens to the bindings of an
+/// action when a button on the controls screen is pressed.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum BindingButton {
+    /// Bind the next input pressed to the action.
+    Rebind(InputAction),
+    /// Restore the default bindings of the action.
+    Reset(InputAction),
+}
+
+/// The bindings text component, given the action whose bindings are listed.
+#[derive(Component, Deref)]
+pub struct BindingsText(pub InputAction);
+
+/// The setting slider component, given the volume setting the slider changes.
+#[derive(Component, Deref)]
+pub struct SettingSlider(pub Setting);
+
+/// The setting slider fill component, given the volume setting whose level
+/// the fill shows.
+#[derive(Component, Deref)]
+pub struct SettingSliderFill(pub Setting);
+
+/// The setting toggle component, given the setting the button switches on or
+/// off.
+#[derive(Component, Deref)]
+pub struct SettingToggle(pub Setting);
+
+/// The setting text component, given the setting whose value is shown.
+#[derive(Component, Deref)]
+pub struct SettingText(pub Setting);
+
+/// The language button component, given the code of the locale the button
+/// chooses.
+#[derive(Component, Deref)]
+pub struct LanguageButton(pub String);
+
+/// The pause button component, deciding what happens when a button in the
+/// pause menu is pressed.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum PauseButton {
+    /// Resume the scenario.
+    Resume,
+    /// Restart the scenario from the beginning.
+    Restart,
+    /// Quit to the menu screen.
+    Quit,
+}
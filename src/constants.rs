@@ -2,12 +2,23 @@
 
 use bevy::prelude::*;
 
-/// The width of the screen in pixels.
+/// The width of the logical canvas, which is scaled to fit the window. Every
+/// position on the screen is given in canvas coordinates.
 pub const SCREEN_WIDTH: f32 = 800.0;
 
-/// The height of the screen in pixels.
+/// The height of the logical canvas, which is scaled to fit the window.
 pub const SCREEN_HEIGHT: f32 = 600.0;
 
+/// The color of the bars around the canvas when the window has another aspect
+/// ratio.
+pub const LETTERBOX_COLOR: Color = Color::BLACK;
+
+/// The render layer of the letterbox camera, which nothing is drawn on.
+pub const LETTERBOX_RENDER_LAYER: usize = 1;
+
+/// How many times a second the camera sways back and forth while shaking.
+pub const CAMERA_SHAKE_FREQUENCY: f32 = 12.0;
+
 /// The normal color of a button.
 pub const NORMAL_BUTTON_COLOR: Color = Color::srgb(0.0, 0.0, 0.0);
 
@@ -17,6 +28,16 @@ pub const HOVERED_BUTTON_COLOR: Color = Color::srgb(0.2, 0.2, 0.2);
 /// The color of a button when it is being pressed.
 pub const PRESSED_BUTTON_COLOR: Color = Color::srgb(0.3, 0.3, 0.3);
 
+/// The tint of the lever that keyboard and gamepad inputs act on, in
+/// scenarios with more than one lever.
+pub const FOCUSED_LEVER_COLOR: Color = Color::srgb(1.0, 0.8, 0.4);
+
+/// The color of the overlay behind the pause menu.
+pub const PAUSE_OVERLAY_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.75);
+
+/// The color of the empty part of a slider.
+pub const SLIDER_TRACK_COLOR: Color = Color::srgb(0.8, 0.8, 0.8);
+
 /// The amount of time in seconds to wait after the trolley has finished its
 /// animation.
 pub const POST_ANIMATION_WAIT_TIME: f32 = 3.0;
@@ -53,17 +74,71 @@ pub const STANDARD_HOSTAGES_POS_TRACK_A: Vec2 = Vec2::new(530.0, 325.0);
 /// The standard position of hostages on track B.
 pub const STANDARD_HOSTAGES_POS_TRACK_B: Vec2 = Vec2::new(550.0, 230.0);
 
-/// The transform for the double it next person's switch.
-pub const NEXT_PERSON_SWITCH_TRANSFORM: Transform = Transform::from_xyz(650.0, 380.0, 0.0);
+/// The standard position of the lever/player texture.
+pub const STANDARD_LEVER_POS: Vec2 = Vec2::new(400.0, 300.0);
 
-/// The transform for the player when they jump onto the track.
-pub const SELF_JUMP_TRANSFORM: Transform = Transform::from_xyz(285.0, 245.0, -10.0);
+/// The standard region in which a click switches the lever.
+pub const STANDARD_LEVER_HIT_RECT: Rect = Rect {
+    min: Vec2::new(346.0, 135.0),
+    max: Vec2::new(410.0, 202.0),
+};
 
 /// A bullet point character.
 pub const BULLET_POINT: char = '-';
 
-/// The audio volume.
+/// The audio volume, before the volume settings are applied.
 pub const GAME_VOLUME: f32 = 0.25;
 
 /// The transform for the cool asset.
 pub const COOL_HAT_TRANSFORM: Transform = Transform::from_xyz(383.0, 137.0, -5.0);
+
+/// The path of the save file.
+pub const SAVE_FILE: &str = "save.ron";
+
+/// The directory containing choice statistics. Every JSONL file in it is read,
+/// so an exported dataset can be imported by copying it into the directory.
+pub const CHOICE_STATS_DIR: &str = "choice-stats";
+
+/// The name of the file in [`CHOICE_STATS_DIR`] that runs played locally are
+/// appended to.
+pub const LOCAL_CHOICE_STATS_FILE: &str = "local.jsonl";
+
+/// The amount of time the clock advances by on each update of a headless
+/// simulation, in seconds.
+pub const SIMULATION_TIMESTEP: f32 = 1.0 / 60.0;
+
+/// The longest a headless simulation may run for before it is abandoned, in
+/// simulated seconds.
+pub const SIMULATION_TIME_LIMIT: f32 = 3600.0;
+
+/// The command line argument that runs a headless simulation instead of the
+/// game, optionally followed by the path of a simulation script.
+pub const HEADLESS_ARG: &str = "--headless";
+
+/// How many times faster the scenario timer runs once the player has locked in
+/// the lever positions.
+pub const LOCKED_IN_SPEED: f32 = 4.0;
+
+/// How close to the trolley arriving a decision must be made to count as a
+/// late decision, in seconds.
+pub const LATE_DECISION_TIME: f32 = 3.0;
+
+/// The keys that point the focused lever to each of its positions, in order.
+pub const LEVER_POSITION_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+/// The file the inputs of the latest run are recorded to.
+pub const REPLAY_FILE: &str = "replay.ron";
+
+/// The command line argument that plays back a replay file instead of taking
+/// input from the player, followed by the path of the replay file.
+pub const REPLAY_ARG: &str = "--replay";
@@ -0,0 +1,266 @@
+//! The controls screen, where the player remaps the inputs bound to each
+//! action.
+
+use crate::components::*;
+use crate::constants::*;
+use crate::input::*;
+use crate::locale::*;
+use crate::resources::*;
+use crate::states::*;
+use crate::util::*;
+use bevy::prelude::*;
+
+/// Gets the text listing the bindings of an action.
+fn bindings_text(
+    save_data: &SaveDataRes,
+    rebinding: Option<&RebindingRes>,
+    locales: &Locales,
+    action: InputAction,
+) -> String {
+    if rebinding.is_some_and(|rebinding| **rebinding == action) {
+        return locales.text("controls.waiting").to_owned();
+    }
+
+    let bindings = save_data.bindings.get(action);
+
+    if bindings.is_empty() {
+        return locales.text("controls.unbound").to_owned();
+    }
+
+    bindings
+        .iter()
+        .map(|binding| binding.name(locales))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Sets up the controls screen.
+pub fn setup_controls_screen(
+    mut commands: Commands,
+    save_data: Res<SaveDataRes>,
+    locales: Res<LocalesRes>,
+) {
+    // Spawn the controls screen text
+    let text_entity = commands
+        .spawn(NodeBundle {
+            background_color: Color::WHITE.into(),
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(75.0),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(16.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            locales.text("controls.title"),
+                            TextStyle {
+                                color: Color::BLACK,
+                                font_size: 32.0,
+                                ..default()
+                            },
+                        )
+                        .with_text_justify(JustifyText::Center),
+                    );
+
+                    // Show the bindings of each action, with buttons to
+                    // change them
+                    for action in InputAction::ALL {
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    width: Val::Percent(100.0),
+                                    align_items: AlignItems::Center,
+                                    column_gap: Val::Px(16.0),
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent
+                                    .spawn(NodeBundle {
+                                        style: Style {
+                                            flex_direction: FlexDirection::Column,
+                                            flex_grow: 1.0,
+                                            ..default()
+                                        },
+                                        ..default()
+                                    })
+                                    .with_children(|parent| {
+                                        parent.spawn(TextBundle::from_section(
+                                            locales.text(action.name_key()),
+                                            TextStyle {
+                                                color: Color::BLACK,
+                                                font_size: 24.0,
+                                                ..default()
+                                            },
+                                        ));
+
+                                        parent.spawn((
+                                            TextBundle::from_section(
+                                                bindings_text(&save_data, None, &locales, action),
+                                                TextStyle {
+                                                    color: Color::BLACK,
+                                                    font_size: 16.0,
+                                                    ..default()
+                                                },
+                                            ),
+                                            BindingsText(action),
+                                        ));
+                                    });
+
+                                spawn_button(
+                                    parent,
+                                    locales.text("controls.rebind"),
+                                    24.0,
+                                    BindingButton::Rebind(action),
+                                );
+                                spawn_button(
+                                    parent,
+                                    locales.text("controls.reset"),
+                                    24.0,
+                                    BindingButton::Reset(action),
+                                );
+                            });
+                    }
+
+                    spawn_button(
+                        parent,
+                        locales.text("menu.back"),
+                        32.0,
+                        ButtonAction::BackToMenu,
+                    );
+                });
+        })
+        .id();
+
+    // Save the entity
+    commands.insert_resource(ControlsScreenEntityRes(text_entity));
+}
+
+/// Updates the controls screen when a button is pressed or the player
+/// confirms. Buttons and confirmation are ignored while waiting for an input
+/// to bind, as that input is bound instead.
+pub fn update_controls_screen(
+    mut commands: Commands,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, Option<&BindingButton>),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut inputs: EventReader<PlayerInput>,
+    mut save_data: ResMut<SaveDataRes>,
+    storage: Res<SaveStorageRes>,
+    rebinding: Option<Res<RebindingRes>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, mut color, binding_button) in &mut interaction_query {
+        match *interaction {
+            Interaction::None => {
+                *color = NORMAL_BUTTON_COLOR.into();
+            }
+            Interaction::Hovered => {
+                *color = HOVERED_BUTTON_COLOR.into();
+            }
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON_COLOR.into();
+
+                if rebinding.is_some() {
+                    continue;
+                }
+
+                match binding_button {
+                    Some(BindingButton::Rebind(action)) => {
+                        commands.insert_resource(RebindingRes(*action));
+                    }
+                    Some(BindingButton::Reset(action)) => {
+                        save_data.bindings.reset(*action);
+
+                        if let Err(error) = storage.store(&save_data) {
+                            warn!("{}", error);
+                        }
+                    }
+                    None => next_game_state.set(GameState::InMenu),
+                }
+            }
+        }
+    }
+
+    let confirmed = inputs.read().any(|input| *input == PlayerInput::Confirm);
+
+    if confirmed && rebinding.is_none() {
+        next_game_state.set(GameState::InMenu);
+    }
+}
+
+/// Binds the next input pressed to the action waiting for one, or cancels if
+/// Escape is pressed.
+pub fn capture_rebinding(
+    mut commands: Commands,
+    raw_inputs: RawInputs,
+    rebinding: Res<RebindingRes>,
+    mut save_data: ResMut<SaveDataRes>,
+    storage: Res<SaveStorageRes>,
+) {
+    // Skip the frame the "Rebind" button was pressed in, so that the press
+    // isn't bound itself
+    if rebinding.is_added() {
+        return;
+    }
+
+    let Some(binding) = raw_inputs.any_just_pressed() else {
+        return;
+    };
+
+    if binding != InputBinding::Key(KeyCode::Escape) {
+        save_data.bindings.rebind(**rebinding, binding);
+
+        if let Err(error) = storage.store(&save_data) {
+            warn!("{}", error);
+        }
+    }
+
+    commands.remove_resource::<RebindingRes>();
+}
+
+/// Updates the bindings listed on the controls screen.
+pub fn update_bindings_text(
+    mut bindings_text_query: Query<(&mut Text, &BindingsText)>,
+    save_data: Res<SaveDataRes>,
+    rebinding: Option<Res<RebindingRes>>,
+    locales: Res<LocalesRes>,
+) {
+    for (mut text, action) in &mut bindings_text_query {
+        let value = bindings_text(&save_data, rebinding.as_deref(), &locales, **action);
+
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+/// Cleans up the controls screen.
+pub fn cleanup_controls_screen(mut commands: Commands, entity: Res<ControlsScreenEntityRes>) {
+    // Despawn the entity
+    let entity_commands = commands.entity(**entity);
+    entity_commands.despawn_recursive();
+
+    // Remove the entity and rebinding resources
+    commands.remove_resource::<ControlsScreenEntityRes>();
+    commands.remove_resource::<RebindingRes>();
+}
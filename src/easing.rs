@@ -0,0 +1,310 @@
+//! Easing curves, which shape the progress of an animation node.
+
+use std::f32::consts::TAU;
+
+/// How far back the back easings pull before moving forwards.
+const BACK_OVERSHOOT: f32 = 1.70158;
+
+/// How far back the back in-out easing pulls before moving forwards.
+const BACK_IN_OUT_OVERSHOOT: f32 = BACK_OVERSHOOT * 1.525;
+
+/// The number of Newton's method iterations used to solve a cubic Bézier
+/// curve before falling back to bisection.
+const BEZIER_NEWTON_ITERATIONS: usize = 8;
+
+/// The number of bisection iterations used to solve a cubic Bézier curve.
+const BEZIER_BISECTION_ITERATIONS: usize = 32;
+
+/// The precision to which a cubic Bézier curve is solved.
+const BEZIER_EPSILON: f32 = 1e-6;
+
+/// An easing curve, mapping the linear progress of an animation node to its
+/// eased progress. Both start at `0.0` and end at `1.0`, but the eased
+/// progress may overshoot in between.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    /// Constant speed.
+    Linear,
+    /// Quadratic acceleration from rest.
+    EaseInQuad,
+    /// Quadratic deceleration to rest.
+    EaseOutQuad,
+    /// Quadratic acceleration, then deceleration.
+    EaseInOutQuad,
+    /// Cubic acceleration from rest.
+    EaseInCubic,
+    /// Cubic deceleration to rest.
+    EaseOutCubic,
+    /// Cubic acceleration, then deceleration.
+    EaseInOutCubic,
+    /// Pulling back before accelerating.
+    EaseInBack,
+    /// Overshooting before settling.
+    EaseOutBack,
+    /// Pulling back, then overshooting before settling.
+    EaseInOutBack,
+    /// Bouncing with growing height before leaving.
+    EaseInBounce,
+    /// Bouncing with shrinking height before settling, like a dropped ball.
+    EaseOutBounce,
+    /// Bouncing in, then bouncing out.
+    EaseInOutBounce,
+    /// Oscillating with growing amplitude before leaving.
+    EaseInElastic,
+    /// Oscillating with shrinking amplitude before settling, like a spring.
+    EaseOutElastic,
+    /// Oscillating in, then oscillating out.
+    EaseInOutElastic,
+    /// A cubic Bézier curve from `(0, 0)` to `(1, 1)`, given the `x` and `y`
+    /// coordinates of its two control points, as in CSS.
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    /// Gets an easing by its name, as used in scenario files. Cubic Bézier
+    /// curves are written as `cubic_bezier(x1, y1, x2, y2)`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let easing = match name {
+            "linear" => Self::Linear,
+            "ease_in_quad" => Self::EaseInQuad,
+            "ease_out_quad" => Self::EaseOutQuad,
+            "ease_in_out_quad" => Self::EaseInOutQuad,
+            "ease_in_cubic" => Self::EaseInCubic,
+            "ease_out_cubic" => Self::EaseOutCubic,
+            "ease_in_out_cubic" => Self::EaseInOutCubic,
+            "ease_in_back" => Self::EaseInBack,
+            "ease_out_back" => Self::EaseOutBack,
+            "ease_in_out_back" => Self::EaseInOutBack,
+            "ease_in_bounce" => Self::EaseInBounce,
+            "ease_out_bounce" => Self::EaseOutBounce,
+            "ease_in_out_bounce" => Self::EaseInOutBounce,
+            "ease_in_elastic" => Self::EaseInElastic,
+            "ease_out_elastic" => Self::EaseOutElastic,
+            "ease_in_out_elastic" => Self::EaseInOutElastic,
+            _ => {
+                let points = name
+                    .strip_prefix("cubic_bezier(")?
+                    .strip_suffix(')')?
+                    .split(',')
+                    .map(|point| point.trim().parse::<f32>().ok())
+                    .collect::<Option<Vec<_>>>()?;
+
+                let [x1, y1, x2, y2] = points[..] else {
+                    return None;
+                };
+
+                Self::CubicBezier(x1, y1, x2, y2)
+            }
+        };
+
+        Some(easing)
+    }
+
+    /// Eases the linear progress of an animation node, from `0.0` to `1.0`.
+    pub fn apply(self, progress: f32) -> f32 {
+        let t = progress.clamp(0.0, 1.0);
+
+        match self {
+            Self::Linear => t,
+            Self::EaseInQuad => t * t,
+            Self::EaseOutQuad => 1.0 - (1.0 - t).powi(2),
+            Self::EaseInOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Self::EaseInCubic => t.powi(3),
+            Self::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOutCubic => {
+                if t < 0.5 {
+                    4.0 * t.powi(3)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Self::EaseInBack => (BACK_OVERSHOOT + 1.0) * t.powi(3) - BACK_OVERSHOOT * t * t,
+            Self::EaseOutBack => 1.0 - Self::EaseInBack.apply(1.0 - t),
+            Self::EaseInOutBack => {
+                let c = BACK_IN_OUT_OVERSHOOT;
+
+                if t < 0.5 {
+                    (2.0 * t).powi(2) * ((c + 1.0) * 2.0 * t - c) / 2.0
+                } else {
+                    ((2.0 * t - 2.0).powi(2) * ((c + 1.0) * (2.0 * t - 2.0) + c) + 2.0) / 2.0
+                }
+            }
+            Self::EaseInBounce => 1.0 - bounce_out(1.0 - t),
+            Self::EaseOutBounce => bounce_out(t),
+            Self::EaseInOutBounce => {
+                if t < 0.5 {
+                    (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0
+                } else {
+                    (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0
+                }
+            }
+            Self::EaseInElastic => 1.0 - Self::EaseOutElastic.apply(1.0 - t),
+            Self::EaseOutElastic => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2.0_f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (TAU / 3.0)).sin() + 1.0
+                }
+            }
+            Self::EaseInOutElastic => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    let oscillation = ((20.0 * t - 11.125) * (TAU / 4.5)).sin();
+
+                    if t < 0.5 {
+                        -(2.0_f32.powf(20.0 * t - 10.0) * oscillation) / 2.0
+                    } else {
+                        2.0_f32.powf(-20.0 * t + 10.0) * oscillation / 2.0 + 1.0
+                    }
+                }
+            }
+            Self::CubicBezier(x1, y1, x2, y2) => {
+                // Find the curve parameter at which the curve reaches the
+                // progress along the x axis, then take the y coordinate there
+                let s = solve_bezier(x1.clamp(0.0, 1.0), x2.clamp(0.0, 1.0), t);
+                bezier(y1, y2, s)
+            }
+        }
+    }
+}
+
+/// The ease-out bounce curve, shared by the bounce easings.
+fn bounce_out(t: f32) -> f32 {
+    /// The stiffness of the bounces.
+    const N: f32 = 7.5625;
+    /// The length of the drop and each bounce.
+    const D: f32 = 2.75;
+
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// Evaluates one coordinate of a cubic Bézier curve from `0.0` to `1.0`,
+/// given that coordinate of its two control points, at the curve parameter
+/// `s`.
+fn bezier(p1: f32, p2: f32, s: f32) -> f32 {
+    let inverse = 1.0 - s;
+    3.0 * inverse * inverse * s * p1 + 3.0 * inverse * s * s * p2 + s.powi(3)
+}
+
+/// Evaluates the derivative of [`bezier`] with respect to `s`.
+fn bezier_slope(p1: f32, p2: f32, s: f32) -> f32 {
+    let inverse = 1.0 - s;
+    3.0 * inverse * inverse * p1 + 6.0 * inverse * s * (p2 - p1) + 3.0 * s * s * (1.0 - p2)
+}
+
+/// Finds the curve parameter at which a cubic Bézier curve, whose control
+/// points have `x` coordinates from `0.0` to `1.0`, reaches `x`.
+fn solve_bezier(x1: f32, x2: f32, x: f32) -> f32 {
+    // Newton's method converges quickly where the curve is not flat
+    let mut s = x;
+
+    for _ in 0..BEZIER_NEWTON_ITERATIONS {
+        let error = bezier(x1, x2, s) - x;
+
+        if error.abs() < BEZIER_EPSILON {
+            return s;
+        }
+
+        let slope = bezier_slope(x1, x2, s);
+
+        if slope.abs() < BEZIER_EPSILON {
+            break;
+        }
+
+        s = (s - error / slope).clamp(0.0, 1.0);
+    }
+
+    // Otherwise, bisect, as the curve only increases along the x axis
+    let (mut low, mut high) = (0.0, 1.0);
+    s = x;
+
+    for _ in 0..BEZIER_BISECTION_ITERATIONS {
+        let error = bezier(x1, x2, s) - x;
+
+        if error.abs() < BEZIER_EPSILON {
+            break;
+        }
+
+        if error < 0.0 {
+            low = s;
+        } else {
+            high = s;
+        }
+
+        s = (low + high) / 2.0;
+    }
+
+    s
+}
+
+/// Remaps the progress around a vertical loop so that the speed follows
+/// gravity, slowing down towards the top of the loop and speeding up towards
+/// the bottom. `slowdown` is between `0.0`, for constant speed, and `1.0`, for
+/// stopping at the top.
+pub fn loop_progress(progress: f32, slowdown: f32) -> f32 {
+    progress + slowdown * (TAU * progress).sin() / TAU
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::EPSILON;
+
+    #[test]
+    fn ease_out_back_overshoots_past_end() {
+        assert!(Easing::EaseOutBack.apply(0.5) > 1.0);
+        assert!((Easing::EaseOutBack.apply(1.0) - 1.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn easings_start_at_zero_and_end_at_one() {
+        let names = [
+            "linear",
+            "ease_in_quad",
+            "ease_out_quad",
+            "ease_in_out_quad",
+            "ease_in_cubic",
+            "ease_out_cubic",
+            "ease_in_out_cubic",
+            "ease_in_back",
+            "ease_out_back",
+            "ease_in_out_back",
+            "ease_in_bounce",
+            "ease_out_bounce",
+            "ease_in_out_bounce",
+            "ease_in_elastic",
+            "ease_out_elastic",
+            "ease_in_out_elastic",
+            "cubic_bezier(0.25, 0.1, 0.25, 1.0)",
+        ];
+
+        for name in names {
+            let easing = Easing::from_name(name).unwrap();
+
+            assert!(easing.apply(0.0).abs() < EPSILON, "{} starts at 0", name);
+            assert!(
+                (easing.apply(1.0) - 1.0).abs() < EPSILON,
+                "{} ends at 1",
+                name
+            );
+        }
+    }
+}
@@ -2,94 +2,87 @@
 
 use crate::components::*;
 use crate::constants::*;
+use crate::input::*;
 use crate::resources::*;
 use crate::states::*;
 use bevy::audio::PlaybackMode;
-use bevy::audio::Volume;
 use bevy::prelude::*;
 
 /// Sets up the end screen.
 pub fn setup_end_screen(
     mut commands: Commands,
     summary: Res<GameSummary>,
+    achievements: Res<AchievementsRes>,
+    image_assets: Res<ImageAssetMap>,
     music: Query<&AudioSink, With<GameMusic>>,
     music_assets: Res<MusicAssetMap>,
+    settings: Res<SettingsRes>,
+    locales: Res<LocalesRes>,
 ) {
     let mut summary_text_sections = Vec::new();
 
-    summary_text_sections.push(format!("Killed {} people", summary.people_killed));
-    summary_text_sections.push(format!("Saved {} people", summary.people_saved));
+    // Show the number of people killed and saved, and the number of any other
+    // entities if there were some
+    for kind in EntityKind::ALL {
+        let killed = summary.killed(kind);
+        let saved = summary.saved(kind);
 
-    if summary.killed_hitler {
-        summary_text_sections.push("Killed Hitler".to_owned());
-    }
-
-    if summary.got_cool_hat {
-        summary_text_sections.push("Got a cool hat".to_owned());
-    }
-
-    if summary.caused_preventable_tragedy {
-        summary_text_sections.push("Caused an entirely preventable tragedy".to_owned());
-    }
-
-    if summary.enforced_darwinism {
-        summary_text_sections.push("Enforced Darwinism".to_owned());
-    }
-
-    if summary.did_sick_loop {
-        summary_text_sections.push("Did a sick loop-da-loop".to_owned());
-    }
-
-    if summary.lobsters_killed > 0 {
-        summary_text_sections.push(format!("Killed {} lobsters", summary.lobsters_killed));
-    }
-
-    if summary.lobsters_saved > 0 {
-        summary_text_sections.push(format!("Saved {} lobsters", summary.lobsters_saved));
-    }
-
-    if summary.returned_shopping_cart {
-        summary_text_sections.push("Returned a shopping cart".to_owned());
-    }
-
-    if summary.doubled_it {
-        summary_text_sections.push("Doubled it and gave it to the next person".to_owned());
-    }
-
-    if summary.watched_thomas_kill_people {
-        summary_text_sections
-            .push("Watched Thomas the Tank Engine run multiple people over".to_owned());
-    }
+        if kind == EntityKind::People || killed > 0 {
+            let key = format!("summary.killed.{}", kind.name());
+            summary_text_sections.push((locales.plural(&key, killed, &[]), None));
+        }
 
-    if summary.did_viral_prank {
-        summary_text_sections.push("Contributed to a viral YouTube prank".to_owned());
+        if kind == EntityKind::People || saved > 0 {
+            let key = format!("summary.saved.{}", kind.name());
+            summary_text_sections.push((locales.plural(&key, saved, &[]), None));
+        }
     }
 
-    if summary.killed_self {
-        summary_text_sections.push("Killed yourself".to_owned());
+    // Show the unlocked achievements
+    for achievement in achievements.unlocked(&summary) {
+        let icon = achievement
+            .icon
+            .as_ref()
+            .map(|icon| image_assets.get_by_name(icon));
+        summary_text_sections.push((locales.text(&achievement.text_key).to_owned(), icon));
     }
 
-    if summary.solved_philosophy {
-        summary_text_sections.push("Solved philosophy".to_owned());
+    // Show the decision the player agonized over most
+    if let Some(decision) = summary.most_agonized_decision() {
+        let key = if decision.late {
+            "end.agonized_late"
+        } else {
+            "end.agonized"
+        };
+        let text = locales.plural(
+            key,
+            decision.switches,
+            &[("scenario", &locales.text(&decision.title_key))],
+        );
+
+        summary_text_sections.push((text, None));
     }
 
     summary_text_sections
         .iter_mut()
-        .for_each(|line| *line = format!("{} {}", BULLET_POINT, line));
+        .for_each(|(line, _)| *line = format!("{} {}", BULLET_POINT, line));
 
     // Pause the game music
     music.single().pause();
 
     // Play the win music
     let win_music = music_assets.get_by_name("win");
-    commands.spawn(AudioBundle {
-        source: win_music,
-        settings: PlaybackSettings {
-            mode: PlaybackMode::Despawn,
-            volume: Volume::new(GAME_VOLUME),
-            ..default()
+    commands.spawn((
+        AudioBundle {
+            source: win_music,
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Despawn,
+                volume: settings.volume(AudioCategory::Music),
+                ..default()
+            },
         },
-    });
+        AudioCategory::Music,
+    ));
 
     // Spawn the end screen text
     let text_entity = commands
@@ -120,7 +113,7 @@ pub fn setup_end_screen(
                 .with_children(|parent| {
                     parent.spawn(
                         TextBundle::from_section(
-                            "Congratulations! You:",
+                            locales.text("end.title"),
                             TextStyle {
                                 color: Color::BLACK,
                                 font_size: 32.0,
@@ -139,15 +132,38 @@ pub fn setup_end_screen(
                             ..default()
                         })
                         .with_children(|parent| {
-                            for summary_line in summary_text_sections {
-                                parent.spawn(TextBundle::from_section(
-                                    summary_line,
-                                    TextStyle {
-                                        color: Color::BLACK,
-                                        font_size: 24.0,
+                            for (summary_line, icon) in summary_text_sections {
+                                parent
+                                    .spawn(NodeBundle {
+                                        style: Style {
+                                            align_items: AlignItems::Center,
+                                            column_gap: Val::Px(8.0),
+                                            ..default()
+                                        },
                                         ..default()
-                                    },
-                                ));
+                                    })
+                                    .with_children(|parent| {
+                                        parent.spawn(TextBundle::from_section(
+                                            summary_line,
+                                            TextStyle {
+                                                color: Color::BLACK,
+                                                font_size: 24.0,
+                                                ..default()
+                                            },
+                                        ));
+
+                                        if let Some(icon) = icon {
+                                            parent.spawn(ImageBundle {
+                                                image: UiImage::new(icon),
+                                                style: Style {
+                                                    width: Val::Px(24.0),
+                                                    height: Val::Px(24.0),
+                                                    ..default()
+                                                },
+                                                ..default()
+                                            });
+                                        }
+                                    });
                             }
                         });
 
@@ -164,7 +180,7 @@ pub fn setup_end_screen(
                         })
                         .with_children(|parent| {
                             parent.spawn(TextBundle::from_section(
-                                "Back to menu",
+                                locales.text("menu.back"),
                                 TextStyle {
                                     font_size: 32.0,
                                     color: Color::srgb(1.0, 1.0, 1.0),
@@ -180,12 +196,14 @@ pub fn setup_end_screen(
     commands.insert_resource(EndScreenEntityRes(text_entity));
 }
 
-/// Updates the end screen when the "Play" button is pressed.
+/// Updates the end screen when the "Play" button is pressed or the player
+/// confirms.
 pub fn update_end_screen(
     mut interaction_query: Query<
         (&Interaction, &mut BackgroundColor),
         (Changed<Interaction>, With<Button>),
     >,
+    mut inputs: EventReader<PlayerInput>,
     mut next_game_state: ResMut<NextState<GameState>>,
 ) {
     for (interaction, mut color) in &mut interaction_query {
@@ -202,6 +220,10 @@ pub fn update_end_screen(
             }
         }
     }
+
+    if inputs.read().any(|input| *input == PlayerInput::Confirm) {
+        next_game_state.set(GameState::InMenu);
+    }
 }
 
 /// Cleans up the end screen.
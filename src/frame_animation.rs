@@ -0,0 +1,460 @@
+//! Sprite-sheet frame animation, which steps a sprite through the frames of a
+//! texture atlas.
+
+use crate::animation::*;
+use crate::components::*;
+use crate::resources::*;
+use crate::scenario::*;
+use crate::states::*;
+use crate::util::*;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// What a frame animation does once it has shown its last frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum FrameAnimationMode {
+    /// Starts again from the first frame.
+    #[default]
+    Loop,
+    /// Stays on the last frame.
+    Once,
+    /// Restores the texture shown before the animation started, such as the
+    /// lever texture after the frames in between its positions.
+    Restore,
+}
+
+/// A single frame of a frame animation.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Frame {
+    /// The index of the frame in the sprite sheet, counting left to right and
+    /// then top to bottom.
+    pub index: usize,
+    /// How long the frame is shown for, in seconds.
+    pub duration: f32,
+}
+
+/// An animation through the frames of a sprite sheet, whose frames are laid
+/// out in a grid of equal cells.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FrameAnimation {
+    /// The name of the sprite sheet texture.
+    pub texture: String,
+    /// The size of each frame in pixels.
+    pub frame_size: UVec2,
+    /// The number of columns of frames in the sprite sheet.
+    pub columns: u32,
+    /// The number of rows of frames in the sprite sheet.
+    pub rows: u32,
+    /// The frames to show, in order.
+    pub frames: Vec<Frame>,
+    /// What happens after the last frame.
+    #[serde(default)]
+    pub mode: FrameAnimationMode,
+}
+
+impl FrameAnimation {
+    /// Gets the position in the list of frames of the frame shown `elapsed`
+    /// seconds after the animation started, or [`None`] once an animation that
+    /// restores its texture has finished.
+    pub fn frame_at(&self, elapsed: f32) -> Option<usize> {
+        let total_duration = self.frames.iter().map(|frame| frame.duration).sum::<f32>();
+        let last_frame = self.frames.len().checked_sub(1)?;
+
+        let mut time = match self.mode {
+            FrameAnimationMode::Loop if total_duration > 0.0 => elapsed % total_duration,
+            FrameAnimationMode::Restore if elapsed >= total_duration => return None,
+            _ => elapsed,
+        };
+
+        for (frame_index, frame) in self.frames.iter().enumerate() {
+            if time < frame.duration {
+                return Some(frame_index);
+            }
+
+            time -= frame.duration;
+        }
+
+        Some(last_frame)
+    }
+}
+
+/// When a frame animation in a scenario starts.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum FrameAnimationTrigger {
+    /// When the scenario starts.
+    ScenarioStart,
+    /// When the given number of seconds remain on the scenario timer.
+    TimeRemaining(f32),
+    /// Whenever a lever is switched. An animation of a lever only starts when
+    /// that lever is switched.
+    LeverSwitched,
+    /// When the trolley starts running.
+    TrolleyRuns,
+    /// Whenever an [`AnimationCue`] with the given name is sent, such as from
+    /// the timeline of an animation.
+    Cue(String),
+}
+
+/// A frame animation in a scenario, with the entities it plays on and when it
+/// starts.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FrameAnimationConfig {
+    /// The entities the animation plays on.
+    #[serde(default)]
+    pub target: AnimationTarget,
+    /// When the animation starts.
+    pub trigger: FrameAnimationTrigger,
+    /// The animation.
+    pub animation: FrameAnimation,
+}
+
+/// Checks whether switching the given levers starts a frame animation on the
+/// given target. Levers only animate when they are switched themselves.
+fn lever_switch_triggers(target: &AnimationTarget, switched_levers: &[usize]) -> bool {
+    match target {
+        AnimationTarget::Lever(lever_index) => switched_levers.contains(lever_index),
+        _ => !switched_levers.is_empty(),
+    }
+}
+
+/// Starts the frame animations of the current scenario whose trigger has just
+/// happened. Starting an animation on an entity that is already playing one
+/// replaces it.
+fn trigger_frame_animations(
+    mut commands: Commands,
+    scenarios_config: Option<Res<ScenariosConfigRes>>,
+    scenario_index: Res<State<ScenarioIndexState>>,
+    timer: Option<Res<ScenarioTimer>>,
+    animation_state: Option<Res<State<AnimationState>>>,
+    mut lever_switched: EventReader<LeverSwitched>,
+    mut cues: EventReader<AnimationCue>,
+    image_assets: Res<ImageAssetMap>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut layout_cache: Local<HashMap<(UVec2, u32, u32), Handle<TextureAtlasLayout>>>,
+    mut previous_time_remaining: Local<f32>,
+    targets: Query<(
+        Entity,
+        &Handle<Image>,
+        Option<&FrameAnimator>,
+        AnimationTargetData,
+    )>,
+) {
+    // Step 1: Collect the events that happened since the last tick
+    let switched_levers = lever_switched
+        .read()
+        .map(|switched| switched.0)
+        .collect::<Vec<_>>();
+    let cues = cues.read().map(|cue| cue.0.clone()).collect::<Vec<_>>();
+
+    let (Some(scenarios_config), Some(timer)) = (scenarios_config, timer) else {
+        return;
+    };
+    let Some(scenario) = scenario_index
+        .0
+        .and_then(|index| scenarios_config.get(index))
+    else {
+        return;
+    };
+
+    // Step 2: Work out how far the timer has advanced, starting from the full
+    // duration when the scenario has just started
+    let scenario_started = timer.is_added();
+    let current_time_remaining = timer.remaining_secs();
+
+    if scenario_started {
+        *previous_time_remaining = timer.duration().as_secs_f32();
+    }
+
+    let trolley_runs = animation_state.is_some_and(|animation_state| {
+        animation_state.is_changed() && *animation_state.get() == AnimationState::Running
+    });
+
+    // Step 3: Start the animations whose trigger happened on every entity they
+    // target
+    for frame_animation in &scenario.frame_animations {
+        let triggered = match &frame_animation.trigger {
+            FrameAnimationTrigger::ScenarioStart => scenario_started,
+            FrameAnimationTrigger::TimeRemaining(time_remaining) => time_remaining_reached(
+                *previous_time_remaining,
+                current_time_remaining,
+                *time_remaining,
+            ),
+            FrameAnimationTrigger::LeverSwitched => {
+                lever_switch_triggers(&frame_animation.target, &switched_levers)
+            }
+            FrameAnimationTrigger::TrolleyRuns => trolley_runs,
+            FrameAnimationTrigger::Cue(name) => cues.contains(name),
+        };
+
+        if !triggered {
+            continue;
+        }
+
+        let animation = &frame_animation.animation;
+        let sheet = image_assets.get_by_name(&animation.texture);
+        // Sprite sheets with the same grid share a layout, rather than adding
+        // a new one every time an animation starts
+        let layout = layout_cache
+            .entry((animation.frame_size, animation.columns, animation.rows))
+            .or_insert_with(|| {
+                layouts.add(TextureAtlasLayout::from_grid(
+                    animation.frame_size,
+                    animation.columns,
+                    animation.rows,
+                    None,
+                    None,
+                ))
+            });
+
+        for (entity, texture, animator, target_data) in &targets {
+            if !frame_animation.target.matches(target_data) {
+                continue;
+            }
+
+            // Keep the texture from before any animation that is being
+            // replaced, rather than the sprite sheet of that animation
+            let previous_texture = match animator {
+                Some(animator) if *texture == animator.sheet => animator.previous_texture.clone(),
+                _ => texture.clone(),
+            };
+
+            commands.entity(entity).insert((
+                sheet.clone(),
+                TextureAtlas {
+                    layout: layout.clone(),
+                    index: animation.frames.first().map_or(0, |frame| frame.index),
+                },
+                FrameAnimator {
+                    animation: animation.clone(),
+                    sheet: sheet.clone(),
+                    previous_texture,
+                    elapsed: 0.0,
+                },
+            ));
+        }
+    }
+
+    *previous_time_remaining = current_time_remaining;
+}
+
+/// Stops the frame animations of entities whose texture has been swapped for
+/// something other than the sprite sheet, such as when the trolley turns.
+fn stop_replaced_frame_animations(
+    mut commands: Commands,
+    animators: Query<(Entity, &Handle<Image>, &FrameAnimator), Changed<Handle<Image>>>,
+) {
+    for (entity, texture, animator) in &animators {
+        if *texture != animator.sheet {
+            commands
+                .entity(entity)
+                .remove::<(TextureAtlas, FrameAnimator)>();
+        }
+    }
+}
+
+/// Steps every frame animation through its frames every tick.
+fn animate_frames(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut animators: Query<(
+        Entity,
+        &mut FrameAnimator,
+        &mut TextureAtlas,
+        &mut Handle<Image>,
+    )>,
+) {
+    for (entity, mut animator, mut atlas, mut texture) in &mut animators {
+        animator.elapsed += time.delta_seconds();
+
+        match animator.animation.frame_at(animator.elapsed) {
+            Some(frame_index) => {
+                let index = animator.animation.frames[frame_index].index;
+
+                if atlas.index != index {
+                    atlas.index = index;
+                }
+            }
+            None => {
+                *texture = animator.previous_texture.clone();
+                commands
+                    .entity(entity)
+                    .remove::<(TextureAtlas, FrameAnimator)>();
+            }
+        }
+    }
+}
+
+/// The plugin which plays the frame animations of scenarios. This runs after
+/// the scenario and animation systems, so the textures they set are in place
+/// before the sprite sheets replace them.
+pub struct FrameAnimationPlugin;
+
+impl Plugin for FrameAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        // The texture atlas layouts are normally added by the sprite plugin,
+        // which is not used when there is no window
+        if !app
+            .world()
+            .contains_resource::<Assets<TextureAtlasLayout>>()
+        {
+            app.init_asset::<TextureAtlasLayout>();
+        }
+
+        app.add_systems(
+            PostUpdate,
+            (
+                trigger_frame_animations,
+                stop_replaced_frame_animations,
+                animate_frames,
+            )
+                .chain(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario_file::ron_options;
+    use bevy::state::app::StatesPlugin;
+    use std::time::Duration;
+
+    /// Creates an animation with frames of the given durations.
+    fn animation(durations: &[f32], mode: FrameAnimationMode) -> FrameAnimation {
+        FrameAnimation {
+            texture: "sheet".to_owned(),
+            frame_size: UVec2::new(10, 10),
+            columns: 4,
+            rows: 1,
+            frames: durations
+                .iter()
+                .enumerate()
+                .map(|(index, &duration)| Frame { index, duration })
+                .collect(),
+            mode,
+        }
+    }
+
+    #[test]
+    fn frames_follow_their_durations() {
+        let animation = animation(&[0.1, 0.3, 0.1], FrameAnimationMode::Once);
+
+        assert_eq!(animation.frame_at(0.0), Some(0));
+        assert_eq!(animation.frame_at(0.05), Some(0));
+        assert_eq!(animation.frame_at(0.15), Some(1));
+        assert_eq!(animation.frame_at(0.45), Some(2));
+    }
+
+    #[test]
+    fn modes_decide_what_follows_the_last_frame() {
+        let looping = animation(&[0.1, 0.3], FrameAnimationMode::Loop);
+        let once = animation(&[0.1, 0.3], FrameAnimationMode::Once);
+        let restore = animation(&[0.1, 0.3], FrameAnimationMode::Restore);
+
+        assert_eq!(looping.frame_at(0.45), Some(0));
+        assert_eq!(looping.frame_at(0.65), Some(1));
+        assert_eq!(once.frame_at(10.0), Some(1));
+        assert_eq!(restore.frame_at(0.35), Some(1));
+        assert_eq!(restore.frame_at(0.45), None);
+    }
+
+    #[test]
+    fn lever_switches_only_animate_the_switched_lever() {
+        assert!(lever_switch_triggers(&AnimationTarget::Lever(1), &[1]));
+        assert!(!lever_switch_triggers(&AnimationTarget::Lever(0), &[1]));
+        assert!(lever_switch_triggers(&AnimationTarget::Trolley, &[1]));
+        assert!(!lever_switch_triggers(&AnimationTarget::Trolley, &[]));
+    }
+
+    /// A scenario with two levers, whose first lever shows two frames of a
+    /// sprite sheet whenever it is switched.
+    const SCENARIO: &str = r#"(
+        text_key: "scenario.text",
+        title_key: "scenario.title",
+        duration: 10.0,
+        levers: [
+            (textures: ["lever-normal", "lever-switched"]),
+            (textures: ["lever-normal", "lever-switched"]),
+        ],
+        tracks: [(tracks_texture: "tracks-normal"), (tracks_texture: "tracks-switched")],
+        frame_animations: [
+            (
+                target: Lever(0),
+                trigger: LeverSwitched,
+                animation: (
+                    texture: "sheet",
+                    frame_size: (10, 10),
+                    columns: 2,
+                    rows: 1,
+                    frames: [(index: 0, duration: 0.05), (index: 1, duration: 0.05)],
+                    mode: Restore,
+                ),
+            ),
+        ],
+    )"#;
+
+    #[test]
+    fn switched_levers_play_their_frames_and_restore_their_texture() {
+        let sheet = Handle::<Image>::weak_from_u128(1);
+        let lever_texture = Handle::<Image>::weak_from_u128(2);
+
+        let mut app = App::new();
+        app.add_plugins(StatesPlugin)
+            .init_resource::<Time>()
+            .init_resource::<Assets<TextureAtlasLayout>>()
+            .add_event::<LeverSwitched>()
+            .add_event::<AnimationCue>()
+            .insert_state(ScenarioIndexState(Some(0)))
+            .insert_resource(ScenariosConfigRes(vec![ron_options()
+                .from_str(SCENARIO)
+                .unwrap()]))
+            .insert_resource(ScenarioTimer(Timer::from_seconds(10.0, TimerMode::Once)))
+            .insert_resource(ImageAssetMap(HashMap::from([(
+                "assets/images/sheet.png".to_owned(),
+                sheet.clone(),
+            )])))
+            .add_plugins(FrameAnimationPlugin);
+
+        let first_lever = app
+            .world_mut()
+            .spawn((lever_texture.clone(), LeverPlayerTexture(0)))
+            .id();
+        let second_lever = app
+            .world_mut()
+            .spawn((lever_texture.clone(), LeverPlayerTexture(1)))
+            .id();
+
+        // Step 1: Switch the first lever, which starts its animation only
+        app.world_mut().send_event(LeverSwitched(0));
+        app.update();
+
+        let world = app.world();
+        assert_eq!(world.get::<Handle<Image>>(first_lever), Some(&sheet));
+        assert_eq!(world.get::<TextureAtlas>(first_lever).unwrap().index, 0);
+        assert!(world.get::<FrameAnimator>(first_lever).is_some());
+        assert!(world.get::<FrameAnimator>(second_lever).is_none());
+
+        // Step 2: Move on to the second frame
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(0.07));
+        app.update();
+
+        assert_eq!(
+            app.world().get::<TextureAtlas>(first_lever).unwrap().index,
+            1
+        );
+
+        // Step 3: Finish the animation, which restores the lever texture
+        app.update();
+
+        let world = app.world();
+        assert_eq!(
+            world.get::<Handle<Image>>(first_lever),
+            Some(&lever_texture)
+        );
+        assert!(world.get::<TextureAtlas>(first_lever).is_none());
+        assert!(world.get::<FrameAnimator>(first_lever).is_none());
+    }
+}
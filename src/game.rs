@@ -2,19 +2,35 @@
 
 use crate::animation::*;
 use crate::assets::*;
+use crate::choice_stats::*;
 use crate::components::*;
 use crate::constants::*;
+use crate::controls_screen::*;
 use crate::end_screen::*;
+use crate::frame_animation::*;
+use crate::input::*;
+use crate::locale::*;
 use crate::menu::*;
+use crate::pause_menu::*;
+use crate::replay::*;
 use crate::resources::*;
+use crate::save::*;
 use crate::scenario::*;
+use crate::scenario_file::*;
+use crate::settings_screen::*;
+use crate::spline::*;
 use crate::states::*;
-use crate::summary::*;
+use crate::stats_screen::*;
 use crate::util::*;
 use bevy::audio::PlaybackMode;
-use bevy::audio::Volume;
+use bevy::input::InputSystem;
 use bevy::prelude::*;
-use bevy::window::WindowResolution;
+use bevy::render::camera::{ScalingMode, Viewport};
+use bevy::render::view::RenderLayers;
+use bevy::ui::UiSystem;
+use bevy::window::{PrimaryWindow, WindowMode, WindowResolution};
+use rand::Rng;
+use std::f32::consts::TAU;
 use std::time::Duration;
 
 /// Loads game assets and stores them as resources.
@@ -37,8 +53,26 @@ fn setup_game(mut commands: Commands, asset_server: Res<AssetServer>) {
         "original-trolley-switched",
     )));
 
-    // UI camera
-    commands.spawn(Camera2dBundle::default());
+    // Canvas camera, showing the canvas at whatever size fits the window
+    let mut canvas_camera = Camera2dBundle::default();
+    canvas_camera.projection.scaling_mode = ScalingMode::Fixed {
+        width: SCREEN_WIDTH,
+        height: SCREEN_HEIGHT,
+    };
+    commands.spawn((canvas_camera, CanvasCamera, IsDefaultUiCamera));
+
+    // Letterbox camera, clearing the window around the canvas
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                order: -1,
+                clear_color: ClearColorConfig::Custom(LETTERBOX_COLOR),
+                ..default()
+            },
+            ..default()
+        },
+        RenderLayers::layer(LETTERBOX_RENDER_LAYER),
+    ));
 }
 
 /// Turns the trolley as it goes to track B.
@@ -66,128 +100,223 @@ fn resume_music(
 ) {
     if let Some(mut timer) = timer {
         if timer.tick(time.delta()).just_finished() {
-            music.single().play();
+            if let Ok(music) = music.get_single() {
+                music.play();
+            }
+
             commands.remove_resource::<GameMusicPauseTimerRes>();
         }
     }
 }
 
-/// Displays the configured wounded texture on track A when appropriate.
-fn show_wounded_track_a(
-    mut commands: Commands,
-    scenarios_config: Res<ScenariosConfigRes>,
-    animation_config: Res<AnimationConfigRes>,
-    scenario_index: Res<State<ScenarioIndexState>>,
-    animation_index: Res<State<AnimationIndexState>>,
-    mut hostage_texture: Query<&mut Handle<Image>, With<HostagesTrackATexture>>,
-    image_assets: Res<ImageAssetMap>,
-    audio_assets: Res<AudioAssetMap>,
-    music: Query<&AudioSink, With<GameMusic>>,
-    trolley_approaching: Query<&AudioSink, With<TrolleyApproachingAudio>>,
+/// Applies the volume settings to the audio already playing.
+fn apply_volume_settings(settings: Res<SettingsRes>, audio: Query<(&AudioSink, &AudioCategory)>) {
+    for (sink, category) in &audio {
+        sink.set_volume(*settings.volume(*category));
+    }
+}
+
+/// Shows the game in the locale chosen in the settings.
+fn apply_language_settings(settings: Res<SettingsRes>, mut locales: ResMut<LocalesRes>) {
+    // Only choose the locale when it differs, so that screens showing text
+    // are not needlessly refreshed
+    if locales.current() != settings.language && !locales.set_current(&settings.language) {
+        warn!("unknown locale `{}`", settings.language);
+    }
+}
+
+/// Scales the canvas to fit the window, keeping its aspect ratio and
+/// centering it between bars. The UI is scaled along with it.
+fn fit_canvas_to_window(
+    windows: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+    mut cameras: Query<&mut Camera, With<CanvasCamera>>,
+    mut canvas: ResMut<CanvasRes>,
+    mut ui_scale: ResMut<UiScale>,
 ) {
-    let this_scenario = &scenarios_config[scenario_index.unwrap()];
-    let this_scenario_animations = &animation_config[scenario_index.unwrap()];
-    let this_animation = &this_scenario_animations[animation_index.unwrap()];
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
 
-    if let Some(wounded_texture_name) = &this_animation.wounded_texture {
-        let wounded_texture = image_assets.get_by_name(wounded_texture_name);
-        *hostage_texture.single_mut() = wounded_texture;
+    // The window has no size when minimized
+    let window_size = UVec2::new(window.physical_width(), window.physical_height());
 
-        for _ in 0..this_scenario.num_hostages_track_a {
-            let scream_audio_name = match &this_scenario.hostages_a_scream_sound_override {
-                Some(sound_name) => sound_name.clone(),
-                None => format!("scream-{}", (rand::random::<usize>() % 24) + 1),
-            };
+    if window_size.cmpeq(UVec2::ZERO).any() {
+        return;
+    }
 
-            let scream_audio = audio_assets.get_by_name(&scream_audio_name);
-            commands.spawn(AudioBundle {
-                source: scream_audio,
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Despawn,
-                    volume: Volume::new(GAME_VOLUME),
-                    ..default()
-                },
+    // Find the largest canvas that fits in the window, in physical pixels
+    let physical_scale =
+        (window_size.x as f32 / SCREEN_WIDTH).min(window_size.y as f32 / SCREEN_HEIGHT);
+    let physical_size = (Vec2::new(SCREEN_WIDTH, SCREEN_HEIGHT) * physical_scale)
+        .round()
+        .as_uvec2()
+        .clamp(UVec2::ONE, window_size);
+    let physical_position = (window_size - physical_size) / 2;
+
+    let fitted = CanvasRes {
+        scale: physical_scale / window.scale_factor(),
+        offset: physical_position.as_vec2() / window.scale_factor(),
+    };
+
+    // The window also changes whenever the cursor moves, so only update when
+    // the fit does
+    if *canvas == fitted {
+        return;
+    }
+
+    *canvas = fitted;
+    ui_scale.0 = fitted.scale;
+
+    for mut camera in &mut cameras {
+        camera.viewport = Some(Viewport {
+            physical_position,
+            physical_size,
+            ..default()
+        });
+    }
+}
+
+/// Starts shaking the camera whenever an animation timeline asks for it.
+fn start_camera_shake(
+    mut commands: Commands,
+    mut timeline_events: EventReader<TimelineEventFired>,
+) {
+    for fired in timeline_events.read() {
+        if let TimelineEvent::ShakeCamera {
+            intensity,
+            duration,
+        } = fired.event
+        {
+            commands.insert_resource(CameraShakeRes {
+                timer: Timer::from_seconds(duration, TimerMode::Once),
+                intensity,
             });
         }
+    }
+}
 
-        if let Some(duration) = this_scenario.pause_music_during_hostages_a_scream {
-            music.single().pause();
-            trolley_approaching.single().pause();
-            commands.insert_resource(GameMusicPauseTimerRes(Timer::new(
-                Duration::from_secs_f32(duration),
-                TimerMode::Once,
-            )));
+/// Shakes the canvas camera, settling back to rest as the shake runs out. The
+/// shake follows a fixed pattern rather than the random number generator, so
+/// that replays and simulations are unaffected.
+fn shake_camera(
+    mut commands: Commands,
+    time: Res<Time>,
+    shake: Option<ResMut<CameraShakeRes>>,
+    mut cameras: Query<&mut Transform, With<CanvasCamera>>,
+) {
+    let Some(mut shake) = shake else {
+        return;
+    };
+
+    shake.timer.tick(time.delta());
+
+    let strength = shake.intensity * (1.0 - shake.timer.fraction());
+    let phase = TAU * CAMERA_SHAKE_FREQUENCY * shake.timer.elapsed_secs();
+    let offset = strength * Vec2::new(phase.sin(), (1.3 * phase).cos());
+
+    for mut transform in &mut cameras {
+        transform.translation.x = offset.x;
+        transform.translation.y = offset.y;
+    }
+
+    if shake.timer.finished() {
+        commands.remove_resource::<CameraShakeRes>();
+    }
+}
+
+/// Applies the display settings to the window.
+fn apply_window_settings(
+    settings: Res<SettingsRes>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if let Ok(mut window) = windows.get_single_mut() {
+        window.mode = if settings.fullscreen {
+            WindowMode::BorderlessFullscreen
         } else {
-            let squash_audio = audio_assets.get_by_name("squash");
-            commands.spawn(AudioBundle {
-                source: squash_audio,
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Despawn,
-                    volume: Volume::new(GAME_VOLUME),
-                    speed: 2.0,
-                    ..default()
-                },
-            });
-        }
+            WindowMode::Windowed
+        };
     }
 }
 
-/// Displays the configured wounded texture on track B when appropriate.
-fn show_wounded_track_b(
+/// Displays the configured wounded texture on the track the trolley took when
+/// appropriate.
+fn show_wounded_hostages(
     mut commands: Commands,
     scenarios_config: Res<ScenariosConfigRes>,
     animation_config: Res<AnimationConfigRes>,
     scenario_index: Res<State<ScenarioIndexState>>,
-    animation_index: Res<State<AnimationIndexState>>,
-    mut hostage_texture: Query<&mut Handle<Image>, With<HostagesTrackBTexture>>,
+    animation_index: Res<CurrentAnimationRes>,
+    track: Res<State<TrackState>>,
+    mut hostage_textures: Query<(&mut Handle<Image>, &HostagesTexture)>,
     image_assets: Res<ImageAssetMap>,
     audio_assets: Res<AudioAssetMap>,
+    settings: Res<SettingsRes>,
     music: Query<&AudioSink, With<GameMusic>>,
     trolley_approaching: Query<&AudioSink, With<TrolleyApproachingAudio>>,
+    mut rng: ResMut<RngRes>,
 ) {
     let this_scenario = &scenarios_config[scenario_index.unwrap()];
     let this_scenario_animations = &animation_config[scenario_index.unwrap()];
-    let this_animation = &this_scenario_animations[animation_index.unwrap()];
+    let this_animation = &this_scenario_animations[**animation_index];
+    let track_index = **track.get();
+    let track = this_scenario.get_track(track_index);
 
     if let Some(wounded_texture_name) = &this_animation.wounded_texture {
         let wounded_texture = image_assets.get_by_name(wounded_texture_name);
-        *hostage_texture.single_mut() = wounded_texture;
 
-        for _ in 0..this_scenario.num_hostages_track_b {
-            let scream_audio_name = match &this_scenario.hostages_b_scream_sound_override {
+        for (mut hostage_texture, hostages) in &mut hostage_textures {
+            if **hostages == track_index {
+                *hostage_texture = wounded_texture.clone();
+            }
+        }
+
+        for _ in 0..track.num_hostages {
+            let scream_audio_name = match &track.scream_sound_override {
                 Some(sound_name) => sound_name.clone(),
-                None => format!("scream-{}", (rand::random::<usize>() % 24) + 1),
+                None => format!("scream-{}", rng.gen_range(1..=24)),
             };
 
             let scream_audio = audio_assets.get_by_name(&scream_audio_name);
-            commands.spawn(AudioBundle {
-                source: scream_audio,
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Despawn,
-                    volume: Volume::new(GAME_VOLUME),
-                    ..default()
+            commands.spawn((
+                AudioBundle {
+                    source: scream_audio,
+                    settings: PlaybackSettings {
+                        mode: PlaybackMode::Despawn,
+                        volume: settings.volume(AudioCategory::Scream),
+                        ..default()
+                    },
                 },
-            });
+                AudioCategory::Scream,
+            ));
         }
 
-        if let Some(duration) = this_scenario.pause_music_during_hostages_b_scream {
-            music.single().pause();
-            trolley_approaching.single().pause();
+        if let Some(duration) = track.pause_music_during_scream {
+            if let Ok(music) = music.get_single() {
+                music.pause();
+            }
+
+            if let Ok(trolley_approaching) = trolley_approaching.get_single() {
+                trolley_approaching.pause();
+            }
+
             commands.insert_resource(GameMusicPauseTimerRes(Timer::new(
                 Duration::from_secs_f32(duration),
                 TimerMode::Once,
             )));
         } else {
             let squash_audio = audio_assets.get_by_name("squash");
-            commands.spawn(AudioBundle {
-                source: squash_audio,
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Despawn,
-                    volume: Volume::new(GAME_VOLUME),
-                    speed: 2.0,
-                    ..default()
+            commands.spawn((
+                AudioBundle {
+                    source: squash_audio,
+                    settings: PlaybackSettings {
+                        mode: PlaybackMode::Despawn,
+                        volume: settings.volume(AudioCategory::Sfx),
+                        speed: 2.0,
+                        ..default()
+                    },
                 },
-            });
+                AudioCategory::Sfx,
+            ));
         }
     }
 }
@@ -195,7 +324,7 @@ fn show_wounded_track_b(
 /// Generates a standard animation for the trolley on track A.
 fn standard_animation_track_a(wounded_texture: Option<&str>) -> Animation {
     let mut animation = Animation::new(APPROACHING_TROLLEY_SIDE_END_TRANSFORM)
-        .on_lever_state(LeverState::Normal)
+        .on_track(TrackState(0))
         .node(
             AnimationNode::new(
                 2.0,
@@ -205,7 +334,7 @@ fn standard_animation_track_a(wounded_texture: Option<&str>) -> Animation {
                     0.0,
                 ),
             )
-            .end_action(show_wounded_track_a),
+            .end_action(show_wounded_hostages),
         )
         .node(AnimationNode::new(
             4.0,
@@ -222,10 +351,11 @@ fn standard_animation_track_a(wounded_texture: Option<&str>) -> Animation {
 /// Generates a standard animation for the trolley on track B.
 fn standard_animation_track_b(wounded_texture: Option<&str>) -> Animation {
     let mut animation = Animation::new(APPROACHING_TROLLEY_SIDE_END_TRANSFORM)
-        .on_lever_state(LeverState::Pulled)
+        .on_track(TrackState(1))
         .with_start_action(turn_trolley_switched_start)
         .node(
             AnimationNode::new(1.0, Transform::from_xyz(400.0, 190.0, 0.0))
+                .animation_fn(SplinePath::catmull_rom([Vec2::new(350.0, 204.0)]))
                 .end_action(turn_trolley_switched_end),
         )
         .node(
@@ -237,7 +367,8 @@ fn standard_animation_track_b(wounded_texture: Option<&str>) -> Animation {
                     0.0,
                 ),
             )
-            .end_action(show_wounded_track_b),
+            .animation_fn(SplinePath::catmull_rom([Vec2::new(470.0, 201.0)]))
+            .end_action(show_wounded_hostages),
         )
         .node(AnimationNode::new(
             3.0,
@@ -251,28 +382,6 @@ fn standard_animation_track_b(wounded_texture: Option<&str>) -> Animation {
     animation
 }
 
-/// The transform function for the loop animation.
-fn loop_animation(
-    start_transform: Transform,
-    _end_transform: Transform,
-    progress: f32,
-) -> Transform {
-    let loop_radius = 70.0;
-    let loop_center = Vec2::new(
-        start_transform.translation.x,
-        start_transform.translation.y - loop_radius,
-    );
-    let progress_angle = -std::f32::consts::TAU * (progress - 0.25);
-
-    Transform::IDENTITY
-        .with_translation(Vec3::new(
-            loop_center.x + loop_radius * progress_angle.cos(),
-            loop_center.y + loop_radius * progress_angle.sin(),
-            0.0,
-        ))
-        .with_rotation(Quat::from_rotation_z(std::f32::consts::TAU * progress))
-}
-
 /// Cliff start system.
 fn scenario_cliff_start(mut commands: Commands, image_assets: Res<ImageAssetMap>) {
     // Spawn the cliff asset
@@ -303,8 +412,8 @@ fn scenario_cliff_end(mut commands: Commands, entities: Res<ScenarioExtraEntitie
 }
 
 /// Cool hat end system.
-fn scenario_cool_hat_end(mut commands: Commands, lever_state: Res<State<LeverState>>) {
-    if !lever_state.pulled() {
+fn scenario_cool_hat_end(mut commands: Commands, track: Res<State<TrackState>>) {
+    if *track.get() == TrackState(0) {
         commands.insert_resource(HatAcquiredRes);
     }
 }
@@ -347,23 +456,28 @@ fn scenario_loan_forgiveness_update(
     mut timer: ResMut<OtherHostagesTextureSwapTimerRes>,
     image_assets: Res<ImageAssetMap>,
     audio_assets: Res<AudioAssetMap>,
+    settings: Res<SettingsRes>,
     mut other_hostages_texture: Query<&mut Handle<Image>, With<OtherHostagesTexture>>,
+    mut rng: ResMut<RngRes>,
 ) {
     if timer.tick(time.delta()).just_finished() {
         let other_hostages_wounded_texture = image_assets.get_by_name("age-hostage-10-wounded");
         *other_hostages_texture.single_mut() = other_hostages_wounded_texture;
 
         for _ in 0..10 {
-            let scream_audio_name = format!("scream-{}", (rand::random::<usize>() % 24) + 1);
+            let scream_audio_name = format!("scream-{}", rng.gen_range(1..=24));
             let scream_audio = audio_assets.get_by_name(&scream_audio_name);
-            commands.spawn(AudioBundle {
-                source: scream_audio,
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Despawn,
-                    volume: Volume::new(GAME_VOLUME),
-                    ..default()
+            commands.spawn((
+                AudioBundle {
+                    source: scream_audio,
+                    settings: PlaybackSettings {
+                        mode: PlaybackMode::Despawn,
+                        volume: settings.volume(AudioCategory::Scream),
+                        ..default()
+                    },
                 },
-            });
+                AudioCategory::Scream,
+            ));
         }
     }
 }
@@ -384,7 +498,11 @@ fn scenario_loan_forgiveness_end(mut commands: Commands, entities: Res<ScenarioE
 }
 
 /// Double it start system.
-fn scenario_double_it_start(mut commands: Commands, image_assets: Res<ImageAssetMap>) {
+fn scenario_double_it_start(
+    mut commands: Commands,
+    image_assets: Res<ImageAssetMap>,
+    mut rng: ResMut<RngRes>,
+) {
     let right_half_texture = image_assets.get_by_name("double-it-right-normal");
     let next_person_texture = image_assets.get_by_name("original-lever-normal");
     let hostage_2_texture = image_assets.get_by_name("double-it-hostage-2");
@@ -443,7 +561,7 @@ fn scenario_double_it_start(mut commands: Commands, image_assets: Res<ImageAsset
 
     // Insert the next person's switch timer resource
     commands.insert_resource(NextPersonSwitchTimerRes(Timer::new(
-        random_switch_delay(),
+        random_switch_delay(&mut **rng),
         TimerMode::Once,
     )));
 }
@@ -459,12 +577,13 @@ fn scenario_double_it_update(
     )>,
     image_assets: Res<ImageAssetMap>,
     next_switch_reached: Option<Res<NextSwitchReachedRes>>,
+    mut rng: ResMut<RngRes>,
 ) {
     #[allow(clippy::collapsible_if)]
     if next_switch_reached.is_none() {
         if timer.tick(time.delta()).just_finished() {
             **switch = !**switch;
-            **timer = Timer::new(random_switch_delay(), TimerMode::Once);
+            **timer = Timer::new(random_switch_delay(&mut **rng), TimerMode::Once);
 
             let (right_half_texture_name, next_person_texture_name) = if **switch {
                 ("double-it-right-normal", "original-lever-normal")
@@ -565,21 +684,30 @@ fn scenario_self_start(mut commands: Commands) {
 /// Self update system.
 fn scenario_self_update(
     mut commands: Commands,
-    buttons: Res<ButtonInput<MouseButton>>,
+    mut inputs: EventReader<PlayerInput>,
     mut jumping: ResMut<SelfJumping>,
     animation_state: Res<State<AnimationState>>,
     image_assets: Res<ImageAssetMap>,
     audio_assets: Res<AudioAssetMap>,
-    mut self_texture: Query<(&mut Handle<Image>, &mut Transform), With<LeverPlayerTexture>>,
+    settings: Res<SettingsRes>,
+    mut self_texture: Query<&mut Handle<Image>, With<LeverPlayerTexture>>,
+    mut cues: EventWriter<AnimationCue>,
 ) {
+    // Toggling the lever, which is the player here, also jumps
+    let jumped = inputs
+        .read()
+        .filter(|input| matches!(input, PlayerInput::Jump | PlayerInput::ToggleLever(_)))
+        .count()
+        > 0;
+
     match **animation_state {
         AnimationState::Waiting => {
-            if buttons.just_pressed(MouseButton::Left) {
+            if jumped {
                 *jumping = SelfJumping::Jumping;
 
                 let player_texture = image_assets.get_by_name("self");
-                *self_texture.single_mut().0 = player_texture;
-                *self_texture.single_mut().1 = normalize_transform_to_canvas(SELF_JUMP_TRANSFORM);
+                *self_texture.single_mut() = player_texture;
+                cues.send(AnimationCue("self_jump".to_owned()));
             }
         }
         AnimationState::Running => {
@@ -587,18 +715,20 @@ fn scenario_self_update(
                 *jumping = SelfJumping::RunOver;
 
                 let player_texture = image_assets.get_by_name("self-wounded");
-                *self_texture.single_mut().0 = player_texture;
-                *self_texture.single_mut().1 = normalize_transform_to_canvas(SELF_JUMP_TRANSFORM);
+                *self_texture.single_mut() = player_texture;
 
                 let scream_audio = audio_assets.get_by_name("self-scream");
-                commands.spawn(AudioBundle {
-                    source: scream_audio,
-                    settings: PlaybackSettings {
-                        mode: PlaybackMode::Despawn,
-                        volume: Volume::new(GAME_VOLUME),
-                        ..default()
+                commands.spawn((
+                    AudioBundle {
+                        source: scream_audio,
+                        settings: PlaybackSettings {
+                            mode: PlaybackMode::Despawn,
+                            volume: settings.volume(AudioCategory::Scream),
+                            ..default()
+                        },
                     },
-                });
+                    AudioCategory::Scream,
+                ));
             }
         }
         AnimationState::Complete => {}
@@ -611,8 +741,124 @@ fn scenario_self_end(mut commands: Commands) {
     commands.remove_resource::<SelfJumping>();
 }
 
-/// The plugin which orchestrates the game logic.
-pub struct GamePlugin;
+/// The plugin which orchestrates the game logic. This does not depend on a
+/// window or audio output, so it is shared by [`GamePlugin`] and the headless
+/// simulation. The asset and state plugins must be added before this one.
+pub struct GameLogicPlugin;
+
+impl Plugin for GameLogicPlugin {
+    fn build(&self, app: &mut App) {
+        // Add embedded assets
+        app.add_plugins(EmbeddedAssetPlugin);
+
+        // Insert stateful values
+        app.insert_state(GameState::Initializing)
+            .insert_state(ScenarioIndexState(None))
+            .insert_state(TrackState::default());
+
+        // Add the player input event
+        app.add_event::<PlayerInput>();
+
+        // Seed the random number generator, unless a seed has already been
+        // chosen
+        if !app.world().contains_resource::<RngRes>() {
+            app.insert_resource(RngRes::from_entropy());
+        }
+
+        // Use the default settings, unless settings have already been loaded
+        if !app.world().contains_resource::<SettingsRes>() {
+            app.insert_resource(SettingsRes(Settings::default()));
+        }
+
+        // Load the locales, unless they have already been loaded, and choose
+        // the one in the settings
+        if !app.world().contains_resource::<LocalesRes>() {
+            let locales = load_locales().unwrap_or_else(|error| panic!("{}", error));
+            app.insert_resource(LocalesRes(locales));
+        }
+
+        let language = app.world().resource::<SettingsRes>().language.clone();
+        app.world_mut()
+            .resource_mut::<LocalesRes>()
+            .set_current(&language);
+
+        // Add game setup system
+        app.add_systems(Startup, setup_game);
+
+        // Add system to resume music whenever paused
+        app.add_systems(Update, resume_music);
+
+        // Add systems to keep playing audio at the volume settings and showing
+        // text in the chosen language
+        app.add_systems(
+            Update,
+            (apply_volume_settings, apply_language_settings)
+                .run_if(resource_changed::<SettingsRes>),
+        );
+
+        // Register the systems and animations that scenario files refer to
+        let hooks = ScenarioHooks::new()
+            .animation("standard_animation_track_a", standard_animation_track_a)
+            .animation("standard_animation_track_b", standard_animation_track_b)
+            .system("show_wounded_hostages", show_wounded_hostages)
+            .system("scenario_cliff_start", scenario_cliff_start)
+            .system("scenario_cliff_end", scenario_cliff_end)
+            .system("scenario_cool_hat_end", scenario_cool_hat_end)
+            .system(
+                "scenario_loan_forgiveness_start",
+                scenario_loan_forgiveness_start,
+            )
+            .system(
+                "scenario_loan_forgiveness_update",
+                scenario_loan_forgiveness_update,
+            )
+            .system(
+                "scenario_loan_forgiveness_end",
+                scenario_loan_forgiveness_end,
+            )
+            .system("scenario_double_it_start", scenario_double_it_start)
+            .system("scenario_double_it_update", scenario_double_it_update)
+            .system("set_next_switch_reached", set_next_switch_reached)
+            .system("scenario_double_it_end", scenario_double_it_end)
+            .system("scenario_youtube_prank_start", scenario_youtube_prank_start)
+            .system("scenario_youtube_prank_end", scenario_youtube_prank_end)
+            .system("scenario_self_start", scenario_self_start)
+            .system("scenario_self_update", scenario_self_update)
+            .system("scenario_self_end", scenario_self_end);
+
+        // Add scenarios
+        app.add_plugins(
+            ScenarioCollectionPlugin::builder()
+                .scenario_files(&hooks)
+                .and_then(ScenarioCollectionPluginBuilder::achievement_file)
+                .unwrap_or_else(|error| panic!("{}", error))
+                .build(),
+        );
+
+        // Play the sprite-sheet frame animations of scenarios
+        app.add_plugins(FrameAnimationPlugin);
+    }
+}
+
+/// The plugin which runs the game in a window.
+#[derive(Default)]
+pub struct GamePlugin {
+    /// A replay to play back in place of the player's input.
+    replay: Option<Replay>,
+}
+
+impl GamePlugin {
+    /// Creates a new game plugin.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Plays back a replay in place of the player's input.
+    pub fn with_replay(mut self, replay: Replay) -> Self {
+        self.replay = Some(replay);
+        self
+    }
+}
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
@@ -626,16 +872,10 @@ impl Plugin for GamePlugin {
                 }),
                 ..default()
             }),
-            EmbeddedAssetPlugin,
+            GameLogicPlugin,
         ));
 
-        // Insert stateful values
-        app.insert_state(GameState::Initializing)
-            .insert_state(ScenarioIndexState(None))
-            .insert_state(LeverState::Normal);
-
-        // Add game setup and menu screen systems
-        app.add_systems(Startup, setup_game);
+        // Add menu screen systems
         app.add_systems(OnEnter(GameState::InMenu), setup_menu_screen);
         app.add_systems(
             Update,
@@ -651,407 +891,116 @@ impl Plugin for GamePlugin {
         );
         app.add_systems(OnExit(GameState::EndScreen), cleanup_end_screen);
 
-        // Add system to resume music whenever paused
-        app.add_systems(Update, resume_music);
+        // Add stats screen systems
+        app.add_systems(OnEnter(GameState::StatsScreen), setup_stats_screen);
+        app.add_systems(
+            Update,
+            update_stats_screen.run_if(in_state(GameState::StatsScreen)),
+        );
+        app.add_systems(OnExit(GameState::StatsScreen), cleanup_stats_screen);
 
-        // Original
-        let scenario_original = Scenario::builder()
-            .text("A trolley is headed towards a group of five people. You can intervene and click on the lever to pull it and switch the tracks so that only one person will be killed. Do you pull the lever?")
-            .duration(20.0)
-            .hostages_track_a_pos(STANDARD_HOSTAGES_POS_TRACK_A)
-            .hostages_track_b_pos(STANDARD_HOSTAGES_POS_TRACK_B)
-            .tracks_normal_texture("original-tracks-normal")
-            .tracks_switched_texture("original-tracks-switched")
-            .lever_normal_texture("original-lever-normal")
-            .lever_switched_texture("original-lever-switched")
-            .hostages_track_a_normal_texture("original-hostage-5")
-            .hostages_track_b_normal_texture("original-hostage-1")
-            .num_hostages_track_a(5)
-            .num_hostages_track_b(1)
-            .animation(standard_animation_track_a(Some("original-hostage-5-wounded")))
-            .animation(standard_animation_track_b(Some("original-hostage-1-wounded")))
-            .on_end(update_summary_original)
-            .build();
-
-        // Age
-        let scenario_age = Scenario::builder()
-            .text("Everyone on the lower track is 90 years old. There is a child on the upper track. Do you pull the lever?")
-            .duration(20.0)
-            .hostages_track_a_pos(STANDARD_HOSTAGES_POS_TRACK_A)
-            .hostages_track_b_pos(STANDARD_HOSTAGES_POS_TRACK_B)
-            .tracks_normal_texture("original-tracks-normal")
-            .tracks_switched_texture("original-tracks-switched")
-            .lever_normal_texture("original-lever-normal")
-            .lever_switched_texture("original-lever-switched")
-            .hostages_track_a_normal_texture("age-hostage-10")
-            .hostages_track_b_normal_texture("original-hostage-1")
-            .num_hostages_track_a(10)
-            .num_hostages_track_b(1)
-            .animation(standard_animation_track_a(Some("age-hostage-10-wounded")))
-            .animation(standard_animation_track_b(Some("original-hostage-1-wounded")))
-            .on_end(update_summary_age)
-            .build();
-
-        // Clone
-        let scenario_clone = Scenario::builder()
-            .text("If you pull the lever, the trolley will kill a clone of Hitler. The clone has all of Hitler's memories and fully believes that they are him and that they committed all of his actions, even though they didn't. Do you pull the lever?")
-            .duration(25.0)
-            .hostages_track_b_pos(STANDARD_HOSTAGES_POS_TRACK_B)
-            .tracks_normal_texture("original-tracks-normal")
-            .tracks_switched_texture("original-tracks-switched")
-            .lever_normal_texture("original-lever-normal")
-            .lever_switched_texture("original-lever-switched")
-            .hostages_track_b_normal_texture("original-hostage-1")
-            .num_hostages_track_a(0)
-            .num_hostages_track_b(1)
-            .animation(standard_animation_track_a(None))
-            .animation(standard_animation_track_b(Some("original-hostage-1-wounded")))
-            .on_end(update_summary_clone)
-            .build();
-
-        // Cliff
-        let scenario_cliff = Scenario::builder()
-            .text("Hitler is the only passenger on the trolley. If you do nothing, five innocent people will be killed, and the trolley will fall off the cliff and explode. If you pull the lever, the innocents will be spared, but Hitler will escape to freedom.")
-            .duration(25.0)
-            .hostages_track_a_pos(STANDARD_HOSTAGES_POS_TRACK_A)
-            .tracks_normal_texture("original-tracks-normal")
-            .tracks_switched_texture("original-tracks-switched")
-            .lever_normal_texture("original-lever-normal")
-            .lever_switched_texture("original-lever-switched")
-            .hostages_track_a_normal_texture("original-hostage-5")
-            .num_hostages_track_a(5)
-            .num_hostages_track_b(0)
-            .animation(
-                Animation::new(APPROACHING_TROLLEY_SIDE_END_TRANSFORM)
-                    .on_lever_state(LeverState::Normal)
-                    .with_wounded_texture("original-hostage-5-wounded")
-                    .node(
-                        AnimationNode::new(
-                            2.0,
-                            Transform::from_xyz(STANDARD_HOSTAGES_POS_TRACK_A.x, STANDARD_HOSTAGES_POS_TRACK_A.y, 0.0))
-                        .end_action(show_wounded_track_a))
-                    .node(
-                        AnimationNode::new(
-                            1.0,
-                            Transform::from_xyz(715.0, 375.0, 0.0)))
-                    .node(
-                        AnimationNode::new(
-                            2.0,
-                            Transform::IDENTITY
-                                .with_translation(Vec3::new(785.0, 460.0, 0.0))
-                                .with_scale(Vec3::new(0.0, 0.0, 0.0))
-                                .with_rotation(Quat::from_rotation_z(-0.375 * std::f32::consts::TAU)))))
-            .animation(standard_animation_track_b(None))
-            .on_start(scenario_cliff_start)
-            .on_end((scenario_cliff_end, update_summary_cliff))
-            .build();
-
-        // Cool hat
-        let scenario_cool_hat = Scenario::builder()
-            .text("You can pull the lever to save five people, but you would kill the guy with the really cool hat. Do you pull the lever?")
-            .duration(15.0)
-            .hostages_track_a_pos(STANDARD_HOSTAGES_POS_TRACK_A)
-            .hostages_track_b_pos(STANDARD_HOSTAGES_POS_TRACK_B)
-            .tracks_normal_texture("original-tracks-normal")
-            .tracks_switched_texture("original-tracks-switched")
-            .lever_normal_texture("original-lever-normal")
-            .lever_switched_texture("original-lever-switched")
-            .hostages_track_a_normal_texture("original-hostage-5")
-            .hostages_track_b_normal_texture("hat-hostage")
-            .num_hostages_track_a(5)
-            .num_hostages_track_b(1)
-            .animation(standard_animation_track_a(Some("original-hostage-5-wounded")))
-            .animation(standard_animation_track_b(Some("hat-hostage-wounded")))
-            .on_end((scenario_cool_hat_end, update_summary_cool_hat))
-            .build();
-
-        // Victim
-        let scenario_victim = Scenario::builder()
-            .text("The person on the track claims that \"Society needs to pull the lever.\" You have told them to just walk off the tracks. Is this person really the victim if they have knowingly done this to themselves? Will you be responsible if they die?")
-            .duration(25.0)
-            .hostages_track_a_pos(STANDARD_HOSTAGES_POS_TRACK_A)
-            .tracks_normal_texture("original-tracks-normal")
-            .tracks_switched_texture("original-tracks-switched")
-            .lever_normal_texture("original-lever-normal")
-            .lever_switched_texture("original-lever-switched")
-            .hostages_track_a_normal_texture("victim")
-            .num_hostages_track_a(1)
-            .num_hostages_track_b(0)
-            .animation(standard_animation_track_a(Some("victim-wounded")))
-            .animation(standard_animation_track_b(None))
-            .on_end(update_summary_victim)
-            .build();
-
-        // Darwinism
-        let scenario_darwinism = Scenario::builder()
-            .text("A person on the lower track is not tied down, and can walk away if he is smart enough to recognize the danger of the trolley. If he is not smart enough, natural selection will do its job and eliminate him from the gene pool, making humanity smarter. The upper track has 5 untied persons, which could potentially apply natural selection to 4 extra people, making humanity even smarter. Do you pull the lever?")
-            .duration(30.0)
-            .hostages_track_a_pos(STANDARD_HOSTAGES_POS_TRACK_A)
-            .hostages_track_b_pos(STANDARD_HOSTAGES_POS_TRACK_B)
-            .tracks_normal_texture("original-tracks-normal")
-            .tracks_switched_texture("original-tracks-switched")
-            .lever_normal_texture("original-lever-normal")
-            .lever_switched_texture("original-lever-switched")
-            .hostages_track_a_normal_texture("darwinism-hostage-1")
-            .hostages_track_b_normal_texture("darwinism-hostage-5")
-            .num_hostages_track_a(1)
-            .num_hostages_track_b(5)
-            .animation(standard_animation_track_a(Some("darwinism-hostage-1-wounded")))
-            .animation(standard_animation_track_b(Some("darwinism-hostage-5-wounded")))
-            .on_end(update_summary_darwinism)
-            .build();
-
-        // Loop
-        let scenario_loop = Scenario::builder()
-            .text("A trolley is headed towards a group of five people. You can pull the lever to only kill one person, but then the trolley won't do the totally sick loop-da-loop. Do you pull the lever?")
-            .duration(20.0)
-            .hostages_track_a_pos(STANDARD_HOSTAGES_POS_TRACK_A)
-            .hostages_track_b_pos(STANDARD_HOSTAGES_POS_TRACK_B)
-            .tracks_normal_texture("loop-normal")
-            .tracks_switched_texture("loop-switched")
-            .lever_normal_texture("original-lever-normal")
-            .lever_switched_texture("original-lever-switched")
-            .hostages_track_a_normal_texture("original-hostage-5")
-            .hostages_track_b_normal_texture("original-hostage-1")
-            .num_hostages_track_a(5)
-            .num_hostages_track_b(1)
-            .animation(
-                Animation::new(APPROACHING_TROLLEY_SIDE_END_TRANSFORM)
-                    .on_lever_state(LeverState::Normal)
-                    .with_wounded_texture("original-hostage-5-wounded")
-                    .node(
-                        AnimationNode::new(
-                            2.0,
-                            Transform::from_xyz(STANDARD_HOSTAGES_POS_TRACK_A.x, STANDARD_HOSTAGES_POS_TRACK_A.y, 0.0))
-                        .end_action(show_wounded_track_a))
-                    .node(AnimationNode::new(0.25, Transform::from_xyz(570.0, 305.0, 0.0)))
-                    .node(AnimationNode::new(2.0, Transform::from_xyz(570.0, 305.0, 0.0)).animation_fn(loop_animation))
-                    .node(AnimationNode::new(2.0, Transform::from_xyz(900.0, 445.0, 0.0))))
-            .animation(standard_animation_track_b(Some("original-hostage-1-wounded")))
-            .on_end(update_summary_loop)
-            .build();
-
-        // Professors
-        let scenario_professors = Scenario::builder()
-            .text("A trolley is headed towards five philosophy professors who like to invent trolley problems. You can pull the lever to switch the track, but then the trolley will kill the nice guy who never had a thought of inventing trolley problems.")
-            .duration(20.0)
-            .hostages_track_a_pos(STANDARD_HOSTAGES_POS_TRACK_A)
-            .hostages_track_b_pos(STANDARD_HOSTAGES_POS_TRACK_B)
-            .tracks_normal_texture("original-tracks-normal")
-            .tracks_switched_texture("original-tracks-switched")
-            .lever_normal_texture("original-lever-normal")
-            .lever_switched_texture("original-lever-switched")
-            .hostages_track_a_normal_texture("original-hostage-5")
-            .hostages_track_b_normal_texture("original-hostage-1")
-            .num_hostages_track_a(5)
-            .num_hostages_track_b(1)
-            .animation(standard_animation_track_a(Some("original-hostage-5-wounded")))
-            .animation(standard_animation_track_b(Some("original-hostage-1-wounded")))
-            .on_end(update_summary_professors)
-            .build();
-
-        // Loan forgiveness
-        let scenario_loan_forgiveness = Scenario::builder()
-            .text("The trolley is going to run over ten people. Unless you intervene, it will run over an additional five people. But if you divert the trolley, wouldn't that be unfair to all the people it will have already killed?")
-            .duration(20.0)
-            .hostages_track_a_pos(STANDARD_HOSTAGES_POS_TRACK_A)
-            .tracks_normal_texture("original-tracks-normal")
-            .tracks_switched_texture("original-tracks-switched")
-            .lever_normal_texture("original-lever-normal")
-            .lever_switched_texture("original-lever-switched")
-            .hostages_track_a_normal_texture("original-hostage-5")
-            .num_hostages_track_a(5)
-            .num_hostages_track_b(0)
-            .animation(standard_animation_track_a(Some("original-hostage-5-wounded")))
-            .animation(standard_animation_track_b(None))
-            .on_start(scenario_loan_forgiveness_start)
-            .on_update(scenario_loan_forgiveness_update)
-            .on_end((scenario_loan_forgiveness_end, update_summary_loan_forgiveness))
-            .build();
-
-        // Lobster
-        let scenario_lobster = Scenario::builder()
-            .text("A trolley is headed towards a group of five lobsters. Are you really going to let five innocent lobsters die?")
-            .duration(15.0)
-            .hostages_track_a_pos(STANDARD_HOSTAGES_POS_TRACK_A)
-            .hostages_track_b_pos(STANDARD_HOSTAGES_POS_TRACK_B)
-            .tracks_normal_texture("original-tracks-normal")
-            .tracks_switched_texture("original-tracks-switched")
-            .lever_normal_texture("original-lever-normal")
-            .lever_switched_texture("original-lever-switched")
-            .hostages_track_a_normal_texture("lobster-hostage-5")
-            .hostages_track_b_normal_texture("original-hostage-1")
-            .num_hostages_track_a(1)
-            .num_hostages_track_b(1)
-            .override_hostages_a_scream_sound("blue-lobster")
-            .pause_music_during_hostages_a_scream(5.5)
-            .animation(standard_animation_track_a(Some("lobster-hostage-5-wounded")))
-            .animation(standard_animation_track_b(Some("original-hostage-1-wounded")))
-            .on_end(update_summary_lobster)
-            .build();
-
-        // Shopping cart
-        let scenario_shopping_cart = Scenario::builder()
-            .text("There is no dire emergency. Do you accept your duty to return the cart even though you gain nothing?")
-            .duration(15.0)
-            .tracks_normal_texture("shopping-cart-tracks-normal")
-            .tracks_switched_texture("shopping-cart-tracks-switched")
-            .lever_normal_texture("original-lever-normal")
-            .lever_switched_texture("original-lever-switched")
-            .num_hostages_track_a(0)
-            .num_hostages_track_b(0)
-            .override_trolley_texture("shopping-cart")
-            .animation(standard_animation_track_a(None))
-            .animation(
-                Animation::new(APPROACHING_TROLLEY_SIDE_END_TRANSFORM)
-                    .on_lever_state(LeverState::Pulled)
-                    .node(AnimationNode::new(1.0, Transform::from_xyz(400.0, 190.0, 0.0)))
-                    .node(AnimationNode::new(2.0, Transform::from_xyz(595.0, 240.0, 0.0)))
-                    .node(AnimationNode::new(1.0, Transform::from_xyz(680.0, 180.0, 0.0))))
-            .on_end(update_summary_shopping_cart)
-            .build();
-
-        // Born lever puller
-        let scenario_born_lever_puller = Scenario::builder()
-            .text("If you do nothing, nobody will get hurt. However, you are a born lever-puller. Do you pull the lever?")
-            .duration(15.0)
-            .hostages_track_b_pos(STANDARD_HOSTAGES_POS_TRACK_B)
-            .tracks_normal_texture("original-tracks-normal")
-            .tracks_switched_texture("original-tracks-switched")
-            .lever_normal_texture("born-lever-puller-normal")
-            .lever_switched_texture("born-lever-puller-switched")
-            .hostages_track_b_normal_texture("original-hostage-1")
-            .num_hostages_track_a(0)
-            .num_hostages_track_b(1)
-            .animation(standard_animation_track_a(None))
-            .animation(standard_animation_track_b(Some("original-hostage-1-wounded")))
-            .on_end(update_summary_born_lever_puller)
-            .build();
-
-        // Double it
-        let scenario_double_it = Scenario::builder()
-            .text("Would you kill one person or double it and give it to the next person?")
-            .duration(15.0)
-            .hostages_track_b_pos(STANDARD_HOSTAGES_POS_TRACK_B)
-            .tracks_normal_texture("double-it-left-normal")
-            .tracks_switched_texture("double-it-left-switched")
-            .lever_normal_texture("original-lever-normal")
-            .lever_switched_texture("original-lever-switched")
-            .hostages_track_b_normal_texture("original-hostage-1")
-            .num_hostages_track_a(0)
-            .num_hostages_track_b(1)
-            .animation(
-                Animation::new(APPROACHING_TROLLEY_SIDE_END_TRANSFORM)
-                    .on_lever_state(LeverState::Normal)
-                    .node(
-                        AnimationNode::new(3.0, NEXT_PERSON_SWITCH_TRANSFORM)
-                            .end_action(set_next_switch_reached),
-                    )
-                    .node(AnimationNode::new(
-                        2.0,
-                        Transform::from_xyz(900.0, 445.0, 0.0),
-                    )),
+        // Add pause menu systems
+        app.add_sub_state::<PauseState>();
+        app.add_systems(Update, toggle_pause.run_if(in_state(GameState::Playing)));
+        app.add_systems(OnEnter(PauseState::Paused), (freeze_game, setup_pause_menu));
+        app.add_systems(
+            Update,
+            (update_pause_menu, resume_on_confirm).run_if(in_state(PauseState::Paused)),
+        );
+        app.add_systems(
+            Update,
+            restart_on_input
+                .after(update_pause_menu)
+                .run_if(in_state(GameState::Playing)),
+        );
+        app.add_systems(
+            OnExit(PauseState::Paused),
+            (unfreeze_game, cleanup_pause_menu),
+        );
+        app.add_systems(
+            Update,
+            restart_scenario.run_if(
+                resource_exists::<RestartScenarioRes>.and_then(in_state(ScenarioIndexState(None))),
+            ),
+        );
+
+        // Add settings screen systems
+        app.add_systems(OnEnter(GameState::SettingsScreen), setup_settings_screen);
+        app.add_systems(
+            Update,
+            (
+                update_settings_screen,
+                update_setting_widgets,
+                refresh_settings_screen,
             )
-            .animation(standard_animation_track_b(Some(
-                "original-hostage-1-wounded",
-            )))
-            .on_start(scenario_double_it_start)
-            .on_update(scenario_double_it_update)
-            .on_end((scenario_double_it_end, update_summary_double_it))
-            .build();
-
-        // Thomas the tank engine
-        let scenario_thomas_the_tank_engine = Scenario::builder()
-            .text("There is only one track. Everyone is in danger and there is nothing you can do to save anyone. Also the trolley is Thomas the Tank Engine.")
-            .duration(10.0)
-            .hostages_track_a_pos(STANDARD_HOSTAGES_POS_TRACK_A)
-            .tracks_normal_texture("self-one-track")
-            .lever_normal_texture("self-standing")
-            .hostages_track_a_normal_texture("original-hostage-5")
-            .num_hostages_track_a(5)
-            .num_hostages_track_b(0)
-            .override_trolley_texture("thomas-the-tank-engine")
-            .override_hostages_a_scream_sound("thomas-theme")
-            .pause_music_during_hostages_a_scream(4.0)
-            .animation(
-                Animation::new(APPROACHING_TROLLEY_SIDE_END_TRANSFORM)
-                    .with_wounded_texture("original-hostage-5-wounded")
-                    .node(
-                        AnimationNode::new(
-                            2.0,
-                            Transform::from_xyz(
-                                STANDARD_HOSTAGES_POS_TRACK_A.x,
-                                STANDARD_HOSTAGES_POS_TRACK_A.y,
-                                0.0,
-                            ),
-                        )
-                        .end_action(show_wounded_track_a),
-                    )
-                    .node(AnimationNode::new(
-                        4.0,
-                        Transform::from_xyz(900.0, 445.0, 0.0),
-                    )))
-            .on_end(update_summary_thomas_the_tank_engine)
-            .build();
-
-        // Youtube prank
-        let scenario_youtube_prank = Scenario::builder()
-            .text("Five reaction YouTubers tied themselves to the tracks and a trolley is heading straight for them. You notice the person tied to the other track is a dummy. Do you pull the lever to save them, contributing to their viral prank?")
-            .duration(25.0)
-            .hostages_track_a_pos(STANDARD_HOSTAGES_POS_TRACK_A)
-            .hostages_track_b_pos(STANDARD_HOSTAGES_POS_TRACK_B)
-            .tracks_normal_texture("original-tracks-normal")
-            .tracks_switched_texture("original-tracks-switched")
-            .lever_normal_texture("original-lever-normal")
-            .lever_switched_texture("original-lever-switched")
-            .hostages_track_a_normal_texture("youtube-prank-youtubers")
-            .hostages_track_b_normal_texture("youtube-prank-dummy")
-            .num_hostages_track_a(5)
-            .num_hostages_track_b(0)
-            .animation(standard_animation_track_a(Some("youtube-prank-youtubers-wounded")))
-            .animation(standard_animation_track_b(Some("youtube-prank-dummy-wounded")))
-            .on_start(scenario_youtube_prank_start)
-            .on_end((scenario_youtube_prank_end, update_summary_youtube_prank))
-            .build();
-
-        // Self
-        let scenario_self = Scenario::builder()
-            .text("Nobody is in any danger. Do you jump in front of the moving trolley?")
-            .duration(10.0)
-            .hostages_track_a_pos(STANDARD_HOSTAGES_POS_TRACK_A)
-            .tracks_normal_texture("self-one-track")
-            .lever_normal_texture("self-standing")
-            .num_hostages_track_a(0)
-            .num_hostages_track_b(0)
-            .animation(Animation::new(APPROACHING_TROLLEY_SIDE_END_TRANSFORM).node(
-                AnimationNode::new(6.0, Transform::from_xyz(900.0, 445.0, 0.0)),
-            ))
-            .on_start(scenario_self_start)
-            .on_update(scenario_self_update)
-            .on_end((update_summary_self, scenario_self_end).chain())
-            .build();
+                .chain()
+                .run_if(in_state(GameState::SettingsScreen)),
+        );
+        app.add_systems(OnExit(GameState::SettingsScreen), cleanup_settings_screen);
+        app.add_systems(
+            Update,
+            apply_window_settings.run_if(resource_changed::<SettingsRes>),
+        );
 
-        // Add scenarios
-        app.add_plugins(
-            ScenarioCollectionPlugin::builder()
-                .scenario(scenario_original)
-                .scenario(scenario_age)
-                .scenario(scenario_clone)
-                .scenario(scenario_cliff)
-                .scenario(scenario_cool_hat)
-                .scenario(scenario_victim)
-                .scenario(scenario_darwinism)
-                .scenario(scenario_loop)
-                .scenario(scenario_professors)
-                .scenario(scenario_loan_forgiveness)
-                .scenario(scenario_lobster)
-                .scenario(scenario_shopping_cart)
-                .scenario(scenario_born_lever_puller)
-                .scenario(scenario_double_it)
-                .scenario(scenario_thomas_the_tank_engine)
-                .scenario(scenario_youtube_prank)
-                .scenario(scenario_self)
-                .build(),
+        // Add system to scale the canvas to the window, before the UI and the
+        // player input use it
+        app.init_resource::<CanvasRes>();
+        app.add_systems(PreUpdate, fit_canvas_to_window.before(UiSystem::Focus));
+
+        // Add systems to shake the camera when animation timelines ask
+        app.add_systems(Update, (start_camera_shake, shake_camera).chain());
+
+        // Add controls screen systems
+        app.add_systems(OnEnter(GameState::ControlsScreen), setup_controls_screen);
+        app.add_systems(
+            Update,
+            (
+                update_controls_screen,
+                capture_rebinding.run_if(resource_exists::<RebindingRes>),
+                update_bindings_text,
+            )
+                .chain()
+                .run_if(in_state(GameState::ControlsScreen)),
         );
+        app.add_systems(OnExit(GameState::ControlsScreen), cleanup_controls_screen);
+
+        // Keep save data in a file, or in memory on the web where there is no
+        // filesystem
+        if cfg!(target_arch = "wasm32") {
+            app.add_plugins(SavePlugin::new(MemoryStorage::default()));
+        } else {
+            app.add_plugins(SavePlugin::new(FileStorage::new(SAVE_FILE)));
+        }
+
+        // Record choices and show how other players chose, keeping them in
+        // memory on the web where there is no filesystem
+        if cfg!(target_arch = "wasm32") {
+            app.add_plugins(ChoiceStatsPlugin::in_memory());
+        } else {
+            app.add_plugins(ChoiceStatsPlugin::new(CHOICE_STATS_DIR));
+        }
+
+        // Take input from the replay if there is one, or from the player,
+        // recording it where there is a filesystem to record to. The player
+        // still controls the screens outside of a replayed run
+        if let Some(replay) = &self.replay {
+            app.add_plugins(ReplayPlugin::play_back(replay.clone()));
+            app.add_systems(
+                PreUpdate,
+                read_player_input
+                    .after(InputSystem)
+                    .after(UiSystem::Focus)
+                    .run_if(not(in_state(GameState::Playing))),
+            );
+        } else {
+            app.add_systems(
+                PreUpdate,
+                read_player_input.after(InputSystem).after(UiSystem::Focus),
+            );
+
+            if !cfg!(target_arch = "wasm32") {
+                app.add_plugins(ReplayPlugin::record(REPLAY_FILE));
+            }
+        }
     }
 }
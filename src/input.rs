@@ -0,0 +1,362 @@
+//! Player input.
+
+use crate::constants::*;
+use crate::locale::*;
+use crate::resources::*;
+use crate::states::*;
+use bevy::ecs::system::SystemParam;
+use bevy::input::gamepad::GamepadButtonType;
+use bevy::input::touch::Touches;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::mem;
+
+/// An action the player can bind inputs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    /// Toggle a lever.
+    ToggleLever,
+    /// Select the next lever for keyboard and gamepad inputs to act on.
+    NextLever,
+    /// Jump onto the track.
+    Jump,
+    /// Lock in the lever positions, fast-forwarding the trolley.
+    LockIn,
+    /// Press the main button on a screen.
+    Confirm,
+    /// Pause or resume a scenario.
+    Pause,
+}
+
+impl InputAction {
+    /// All input actions, in the order they are listed.
+    pub const ALL: [Self; 6] = [
+        Self::ToggleLever,
+        Self::NextLever,
+        Self::Jump,
+        Self::LockIn,
+        Self::Confirm,
+        Self::Pause,
+    ];
+
+    /// Gets the key of the name of the action in the locale files.
+    pub const fn name_key(self) -> &'static str {
+        match self {
+            Self::ToggleLever => "action.toggle_lever",
+            Self::NextLever => "action.next_lever",
+            Self::Jump => "action.jump",
+            Self::LockIn => "action.lock_in",
+            Self::Confirm => "action.confirm",
+            Self::Pause => "action.pause",
+        }
+    }
+}
+
+/// An input that can be bound to an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputBinding {
+    /// A keyboard key.
+    Key(KeyCode),
+    /// A mouse button, pressed with the cursor over the canvas.
+    Mouse(MouseButton),
+    /// A button on any gamepad.
+    Gamepad(GamepadButtonType),
+    /// A touch anywhere on the canvas.
+    Touch,
+}
+
+impl InputBinding {
+    /// Gets the name of the binding as shown to the player.
+    pub fn name(self, locales: &Locales) -> String {
+        match self {
+            Self::Key(key) => locales.format("binding.key", &[("name", &format!("{:?}", key))]),
+            Self::Mouse(button) => {
+                locales.format("binding.mouse", &[("name", &format!("{:?}", button))])
+            }
+            Self::Gamepad(button) => {
+                locales.format("binding.gamepad", &[("name", &format!("{:?}", button))])
+            }
+            Self::Touch => locales.text("binding.touch").to_owned(),
+        }
+    }
+
+    /// Checks whether the binding is for a pointer, pressed at a position on
+    /// the canvas.
+    pub const fn is_pointer(self) -> bool {
+        matches!(self, Self::Mouse(_) | Self::Touch)
+    }
+
+    /// Checks whether two bindings are for the same kind of device.
+    fn same_device(self, other: Self) -> bool {
+        mem::discriminant(&self) == mem::discriminant(&other)
+    }
+}
+
+/// The inputs bound to each action. Actions missing from stored bindings,
+/// such as those added since they were stored, get their default bindings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    from = "BTreeMap<InputAction, Vec<InputBinding>>",
+    into = "BTreeMap<InputAction, Vec<InputBinding>>"
+)]
+pub struct InputBindings(BTreeMap<InputAction, Vec<InputBinding>>);
+
+impl InputBindings {
+    /// Gets the default bindings of an action. No two actions share a
+    /// default binding; pointers only toggle levers, and the self scenario
+    /// treats toggling the player's lever as jumping.
+    fn defaults(action: InputAction) -> Vec<InputBinding> {
+        match action {
+            InputAction::ToggleLever => vec![
+                InputBinding::Mouse(MouseButton::Left),
+                InputBinding::Touch,
+                InputBinding::Key(KeyCode::Space),
+                InputBinding::Gamepad(GamepadButtonType::South),
+            ],
+            InputAction::NextLever => vec![
+                InputBinding::Key(KeyCode::Tab),
+                InputBinding::Gamepad(GamepadButtonType::RightTrigger),
+            ],
+            InputAction::Jump => vec![
+                InputBinding::Key(KeyCode::ArrowUp),
+                InputBinding::Gamepad(GamepadButtonType::North),
+            ],
+            InputAction::LockIn => vec![
+                InputBinding::Key(KeyCode::KeyL),
+                InputBinding::Gamepad(GamepadButtonType::East),
+            ],
+            InputAction::Confirm => vec![
+                InputBinding::Key(KeyCode::Enter),
+                InputBinding::Gamepad(GamepadButtonType::Start),
+            ],
+            InputAction::Pause => vec![
+                InputBinding::Key(KeyCode::Escape),
+                InputBinding::Gamepad(GamepadButtonType::Select),
+            ],
+        }
+    }
+
+    /// Gets the inputs bound to an action.
+    pub fn get(&self, action: InputAction) -> &[InputBinding] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Binds an input to an action, replacing whatever was bound to the
+    /// action on the same kind of device.
+    pub fn rebind(&mut self, action: InputAction, binding: InputBinding) {
+        let bindings = self.0.entry(action).or_default();
+        bindings.retain(|bound| !bound.same_device(binding));
+        bindings.push(binding);
+    }
+
+    /// Restores the default bindings of an action.
+    pub fn reset(&mut self, action: InputAction) {
+        self.0.insert(action, Self::defaults(action));
+    }
+}
+
+impl From<BTreeMap<InputAction, Vec<InputBinding>>> for InputBindings {
+    fn from(mut bindings: BTreeMap<InputAction, Vec<InputBinding>>) -> Self {
+        for action in InputAction::ALL {
+            bindings
+                .entry(action)
+                .or_insert_with(|| Self::defaults(action));
+        }
+
+        Self(bindings)
+    }
+}
+
+impl From<InputBindings> for BTreeMap<InputAction, Vec<InputBinding>> {
+    fn from(bindings: InputBindings) -> Self {
+        bindings.0
+    }
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self(
+            InputAction::ALL
+                .into_iter()
+                .map(|action| (action, Self::defaults(action)))
+                .collect(),
+        )
+    }
+}
+
+/// An input from the player that scenarios respond to. Scenarios only ever
+/// read these events, so inputs can come from the player or from a replay.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PlayerInput {
+    /// The player toggled a lever. With a position on the canvas, from the
+    /// top left, every lever at that position is toggled. Otherwise, the
+    /// focused lever is toggled.
+    ToggleLever(Option<Vec2>),
+    /// The player focused the next lever.
+    NextLever,
+    /// The player jumped onto the track.
+    Jump,
+    /// The player locked in the lever positions.
+    LockIn,
+    /// The player pressed the main button on a screen.
+    Confirm,
+    /// The player paused or resumed a scenario.
+    Pause,
+    /// The player restarted the active scenario.
+    Restart,
+    /// The player pointed the focused lever directly to a position.
+    PointLever(LeverState),
+}
+
+/// The raw keyboard, mouse, gamepad and touch inputs.
+#[derive(SystemParam)]
+pub struct RawInputs<'w, 's> {
+    /// The keyboard keys.
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    /// The mouse buttons.
+    mouse_buttons: Res<'w, ButtonInput<MouseButton>>,
+    /// The gamepad buttons.
+    gamepad_buttons: Res<'w, ButtonInput<GamepadButton>>,
+    /// The touches.
+    touches: Res<'w, Touches>,
+    /// The window, for the cursor position.
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    /// How the canvas fits the window, for pointer positions on the canvas.
+    canvas: Res<'w, CanvasRes>,
+    /// The UI buttons, for whether the pointer is over one.
+    buttons: Query<'w, 's, &'static Interaction, With<Button>>,
+}
+
+impl RawInputs<'_, '_> {
+    /// Checks whether a bound input was just pressed.
+    pub fn just_pressed(&self, binding: InputBinding) -> bool {
+        match binding {
+            InputBinding::Key(key) => self.keys.just_pressed(key),
+            InputBinding::Mouse(button) => self.mouse_buttons.just_pressed(button),
+            InputBinding::Gamepad(button_type) => self
+                .gamepad_buttons
+                .get_just_pressed()
+                .any(|button| button.button_type == button_type),
+            InputBinding::Touch => self.touches.any_just_pressed(),
+        }
+    }
+
+    /// Gets the position on the canvas a bound input was pressed at, if it
+    /// is a pointer.
+    pub fn pointer_position(&self, binding: InputBinding) -> Option<Vec2> {
+        let window_position = match binding {
+            InputBinding::Mouse(_) => self
+                .windows
+                .get_single()
+                .ok()
+                .and_then(Window::cursor_position),
+            InputBinding::Touch => self
+                .touches
+                .iter_just_pressed()
+                .next()
+                .map(|touch| touch.position()),
+            InputBinding::Key(_) | InputBinding::Gamepad(_) => None,
+        };
+
+        window_position.map(|position| self.canvas.canvas_position(position))
+    }
+
+    /// Checks whether the pointer is over a UI button, in which case pointer
+    /// presses go to the button rather than the scenario.
+    pub fn pointer_over_button(&self) -> bool {
+        self.buttons
+            .iter()
+            .any(|interaction| *interaction != Interaction::None)
+    }
+
+    /// Gets any input that was just pressed.
+    pub fn any_just_pressed(&self) -> Option<InputBinding> {
+        let key = self.keys.get_just_pressed().next().copied();
+        let mouse_button = self.mouse_buttons.get_just_pressed().next().copied();
+        let gamepad_button = self.gamepad_buttons.get_just_pressed().next();
+
+        key.map(InputBinding::Key)
+            .or(mouse_button.map(InputBinding::Mouse))
+            .or(gamepad_button.map(|button| InputBinding::Gamepad(button.button_type)))
+            .or(self
+                .touches
+                .any_just_pressed()
+                .then_some(InputBinding::Touch))
+    }
+}
+
+/// Turns the raw inputs bound to actions, and the number keys, into player
+/// inputs. While paused, only the inputs that work in the pause menu are
+/// sent, so that the scenario cannot be played.
+pub fn read_player_input(
+    raw_inputs: RawInputs,
+    save_data: Res<SaveDataRes>,
+    pause_state: Option<Res<State<PauseState>>>,
+    mut inputs: EventWriter<PlayerInput>,
+) {
+    let paused = pause_state.is_some_and(|pause_state| *pause_state.get() == PauseState::Paused);
+
+    for action in InputAction::ALL {
+        if paused && !matches!(action, InputAction::Confirm | InputAction::Pause) {
+            continue;
+        }
+
+        let pressed = save_data.bindings.get(action).iter().find(|binding| {
+            raw_inputs.just_pressed(**binding)
+                && !(binding.is_pointer() && raw_inputs.pointer_over_button())
+        });
+
+        if let Some(binding) = pressed {
+            inputs.send(match action {
+                InputAction::ToggleLever => {
+                    PlayerInput::ToggleLever(raw_inputs.pointer_position(*binding))
+                }
+                InputAction::NextLever => PlayerInput::NextLever,
+                InputAction::Jump => PlayerInput::Jump,
+                InputAction::LockIn => PlayerInput::LockIn,
+                InputAction::Confirm => PlayerInput::Confirm,
+                InputAction::Pause => PlayerInput::Pause,
+            });
+        }
+    }
+
+    if paused {
+        return;
+    }
+
+    for (position, key) in LEVER_POSITION_KEYS.iter().enumerate() {
+        if raw_inputs.keys.just_pressed(*key) {
+            inputs.send(PlayerInput::PointLever(LeverState(position)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_do_not_share_default_bindings() {
+        let bindings = InputBindings::default();
+
+        for (index, action) in InputAction::ALL.into_iter().enumerate() {
+            for other in &InputAction::ALL[index + 1..] {
+                let shared = bindings
+                    .get(action)
+                    .iter()
+                    .filter(|binding| bindings.get(*other).contains(binding))
+                    .collect::<Vec<_>>();
+
+                assert!(
+                    shared.is_empty(),
+                    "{:?} and {:?} share {:?}",
+                    action,
+                    other,
+                    shared
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,357 @@
+//! Locale files, which translate the text shown to the player.
+
+use crate::scenario_file::*;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// The directory containing the locale files. Each locale is named after its
+/// file, such as `en` for `en.ron`.
+pub const LOCALE_FILES_DIR: &str = "assets/locales";
+
+/// The locale used when no other is chosen, and for any text missing from the
+/// chosen locale.
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Embeds locale files into the binary.
+macro_rules! embedded_locale_files {
+    ( $( $name:literal ),+ $(,)? ) => {
+        &[ $( ($name, include_str!(concat!("../assets/locales/", $name))) ),+ ]
+    };
+}
+
+/// The locale files embedded in the binary. These are used whenever the locale
+/// directory cannot be read at runtime, such as on the web.
+const EMBEDDED_LOCALE_FILES: &[(&str, &str)] = embedded_locale_files!("en.ron", "es.ron");
+
+/// How a locale chooses between the singular and plural forms of a text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum PluralRule {
+    /// A count of exactly one is singular, such as in English.
+    OneIsSingular,
+    /// Counts of zero and one are singular, such as in French.
+    ZeroAndOneAreSingular,
+    /// Every count uses the same form, such as in Japanese.
+    NoPlural,
+}
+
+impl PluralRule {
+    /// Gets the suffix of the key of the form to use for a count.
+    const fn suffix(self, count: usize) -> &'static str {
+        let singular = match self {
+            Self::OneIsSingular => count == 1,
+            Self::ZeroAndOneAreSingular => count <= 1,
+            Self::NoPlural => false,
+        };
+
+        if singular {
+            "one"
+        } else {
+            "other"
+        }
+    }
+}
+
+/// The translations of every text into a language.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Locale {
+    /// The name of the language, written in that language.
+    pub name: String,
+    /// How the language chooses between singular and plural forms.
+    pub plural_rule: PluralRule,
+    /// The translated texts, keyed by text key. Texts with singular and plural
+    /// forms have keys ending in `.one` and `.other`.
+    pub messages: HashMap<String, String>,
+}
+
+/// An error encountered while loading locale files.
+#[derive(Debug)]
+pub enum LocaleError {
+    /// A locale file could not be read.
+    Read {
+        /// The name of the file.
+        file: String,
+        /// The underlying error.
+        error: std::io::Error,
+    },
+    /// A locale file could not be parsed.
+    Parse {
+        /// The name of the file.
+        file: String,
+        /// The underlying error.
+        error: ron::error::SpannedError,
+    },
+    /// There is no file for [`DEFAULT_LOCALE`].
+    MissingDefault,
+}
+
+impl fmt::Display for LocaleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read { file, error } => write!(f, "failed to read `{}`: {}", file, error),
+            Self::Parse { file, error } => write!(f, "failed to parse `{}`: {}", file, error),
+            Self::MissingDefault => {
+                write!(
+                    f,
+                    "there is no file for the default locale `{}`",
+                    DEFAULT_LOCALE
+                )
+            }
+        }
+    }
+}
+
+impl Error for LocaleError {}
+
+/// Every locale, and the one chosen by the player.
+#[derive(Debug, Clone)]
+pub struct Locales {
+    /// The locales, keyed by locale code.
+    locales: BTreeMap<String, Locale>,
+    /// The code of the chosen locale.
+    current: String,
+}
+
+impl Locales {
+    /// Creates the collection of locales, choosing [`DEFAULT_LOCALE`].
+    pub fn new(locales: BTreeMap<String, Locale>) -> Result<Self, LocaleError> {
+        if !locales.contains_key(DEFAULT_LOCALE) {
+            return Err(LocaleError::MissingDefault);
+        }
+
+        Ok(Self {
+            locales,
+            current: DEFAULT_LOCALE.to_owned(),
+        })
+    }
+
+    /// Gets the code and name of every locale, ordered by code.
+    pub fn available(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.locales
+            .iter()
+            .map(|(code, locale)| (code.as_str(), locale.name.as_str()))
+    }
+
+    /// Gets the code of the chosen locale.
+    pub fn current(&self) -> &str {
+        &self.current
+    }
+
+    /// Chooses the locale with the given code, returning whether it exists.
+    pub fn set_current(&mut self, code: &str) -> bool {
+        if !self.locales.contains_key(code) {
+            return false;
+        }
+
+        code.clone_into(&mut self.current);
+        true
+    }
+
+    /// Gets the chosen locale.
+    fn current_locale(&self) -> &Locale {
+        &self.locales[&self.current]
+    }
+
+    /// Gets the default locale.
+    fn default_locale(&self) -> &Locale {
+        &self.locales[DEFAULT_LOCALE]
+    }
+
+    /// Checks whether the default locale has a text for a key, or both the
+    /// singular and plural forms of one.
+    pub fn contains(&self, key: &str) -> bool {
+        let messages = &self.default_locale().messages;
+
+        messages.contains_key(key)
+            || (messages.contains_key(&format!("{}.one", key))
+                && messages.contains_key(&format!("{}.other", key)))
+    }
+
+    /// Gets the text for a key in the chosen locale, falling back to the
+    /// default locale and then to the key itself.
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        self.current_locale()
+            .messages
+            .get(key)
+            .or_else(|| self.default_locale().messages.get(key))
+            .map_or(key, String::as_str)
+    }
+
+    /// Gets the text for a key, replacing each `{name}` placeholder with the
+    /// value of the argument of that name.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        args.iter()
+            .fold(self.text(key).to_owned(), |text, (name, value)| {
+                text.replace(&format!("{{{}}}", name), &value.to_string())
+            })
+    }
+
+    /// Gets the singular or plural form of the text for a key, depending on
+    /// the count, and replaces the placeholders as in [`Self::format`]. The
+    /// count is also available as the `{count}` placeholder.
+    pub fn plural(&self, key: &str, count: usize, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let suffix = self.current_locale().plural_rule.suffix(count);
+        let mut args = args.to_vec();
+        args.push(("count", &count));
+
+        self.format(&format!("{}.{}", key, suffix), &args)
+    }
+}
+
+/// Reads the locale files in order. Files in [`LOCALE_FILES_DIR`] are
+/// preferred so that translations can be edited without recompiling; the
+/// embedded copies are used if the directory cannot be read.
+fn read_locale_files() -> Result<Vec<(String, String)>, LocaleError> {
+    let Ok(entries) = fs::read_dir(LOCALE_FILES_DIR) else {
+        return Ok(EMBEDDED_LOCALE_FILES
+            .iter()
+            .map(|(file, contents)| ((*file).to_owned(), (*contents).to_owned()))
+            .collect());
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let file = path.display().to_string();
+            fs::read_to_string(&path)
+                .map(|contents| (file.clone(), contents))
+                .map_err(|error| LocaleError::Read { file, error })
+        })
+        .collect()
+}
+
+/// Parses locale files, keying each locale by the name of its file.
+fn parse_locale_files(
+    files: Vec<(String, String)>,
+) -> Result<BTreeMap<String, Locale>, LocaleError> {
+    let options = ron_options();
+
+    files
+        .into_iter()
+        .map(|(file, contents)| {
+            let code = Path::new(&file)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default()
+                .to_owned();
+
+            options
+                .from_str::<Locale>(&contents)
+                .map(|locale| (code, locale))
+                .map_err(|error| LocaleError::Parse { file, error })
+        })
+        .collect()
+}
+
+/// Loads every locale from the locale files, choosing [`DEFAULT_LOCALE`].
+pub fn load_locales() -> Result<Locales, LocaleError> {
+    Locales::new(parse_locale_files(read_locale_files()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loads the locales embedded in the binary, choosing the given locale.
+    fn embedded_locales(code: &str) -> Locales {
+        let files = EMBEDDED_LOCALE_FILES
+            .iter()
+            .map(|(file, contents)| ((*file).to_owned(), (*contents).to_owned()))
+            .collect();
+        let mut locales = Locales::new(parse_locale_files(files).unwrap()).unwrap();
+        assert!(locales.set_current(code));
+
+        locales
+    }
+
+    #[test]
+    fn embedded_locales_are_keyed_by_file_name() {
+        let locales = embedded_locales(DEFAULT_LOCALE);
+
+        assert_eq!(
+            locales.available().collect::<Vec<_>>(),
+            [("en", "English"), ("es", "Español")]
+        );
+    }
+
+    #[test]
+    fn plurals_choose_their_form_by_count() {
+        let en = embedded_locales("en");
+
+        assert_eq!(
+            en.plural("summary.killed.people", 1, &[]),
+            "Killed 1 person"
+        );
+        assert_eq!(
+            en.plural("summary.killed.people", 0, &[]),
+            "Killed 0 people"
+        );
+        assert_eq!(
+            en.plural("summary.killed.people", 2, &[]),
+            "Killed 2 people"
+        );
+
+        let es = embedded_locales("es");
+
+        assert_eq!(
+            es.plural("summary.killed.people", 1, &[]),
+            "Mataste a 1 persona"
+        );
+        assert_eq!(
+            es.plural("summary.killed.people", 0, &[]),
+            "Mataste a 0 personas"
+        );
+    }
+
+    #[test]
+    fn plural_rules_choose_their_suffix() {
+        assert_eq!(PluralRule::OneIsSingular.suffix(0), "other");
+        assert_eq!(PluralRule::ZeroAndOneAreSingular.suffix(0), "one");
+        assert_eq!(PluralRule::ZeroAndOneAreSingular.suffix(2), "other");
+        assert_eq!(PluralRule::NoPlural.suffix(1), "other");
+    }
+
+    #[test]
+    fn placeholders_are_replaced_by_name() {
+        let en = embedded_locales("en");
+
+        assert_eq!(
+            en.plural("end.agonized", 3, &[("scenario", &"The Loop")]),
+            "Agonized most over \"The Loop\", switching 3 times"
+        );
+        assert_eq!(
+            en.format("binding.key", &[("name", &"Space"), ("unused", &1)]),
+            "Space key"
+        );
+    }
+
+    #[test]
+    fn missing_texts_fall_back_to_the_default_locale_and_then_the_key() {
+        let locale = |name: &str, messages: &[(&str, &str)]| Locale {
+            name: name.to_owned(),
+            plural_rule: PluralRule::OneIsSingular,
+            messages: messages
+                .iter()
+                .map(|(key, text)| ((*key).to_owned(), (*text).to_owned()))
+                .collect(),
+        };
+        let mut locales = Locales::new(BTreeMap::from([
+            ("en".to_owned(), locale("English", &[("menu.play", "Play")])),
+            ("es".to_owned(), locale("Español", &[])),
+        ]))
+        .unwrap();
+        assert!(locales.set_current("es"));
+
+        assert_eq!(locales.text("menu.play"), "Play");
+        assert_eq!(locales.text("missing.key"), "missing.key");
+    }
+}
@@ -10,20 +10,83 @@
 
 mod animation;
 mod assets;
+mod choice_stats;
 mod components;
 mod constants;
+mod controls_screen;
+mod easing;
 mod end_screen;
+mod frame_animation;
 mod game;
+mod input;
+mod locale;
 mod menu;
+mod pause_menu;
+mod replay;
 mod resources;
+mod save;
 mod scenario;
+mod scenario_file;
+mod settings_screen;
+mod simulation;
+mod spline;
 mod states;
-mod summary;
+mod stats_screen;
 mod util;
+mod validation;
 
+use crate::constants::*;
 use crate::game::*;
+use crate::replay::*;
+use crate::simulation::*;
 use bevy::prelude::*;
+use std::process::ExitCode;
 
-fn main() {
-    App::new().add_plugins(GamePlugin).run();
+/// Runs a headless simulation, printing the final game summary.
+fn simulate(script_path: Option<String>) -> Result<String, SimulationError> {
+    let script = match script_path {
+        Some(path) => SimulationScript::load(path)?,
+        None => SimulationScript::default(),
+    };
+    let summary = run_simulation(script)?;
+
+    ron::ser::to_string_pretty(&summary, ron::ser::PrettyConfig::default())
+        .map_err(SimulationError::Serialize)
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+
+    let game_plugin = match args.next().as_deref() {
+        Some(HEADLESS_ARG) => {
+            return match simulate(args.next()) {
+                Ok(summary) => {
+                    println!("{}", summary);
+                    ExitCode::SUCCESS
+                }
+                Err(error) => {
+                    eprintln!("{}", error);
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        Some(REPLAY_ARG) => {
+            let Some(replay_path) = args.next() else {
+                eprintln!("{} requires the path of a replay file", REPLAY_ARG);
+                return ExitCode::FAILURE;
+            };
+
+            match Replay::load(replay_path) {
+                Ok(replay) => GamePlugin::new().with_replay(replay),
+                Err(error) => {
+                    eprintln!("{}", error);
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        _ => GamePlugin::new(),
+    };
+
+    App::new().add_plugins(game_plugin).run();
+    ExitCode::SUCCESS
 }
@@ -2,10 +2,10 @@
 
 use crate::components::*;
 use crate::constants::*;
+use crate::input::*;
 use crate::resources::*;
 use crate::states::*;
 use bevy::audio::PlaybackMode;
-use bevy::audio::Volume;
 use bevy::prelude::*;
 
 /// Sets up the menu screen.
@@ -13,6 +13,8 @@ pub fn setup_menu_screen(
     mut commands: Commands,
     music_assets: Res<MusicAssetMap>,
     music: Query<&AudioSink, With<GameMusic>>,
+    settings: Res<SettingsRes>,
+    locales: Res<LocalesRes>,
 ) {
     // Spawn the menu screen text
     let text_entity = commands
@@ -43,7 +45,7 @@ pub fn setup_menu_screen(
                 .with_children(|parent| {
                     parent.spawn(
                         TextBundle::from_section(
-                            "Trolley Problem Simulator",
+                            locales.text("menu.title"),
                             TextStyle {
                                 color: Color::BLACK,
                                 font_size: 64.0,
@@ -53,27 +55,37 @@ pub fn setup_menu_screen(
                         .with_text_justify(JustifyText::Center),
                     );
 
-                    parent
-                        .spawn(ButtonBundle {
-                            style: Style {
-                                padding: UiRect::all(Val::Px(16.0)),
-                                justify_content: JustifyContent::Center,
-                                align_items: AlignItems::Center,
-                                ..default()
-                            },
-                            background_color: NORMAL_BUTTON_COLOR.into(),
-                            ..default()
-                        })
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section(
-                                "Play",
-                                TextStyle {
-                                    font_size: 40.0,
-                                    color: Color::srgb(1.0, 1.0, 1.0),
+                    for (key, action) in [
+                        ("menu.play", ButtonAction::Play),
+                        ("menu.stats", ButtonAction::ShowStats),
+                        ("menu.controls", ButtonAction::ShowControls),
+                        ("menu.settings", ButtonAction::ShowSettings),
+                    ] {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        padding: UiRect::all(Val::Px(16.0)),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    background_color: NORMAL_BUTTON_COLOR.into(),
                                     ..default()
                                 },
-                            ));
-                        });
+                                action,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    locales.text(key),
+                                    TextStyle {
+                                        font_size: 40.0,
+                                        color: Color::srgb(1.0, 1.0, 1.0),
+                                        ..default()
+                                    },
+                                ));
+                            });
+                    }
                 });
         })
         .id();
@@ -92,26 +104,29 @@ pub fn setup_menu_screen(
                 source: game_music,
                 settings: PlaybackSettings {
                     mode: PlaybackMode::Loop,
-                    volume: Volume::new(GAME_VOLUME),
+                    volume: settings.volume(AudioCategory::Music),
                     ..default()
                 },
             },
             GameMusic,
+            AudioCategory::Music,
         ));
     } else {
         music.single().play();
     }
 }
 
-/// Updates the menu screen when the "Play" button is pressed.
+/// Updates the menu screen when a button is pressed, or starts playing when
+/// the player confirms.
 pub fn update_menu_screen(
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
+        (&Interaction, &mut BackgroundColor, &ButtonAction),
         (Changed<Interaction>, With<Button>),
     >,
+    mut inputs: EventReader<PlayerInput>,
     mut next_game_state: ResMut<NextState<GameState>>,
 ) {
-    for (interaction, mut color) in &mut interaction_query {
+    for (interaction, mut color, action) in &mut interaction_query {
         match *interaction {
             Interaction::None => {
                 *color = NORMAL_BUTTON_COLOR.into();
@@ -121,10 +136,21 @@ pub fn update_menu_screen(
             }
             Interaction::Pressed => {
                 *color = PRESSED_BUTTON_COLOR.into();
-                next_game_state.set(GameState::Playing);
+
+                match action {
+                    ButtonAction::Play => next_game_state.set(GameState::Playing),
+                    ButtonAction::ShowStats => next_game_state.set(GameState::StatsScreen),
+                    ButtonAction::ShowControls => next_game_state.set(GameState::ControlsScreen),
+                    ButtonAction::ShowSettings => next_game_state.set(GameState::SettingsScreen),
+                    ButtonAction::BackToMenu => next_game_state.set(GameState::InMenu),
+                }
             }
         }
     }
+
+    if inputs.read().any(|input| *input == PlayerInput::Confirm) {
+        next_game_state.set(GameState::Playing);
+    }
 }
 
 /// Cleans up the menu screen.
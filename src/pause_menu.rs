@@ -0,0 +1,204 @@
+//! The pause menu, shown over a paused scenario.
+
+use crate::components::*;
+use crate::constants::*;
+use crate::input::*;
+use crate::resources::*;
+use crate::states::*;
+use crate::util::*;
+use bevy::prelude::*;
+
+/// Pauses or resumes the scenario when the player presses pause.
+pub fn toggle_pause(
+    mut inputs: EventReader<PlayerInput>,
+    pause_state: Res<State<PauseState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    if inputs.read().any(|input| *input == PlayerInput::Pause) {
+        next_pause_state.set(match pause_state.get() {
+            PauseState::Running => PauseState::Paused,
+            PauseState::Paused => PauseState::Running,
+        });
+    }
+}
+
+/// Freezes time and pauses the audio that is playing.
+pub fn freeze_game(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    audio: Query<(Entity, &AudioSink)>,
+) {
+    // Freezing virtual time stops every timer and animation from ticking
+    time.pause();
+
+    // Pause the audio, remembering what was playing so that audio paused for
+    // other reasons stays paused
+    for (entity, sink) in &audio {
+        if !sink.is_paused() {
+            sink.pause();
+            commands.entity(entity).insert(PausedAudio);
+        }
+    }
+}
+
+/// Unfreezes time and resumes the audio that was playing when paused.
+pub fn unfreeze_game(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    audio: Query<(Entity, &AudioSink), With<PausedAudio>>,
+) {
+    time.unpause();
+
+    for (entity, sink) in &audio {
+        sink.play();
+        commands.entity(entity).remove::<PausedAudio>();
+    }
+}
+
+/// Sets up the pause menu.
+pub fn setup_pause_menu(
+    mut commands: Commands,
+    animation_state: Res<State<AnimationState>>,
+    locales: Res<LocalesRes>,
+) {
+    // The outcome of the scenario is recorded once the trolley animation
+    // completes, so it can only be restarted before then
+    let mut buttons = vec![("pause.resume", PauseButton::Resume)];
+
+    if *animation_state.get() != AnimationState::Complete {
+        buttons.push(("pause.restart", PauseButton::Restart));
+    }
+
+    buttons.push(("pause.quit", PauseButton::Quit));
+
+    // Spawn the pause menu overlay
+    let overlay_entity = commands
+        .spawn((
+            NodeBundle {
+                background_color: PAUSE_OVERLAY_COLOR.into(),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(16.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ZIndex::Global(1),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                locales.text("pause.title"),
+                TextStyle {
+                    color: Color::WHITE,
+                    font_size: 64.0,
+                    ..default()
+                },
+            ));
+
+            for (key, button) in buttons {
+                spawn_button(parent, locales.text(key), 32.0, button);
+            }
+        })
+        .id();
+
+    // Save the entity
+    commands.insert_resource(PauseMenuEntityRes(overlay_entity));
+}
+
+/// Updates the pause menu when a button is pressed. Restarting is sent as a
+/// player input, so that replays record it like any other input.
+pub fn update_pause_menu(
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &PauseButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut inputs: EventWriter<PlayerInput>,
+    mut next_animation_state: ResMut<NextState<AnimationState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::None => {
+                *color = NORMAL_BUTTON_COLOR.into();
+            }
+            Interaction::Hovered => {
+                *color = HOVERED_BUTTON_COLOR.into();
+            }
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON_COLOR.into();
+
+                match button {
+                    PauseButton::Resume => next_pause_state.set(PauseState::Running),
+                    PauseButton::Restart => {
+                        inputs.send(PlayerInput::Restart);
+                    }
+                    PauseButton::Quit => {
+                        next_animation_state.set(AnimationState::Waiting);
+                        next_game_state.set(GameState::InMenu);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Resumes the scenario when the player confirms in the pause menu.
+pub fn resume_on_confirm(
+    mut inputs: EventReader<PlayerInput>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    if inputs.read().any(|input| *input == PlayerInput::Confirm) {
+        next_pause_state.set(PauseState::Running);
+    }
+}
+
+/// Restarts the active scenario when the player chooses to, either from the
+/// pause menu or from a replay.
+pub fn restart_on_input(
+    mut commands: Commands,
+    mut inputs: EventReader<PlayerInput>,
+    scenario_index_state: Res<State<ScenarioIndexState>>,
+    mut next_scenario_index_state: ResMut<NextState<ScenarioIndexState>>,
+    mut next_animation_state: ResMut<NextState<AnimationState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    if !inputs.read().any(|input| *input == PlayerInput::Restart) {
+        return;
+    }
+
+    // Leave the scenario, so that it is cleaned up and set up again when it
+    // is entered on the next update
+    if let Some(scenario_index) = scenario_index_state.0 {
+        commands.insert_resource(RestartScenarioRes(scenario_index));
+        next_scenario_index_state.set(ScenarioIndexState(None));
+    }
+
+    next_animation_state.set(AnimationState::Waiting);
+    next_pause_state.set(PauseState::Running);
+}
+
+/// Enters the scenario being restarted, once it has been left.
+pub fn restart_scenario(
+    mut commands: Commands,
+    restart_scenario: Res<RestartScenarioRes>,
+    mut next_scenario_index_state: ResMut<NextState<ScenarioIndexState>>,
+) {
+    next_scenario_index_state.set(ScenarioIndexState(Some(**restart_scenario)));
+    commands.remove_resource::<RestartScenarioRes>();
+}
+
+/// Cleans up the pause menu.
+pub fn cleanup_pause_menu(mut commands: Commands, entity: Res<PauseMenuEntityRes>) {
+    // Despawn the entity
+    let entity_commands = commands.entity(**entity);
+    entity_commands.despawn_recursive();
+
+    // Remove the entity resource
+    commands.remove_resource::<PauseMenuEntityRes>();
+}
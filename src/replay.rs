@@ -0,0 +1,457 @@
+//! Recording and playback of the inputs of a run.
+
+use crate::input::*;
+use crate::resources::*;
+use crate::scenario_file::*;
+use crate::states::*;
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A player input, tagged with when it happened.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedInput {
+    /// The index of the scenario the input happened in.
+    pub scenario: usize,
+    /// The number of scenarios entered so far when the input happened,
+    /// including the one it happened in. Missing from replays recorded
+    /// before it was tracked.
+    #[serde(default)]
+    pub visit: Option<usize>,
+    /// The time elapsed on the scenario timer when the input happened, in
+    /// seconds.
+    pub elapsed: f32,
+    /// The input.
+    pub input: PlayerInput,
+}
+
+/// Everything needed to play a run back exactly.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Replay {
+    /// The seed of the random number generator at the start of the run.
+    pub seed: u64,
+    /// The inputs, in the order they happened.
+    pub inputs: Vec<RecordedInput>,
+}
+
+impl Replay {
+    /// Loads a replay from a RON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let contents = fs::read_to_string(path).map_err(ReplayError::Io)?;
+
+        ron_options()
+            .from_str(&contents)
+            .map_err(ReplayError::Parse)
+    }
+
+    /// Stores the replay in a RON file, replacing any previously stored.
+    pub fn store(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(ReplayError::Serialize)?;
+
+        fs::write(path, contents).map_err(ReplayError::Io)
+    }
+}
+
+/// An error encountered while loading or storing a replay.
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The replay could not be read or written.
+    Io(std::io::Error),
+    /// The replay could not be parsed.
+    Parse(ron::error::SpannedError),
+    /// The replay could not be serialized.
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to access replay: {}", error),
+            Self::Parse(error) => write!(f, "failed to parse replay: {}", error),
+            Self::Serialize(error) => write!(f, "failed to serialize replay: {}", error),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+/// Starts recording a new run, reseeding the random number generator so that
+/// the run can be reproduced.
+fn start_recording(
+    mut commands: Commands,
+    mut rng: ResMut<RngRes>,
+    replay_file: Res<ReplayFileRes>,
+) {
+    let seed = rng.gen();
+    *rng = RngRes::seeded(seed);
+
+    let replay = Replay {
+        seed,
+        inputs: Vec::new(),
+    };
+
+    if let Err(error) = replay.store(&**replay_file) {
+        warn!("{}", error);
+    }
+
+    commands.insert_resource(ReplayRecordingRes(replay));
+}
+
+/// Resets the count of scenarios entered at the start of a run.
+fn reset_scenario_visits(mut visits: ResMut<ScenarioVisitsRes>) {
+    **visits = 0;
+}
+
+/// Counts each scenario entered, so that inputs can be told apart when the
+/// same scenario is played more than once.
+fn count_scenario_visits(
+    scenario_index_state: Res<State<ScenarioIndexState>>,
+    mut visits: ResMut<ScenarioVisitsRes>,
+) {
+    if scenario_index_state.is_changed() && scenario_index_state.0.is_some() {
+        **visits += 1;
+    }
+}
+
+/// Records the player inputs in the active scenario, storing the replay
+/// whenever there are new inputs so that it survives a crash. Pausing is not
+/// recorded, as a replay plays back without pausing.
+fn record_inputs(
+    mut inputs: EventReader<PlayerInput>,
+    mut recording: ResMut<ReplayRecordingRes>,
+    replay_file: Res<ReplayFileRes>,
+    scenario_index_state: Res<State<ScenarioIndexState>>,
+    visits: Res<ScenarioVisitsRes>,
+    timer: Option<Res<ScenarioTimer>>,
+) {
+    let (Some(scenario_index), Some(timer)) = (scenario_index_state.0, timer) else {
+        inputs.clear();
+        return;
+    };
+
+    let num_inputs = recording.inputs.len();
+
+    for input in inputs.read() {
+        if *input == PlayerInput::Pause {
+            continue;
+        }
+
+        recording.inputs.push(RecordedInput {
+            scenario: scenario_index,
+            visit: Some(**visits),
+            elapsed: timer.elapsed_secs(),
+            input: *input,
+        });
+    }
+
+    if recording.inputs.len() > num_inputs {
+        if let Err(error) = recording.store(&**replay_file) {
+            warn!("{}", error);
+        }
+    }
+}
+
+/// Starts playing back the replay from the beginning, reseeding the random
+/// number generator as it was at the start of the recorded run.
+fn start_playback(mut commands: Commands, mut playback: ResMut<ReplayPlaybackRes>) {
+    commands.insert_resource(RngRes::seeded(playback.replay.seed));
+    playback.next_input = 0;
+}
+
+/// Takes the recorded inputs due by the given time in the given visit of a
+/// scenario. Inputs from scenarios that have already ended are skipped, and
+/// playback stops if the replay turns out to have been recorded in a
+/// different scenario, as the run no longer matches the recorded one.
+fn take_due_inputs(
+    playback: &mut ReplayPlaybackRes,
+    visit: usize,
+    scenario_index: usize,
+    elapsed: f32,
+) -> Vec<PlayerInput> {
+    let mut due = Vec::new();
+    let mut skipped = 0;
+
+    while let Some(recorded) = playback.replay.inputs.get(playback.next_input) {
+        // Step 1: Skip inputs from earlier scenarios and wait for later ones.
+        // Replays without visits fall back to the scenario index alone.
+        let order = match recorded.visit {
+            Some(recorded_visit) => recorded_visit.cmp(&visit),
+            None => recorded.scenario.cmp(&scenario_index),
+        };
+
+        match order {
+            Ordering::Less => {
+                skipped += 1;
+                playback.next_input += 1;
+                continue;
+            }
+            Ordering::Greater => break,
+            Ordering::Equal => {}
+        }
+
+        // Step 2: Stop playback if the run has gone off the recorded one.
+        if recorded.scenario != scenario_index {
+            warn!(
+                "replay was recorded in scenario {} but scenario {} is playing, stopping playback",
+                recorded.scenario, scenario_index
+            );
+            playback.next_input = playback.replay.inputs.len();
+            break;
+        }
+
+        // Step 3: Send inputs once the scenario reaches their time.
+        if recorded.elapsed > elapsed {
+            break;
+        }
+
+        due.push(recorded.input);
+        playback.next_input += 1;
+    }
+
+    if skipped > 0 {
+        warn!(
+            "skipped {} replay inputs from scenarios that have already ended",
+            skipped
+        );
+    }
+
+    due
+}
+
+/// Sends the recorded inputs once the scenario timer reaches the time they
+/// happened at.
+fn play_back_inputs(
+    mut inputs: EventWriter<PlayerInput>,
+    mut playback: ResMut<ReplayPlaybackRes>,
+    scenario_index_state: Res<State<ScenarioIndexState>>,
+    visits: Res<ScenarioVisitsRes>,
+    timer: Option<Res<ScenarioTimer>>,
+) {
+    let (Some(scenario_index), Some(timer)) = (scenario_index_state.0, timer) else {
+        return;
+    };
+
+    inputs.send_batch(take_due_inputs(
+        &mut playback,
+        **visits,
+        scenario_index,
+        timer.elapsed_secs(),
+    ));
+}
+
+/// What a replay plugin does.
+enum ReplayMode {
+    /// Record the player inputs of each run to a replay file.
+    Record(PathBuf),
+    /// Play back a replay in place of the player inputs.
+    PlayBack(Replay),
+}
+
+/// A plugin that records the inputs of each run, or plays back a recorded
+/// run.
+pub struct ReplayPlugin {
+    /// Whether to record or play back.
+    mode: ReplayMode,
+}
+
+impl ReplayPlugin {
+    /// Creates a replay plugin that records each run to the given replay
+    /// file. Each run replaces the last.
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            mode: ReplayMode::Record(path.into()),
+        }
+    }
+
+    /// Creates a replay plugin that plays back the given replay on each run.
+    pub const fn play_back(replay: Replay) -> Self {
+        Self {
+            mode: ReplayMode::PlayBack(replay),
+        }
+    }
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScenarioVisitsRes>();
+        app.add_systems(OnEnter(GameState::Playing), reset_scenario_visits);
+
+        match &self.mode {
+            ReplayMode::Record(path) => {
+                app.insert_resource(ReplayFileRes(path.clone()));
+                app.add_systems(OnEnter(GameState::Playing), start_recording);
+                app.add_systems(
+                    PreUpdate,
+                    (count_scenario_visits, record_inputs)
+                        .chain()
+                        .after(read_player_input)
+                        .run_if(in_state(GameState::Playing)),
+                );
+            }
+            ReplayMode::PlayBack(replay) => {
+                app.insert_resource(ReplayPlaybackRes {
+                    replay: replay.clone(),
+                    next_input: 0,
+                });
+                app.add_systems(OnEnter(GameState::Playing), start_playback);
+                app.add_systems(
+                    PreUpdate,
+                    (
+                        count_scenario_visits,
+                        play_back_inputs.run_if(in_state(PauseState::Running)),
+                    )
+                        .chain(),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::state::app::StatesPlugin;
+    use std::time::Duration;
+
+    /// Creates an input recorded at the given time in a visit of a scenario.
+    const fn recorded(
+        scenario: usize,
+        visit: Option<usize>,
+        elapsed: f32,
+        input: PlayerInput,
+    ) -> RecordedInput {
+        RecordedInput {
+            scenario,
+            visit,
+            elapsed,
+            input,
+        }
+    }
+
+    /// Creates a playback of the given inputs from the beginning.
+    fn playback(inputs: Vec<RecordedInput>) -> ReplayPlaybackRes {
+        ReplayPlaybackRes {
+            replay: Replay { seed: 0, inputs },
+            next_input: 0,
+        }
+    }
+
+    #[test]
+    fn recorded_inputs_play_back_at_their_time() {
+        let path = std::env::temp_dir().join(format!("replay-test-{}.ron", std::process::id()));
+
+        // Step 1: Record inputs at two points of a scenario.
+        let mut app = App::new();
+        app.add_plugins(StatesPlugin)
+            .add_event::<PlayerInput>()
+            .insert_state(ScenarioIndexState(Some(2)))
+            .insert_resource(ReplayFileRes(path.clone()))
+            .insert_resource(ReplayRecordingRes(Replay {
+                seed: 7,
+                inputs: Vec::new(),
+            }))
+            .insert_resource(ScenarioVisitsRes(1))
+            .insert_resource(ScenarioTimer(Timer::from_seconds(10.0, TimerMode::Once)))
+            .add_systems(Update, record_inputs);
+
+        for (time, input) in [
+            (1.5, PlayerInput::ToggleLever(None)),
+            (1.0, PlayerInput::LockIn),
+        ] {
+            let mut timer = app.world_mut().resource_mut::<ScenarioTimer>();
+            timer.tick(Duration::from_secs_f32(time));
+            app.world_mut().send_event(input);
+            app.world_mut().send_event(PlayerInput::Pause);
+            app.update();
+        }
+
+        // Step 2: Load the stored replay back.
+        let replay = Replay::load(&path);
+        let _ = fs::remove_file(&path);
+        let replay = replay.expect("the replay should load");
+
+        assert_eq!(replay.seed, 7);
+        assert_eq!(replay.inputs.len(), 2);
+
+        // Step 3: Play it back.
+        let mut playback = ReplayPlaybackRes {
+            replay,
+            next_input: 0,
+        };
+
+        assert_eq!(take_due_inputs(&mut playback, 1, 2, 1.0), Vec::new());
+        assert_eq!(
+            take_due_inputs(&mut playback, 1, 2, 2.0),
+            vec![PlayerInput::ToggleLever(None)]
+        );
+        assert_eq!(
+            take_due_inputs(&mut playback, 1, 2, 3.0),
+            vec![PlayerInput::LockIn]
+        );
+    }
+
+    #[test]
+    fn inputs_from_ended_scenarios_are_skipped() {
+        let mut playback = playback(vec![
+            recorded(0, Some(1), 9.0, PlayerInput::ToggleLever(None)),
+            recorded(1, Some(2), 0.5, PlayerInput::Jump),
+        ]);
+
+        assert_eq!(
+            take_due_inputs(&mut playback, 2, 1, 1.0),
+            vec![PlayerInput::Jump]
+        );
+        assert_eq!(playback.next_input, 2);
+    }
+
+    #[test]
+    fn inputs_without_visits_are_matched_by_scenario() {
+        let mut playback = playback(vec![
+            recorded(0, None, 9.0, PlayerInput::ToggleLever(None)),
+            recorded(2, None, 0.5, PlayerInput::Jump),
+        ]);
+
+        assert_eq!(take_due_inputs(&mut playback, 2, 1, 1.0), Vec::new());
+        assert_eq!(playback.next_input, 1);
+        assert_eq!(
+            take_due_inputs(&mut playback, 3, 2, 1.0),
+            vec![PlayerInput::Jump]
+        );
+    }
+
+    #[test]
+    fn playback_stops_when_the_run_diverges() {
+        let mut playback = playback(vec![
+            recorded(3, Some(2), 0.5, PlayerInput::ToggleLever(None)),
+            recorded(5, Some(3), 0.5, PlayerInput::Jump),
+        ]);
+
+        assert_eq!(take_due_inputs(&mut playback, 2, 4, 1.0), Vec::new());
+        assert_eq!(playback.next_input, 2);
+    }
+
+    #[test]
+    fn restarted_scenarios_play_back_from_the_restart() {
+        let mut playback = playback(vec![
+            recorded(4, Some(5), 2.0, PlayerInput::ToggleLever(None)),
+            recorded(4, Some(5), 3.0, PlayerInput::Restart),
+            recorded(4, Some(6), 1.0, PlayerInput::LockIn),
+        ]);
+
+        assert_eq!(
+            take_due_inputs(&mut playback, 5, 4, 3.0),
+            vec![PlayerInput::ToggleLever(None), PlayerInput::Restart]
+        );
+        assert_eq!(take_due_inputs(&mut playback, 6, 4, 0.5), Vec::new());
+        assert_eq!(
+            take_due_inputs(&mut playback, 6, 4, 1.0),
+            vec![PlayerInput::LockIn]
+        );
+    }
+}
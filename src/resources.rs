@@ -1,9 +1,22 @@
 //! Handles to application resources, such as images and sounds.
 
-use crate::animation::AnimationFn;
-use crate::states::LeverState;
+use crate::animation::{AnimationFn, AnimationTarget, TimelineEvent};
+use crate::choice_stats::{ChoiceStats, RunChoices};
+use crate::constants::*;
+use crate::frame_animation::FrameAnimationConfig;
+use crate::input::InputAction;
+use crate::locale::Locales;
+use crate::replay::Replay;
+use crate::save::{SaveData, SaveStorage, Settings};
+use crate::simulation::SimulationScript;
+use crate::states::{LeverState, TrackState};
+use crate::util::{deserialize_rect, deserialize_transform};
 use bevy::prelude::*;
-use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
 
 /// Lists of loaded asset paths.
 #[derive(Resource)]
@@ -67,42 +80,257 @@ impl MusicAssetMap {
     }
 }
 
+/// A condition on the outcome of a scenario.
+#[derive(Debug, Clone, Deserialize)]
+pub enum OutcomeCondition {
+    /// The trolley took the given track.
+    Track(TrackState),
+    /// The player jumped onto the tracks.
+    SelfJumped,
+    /// The named event has happened during the game.
+    Event(String),
+    /// The named flag was raised by an animation timeline in the scenario.
+    Flag(String),
+    /// The named game summary count is at least the given value.
+    CountAtLeast(String, usize),
+    /// All of the conditions hold.
+    All(Vec<OutcomeCondition>),
+    /// Any of the conditions hold.
+    Any(Vec<OutcomeCondition>),
+    /// The condition does not hold.
+    Not(Box<OutcomeCondition>),
+}
+
+impl OutcomeCondition {
+    /// Checks whether the condition holds for the outcome of a scenario.
+    pub fn holds(
+        &self,
+        track: TrackState,
+        self_jumping: Option<&SelfJumping>,
+        flags: &HashSet<String>,
+        summary: &GameSummary,
+    ) -> bool {
+        match self {
+            Self::Track(state) => *state == track,
+            Self::SelfJumped => self_jumping.is_some_and(SelfJumping::jumped),
+            Self::Event(name) => summary.events.contains(name),
+            Self::Flag(name) => flags.contains(name),
+            Self::CountAtLeast(name, count) => {
+                summary.count(name).is_some_and(|value| value >= *count)
+            }
+            Self::All(conditions) => conditions
+                .iter()
+                .all(|condition| condition.holds(track, self_jumping, flags, summary)),
+            Self::Any(conditions) => conditions
+                .iter()
+                .any(|condition| condition.holds(track, self_jumping, flags, summary)),
+            Self::Not(condition) => !condition.holds(track, self_jumping, flags, summary),
+        }
+    }
+}
+
+/// A kind of entity that can be killed or saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum EntityKind {
+    /// People.
+    People,
+    /// Lobsters.
+    Lobsters,
+}
+
+impl EntityKind {
+    /// Every kind of entity.
+    pub const ALL: [Self; 2] = [Self::People, Self::Lobsters];
+
+    /// Gets the name of the entity kind, as used in game summary count names
+    /// and on the end screen.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::People => "people",
+            Self::Lobsters => "lobsters",
+        }
+    }
+}
+
+/// An outcome of a scenario, applied to the game summary when the trolley
+/// animation completes.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OutcomeConfig {
+    /// The condition under which this outcome applies. Every outcome whose
+    /// condition holds is applied, and an outcome without a condition always
+    /// holds.
+    #[serde(default)]
+    pub when: Option<OutcomeCondition>,
+    /// The number of entities killed, by kind.
+    #[serde(default)]
+    pub killed: HashMap<EntityKind, usize>,
+    /// The number of entities saved, by kind.
+    #[serde(default)]
+    pub saved: HashMap<EntityKind, usize>,
+    /// The names of the events that happen.
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+impl OutcomeConfig {
+    /// Applies the outcome to the game summary.
+    pub fn apply(&self, summary: &mut GameSummary) {
+        for (kind, count) in &self.killed {
+            *summary.killed.entry(*kind).or_default() += count;
+        }
+
+        for (kind, count) in &self.saved {
+            *summary.saved.entry(*kind).or_default() += count;
+        }
+
+        summary.events.extend(self.events.iter().cloned());
+    }
+}
+
+/// The scenario to go to after a scenario ends.
+#[derive(Debug, Clone, Deserialize)]
+pub enum SuccessorTarget {
+    /// The scenario with the given ID.
+    Scenario(String),
+    /// The end screen.
+    End,
+}
+
+/// A possible successor of a scenario.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SuccessorConfig {
+    /// The condition under which this successor is chosen. The first successor
+    /// whose condition holds is chosen, and a successor without a condition
+    /// always holds.
+    #[serde(default)]
+    pub when: Option<OutcomeCondition>,
+    /// The scenario to go to.
+    pub goto: SuccessorTarget,
+}
+
+/// Returns whether the lever states match a pattern of lever states. `None`
+/// matches any state, as do missing trailing entries.
+pub fn lever_states_match(pattern: &[Option<LeverState>], lever_states: &[LeverState]) -> bool {
+    pattern
+        .iter()
+        .zip(lever_states)
+        .all(|(expected, lever_state)| expected.is_none_or(|expected| expected == *lever_state))
+}
+
+/// Configuration of a single lever in a scenario.
+#[derive(Deserialize)]
+pub struct LeverConfig {
+    /// The names of the lever/player textures, one for each position of the
+    /// lever. Clicking the lever cycles through them in order.
+    pub textures: Vec<String>,
+    /// The position of the lever/player texture.
+    #[serde(default = "LeverConfig::default_pos")]
+    pub pos: Vec2,
+    /// The region in which a click switches the lever.
+    #[serde(
+        default = "LeverConfig::default_hit_rect",
+        deserialize_with = "deserialize_rect"
+    )]
+    pub hit_rect: Rect,
+}
+
+impl LeverConfig {
+    /// The default position of the lever/player texture.
+    const fn default_pos() -> Vec2 {
+        STANDARD_LEVER_POS
+    }
+
+    /// The default region in which a click switches the lever.
+    const fn default_hit_rect() -> Rect {
+        STANDARD_LEVER_HIT_RECT
+    }
+}
+
+/// Configuration of a single track in a scenario.
+#[derive(Deserialize)]
+pub struct TrackConfig {
+    /// The lever states that send the trolley down this track, one for each
+    /// lever. If empty, the track is taken when the first lever points to the
+    /// index of the track.
+    #[serde(default)]
+    pub lever_states: Vec<Option<LeverState>>,
+    /// The name of the tracks texture shown when the trolley is headed down
+    /// this track.
+    pub tracks_texture: String,
+    /// The position of the hostages on this track.
+    pub hostages_pos: Option<Vec2>,
+    /// The name of the hostages texture.
+    pub hostages_texture: Option<String>,
+    /// The number of hostages on this track.
+    #[serde(default)]
+    pub num_hostages: usize,
+    /// The overridden hostages scream sound.
+    pub scream_sound_override: Option<String>,
+    /// Whether to pause the music while the hostage scream plays.
+    pub pause_music_during_scream: Option<f32>,
+}
+
 /// Scenario configuration.
+#[derive(Deserialize)]
 pub struct ScenarioConfig {
-    /// The scenario text.
-    pub text: String,
+    /// The scenario ID, used to refer to the scenario as a successor.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// The key of the scenario text in the locale files.
+    pub text_key: String,
+    /// The key of the scenario title in the locale files.
+    pub title_key: String,
     /// The scenario duration.
     pub duration: f32,
-    /// The position of hostages on track A.
-    pub hostages_track_a_pos: Option<Vec2>,
-    /// The position of hostages on track B.
-    pub hostages_track_b_pos: Option<Vec2>,
-    /// The name of the normal track texture.
-    pub tracks_normal_texture: String,
-    /// The name of the switched track texture.
-    pub tracks_switched_texture: Option<String>,
-    /// The name of the normal lever/player texture.
-    pub lever_normal_texture: String,
-    /// The name of the switched lever/player texture.
-    pub lever_switched_texture: Option<String>,
-    /// The name of the track A hostages texture.
-    pub hostages_track_a_normal_texture: Option<String>,
-    /// The name of the track B hostages texture.
-    pub hostages_track_b_normal_texture: Option<String>,
-    /// The number of hostages on track A.
-    pub num_hostages_track_a: usize,
-    /// The number of hostages on track B.
-    pub num_hostages_track_b: usize,
+    /// The levers the player can switch.
+    pub levers: Vec<LeverConfig>,
+    /// The tracks the trolley can take. The first track whose lever states
+    /// match is taken.
+    pub tracks: Vec<TrackConfig>,
     /// The overridden trolley texture.
     pub trolley_texture_override: Option<String>,
-    /// The overridden track A hostages scream sound.
-    pub hostages_a_scream_sound_override: Option<String>,
-    /// The overridden track B hostages scream sound.
-    pub hostages_b_scream_sound_override: Option<String>,
-    /// Whether to pause the music while the track B hostage scream plays.
-    pub pause_music_during_hostages_a_scream: Option<f32>,
-    /// Whether to pause the music while the track B hostage scream plays.
-    pub pause_music_during_hostages_b_scream: Option<f32>,
+    /// The possible outcomes of the scenario.
+    #[serde(default)]
+    pub outcomes: Vec<OutcomeConfig>,
+    /// The possible successors of the scenario, in order of priority. If none
+    /// apply, the next scenario in the collection follows.
+    #[serde(default)]
+    pub next: Vec<SuccessorConfig>,
+    /// The sprite-sheet frame animations of the scenario.
+    #[serde(default)]
+    pub frame_animations: Vec<FrameAnimationConfig>,
+}
+
+impl ScenarioConfig {
+    /// Gets the configuration of a track.
+    pub fn get_track(&self, track: usize) -> &TrackConfig {
+        self.tracks.get(track).unwrap()
+    }
+
+    /// Gets the configuration of a lever.
+    pub fn get_lever(&self, lever: usize) -> &LeverConfig {
+        self.levers.get(lever).unwrap()
+    }
+
+    /// Finds the track the trolley takes given the state of every lever.
+    pub fn route(&self, lever_states: &[LeverState]) -> Option<TrackState> {
+        self.tracks
+            .iter()
+            .enumerate()
+            .position(|(track_index, track)| {
+                if track.lever_states.is_empty() {
+                    lever_states.first().copied().unwrap_or_default() == LeverState(track_index)
+                } else {
+                    lever_states_match(&track.lever_states, lever_states)
+                }
+            })
+            .map(TrackState)
+    }
+
+    /// Finds the track the trolley takes before any lever is pulled.
+    pub fn default_track(&self) -> Option<TrackState> {
+        self.route(&vec![LeverState::NORMAL; self.levers.len()])
+    }
 }
 
 /// Scenarios configuration resource.
@@ -116,30 +344,86 @@ impl ScenariosConfigRes {
     }
 }
 
+/// A successor of a scenario, resolved to a scenario index. An index past the
+/// last scenario leads to the end screen.
+pub struct ResolvedSuccessor {
+    /// The condition under which this successor is chosen.
+    pub when: Option<OutcomeCondition>,
+    /// The index of the next scenario.
+    pub scenario_index: usize,
+}
+
+/// Resource containing the state of every lever in the active scenario.
+#[derive(Resource, Deref, DerefMut)]
+pub struct LeverStatesRes(pub Vec<LeverState>);
+
+/// Resource containing the index of the lever in the active scenario that
+/// keyboard and gamepad inputs act on.
+#[derive(Resource, Deref, DerefMut, Default)]
+pub struct FocusedLeverRes(pub usize);
+
+/// Resource containing the successors of every scenario, forming the scenario
+/// graph.
+#[derive(Resource, Deref, DerefMut)]
+pub struct ScenarioGraphRes(pub Vec<Vec<ResolvedSuccessor>>);
+
 /// Scenario timer resource.
 #[derive(Resource, Deref, DerefMut)]
 pub struct ScenarioTimer(pub Timer);
 
 /// Animation node configuration.
 /// A single node in an animation. Construct this using the builder pattern.
+#[derive(Deserialize)]
 pub struct AnimationNodeConfig {
     /// The duration in seconds of this section of the animation.
     pub duration: f32,
     /// The end transformation value.
+    #[serde(deserialize_with = "deserialize_transform")]
     pub transform: Transform,
     /// The function to model this section of the animation transformation.
+    #[serde(default)]
     pub animation_fn: AnimationFn,
 }
 
+/// An event at a point in the timeline of an animation.
+#[derive(Deserialize, Clone)]
+pub struct TimedEvent {
+    /// The time in seconds since the start of the animation.
+    pub time: f32,
+    /// What happens at that time.
+    pub event: TimelineEvent,
+}
+
 /// Animation configuration.
+#[derive(Deserialize)]
 pub struct AnimationConfig {
-    /// An optional lever-state-related condition to decide whether to run the
+    /// An optional condition on the track taken to decide whether to run the
     /// animation.
-    pub lever_state_condition: Option<LeverState>,
+    pub track_condition: Option<TrackState>,
+    /// A condition on the combination of lever states to decide whether to
+    /// run the animation. If empty, the animation runs whatever the lever
+    /// states.
+    #[serde(default)]
+    pub lever_states_condition: Vec<Option<LeverState>>,
+    /// The entity moved by the animation.
+    #[serde(default)]
+    pub target: AnimationTarget,
+    /// The name of the cue that starts the animation. If unset, the animation
+    /// starts when the trolley runs, and the trolley run completes once every
+    /// such animation has finished.
+    pub cue: Option<String>,
     /// The animation start transformation.
+    #[serde(deserialize_with = "deserialize_transform")]
     pub start_transform: Transform,
     /// The collection of animation nodes.
     pub nodes: Vec<AnimationNodeConfig>,
+    /// The events at points in the timeline of the animation.
+    #[serde(default)]
+    pub timeline: Vec<TimedEvent>,
+    /// The times in seconds since the start of the animation at which its
+    /// timed actions run, in the order they were added.
+    #[serde(skip)]
+    pub action_times: Vec<f32>,
     /// The optional wounded texture.
     pub wounded_texture: Option<String>,
 }
@@ -148,9 +432,40 @@ pub struct AnimationConfig {
 #[derive(Resource, Deref, DerefMut)]
 pub struct AnimationConfigRes(pub Vec<Vec<AnimationConfig>>);
 
-/// Resource containing the timer for a section of an animation.
-#[derive(Resource, Deref, DerefMut)]
-pub struct AnimationSectionTimer(pub Timer);
+/// An animation that is currently running.
+pub struct RunningAnimation {
+    /// The index of the animation within the scenario.
+    pub animation_index: usize,
+    /// The index of the running node.
+    pub node_index: usize,
+    /// The timer for the running node.
+    pub timer: Timer,
+}
+
+/// Resource containing the animations currently running, in the order they
+/// started.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct RunningAnimationsRes(pub Vec<RunningAnimation>);
+
+/// Resource containing the flags raised by animation timelines. These are
+/// lowered when the next scenario starts.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct AnimationFlagsRes(pub HashSet<String>);
+
+/// Resource containing the state of a camera shake.
+#[derive(Resource)]
+pub struct CameraShakeRes {
+    /// The timer for the shake.
+    pub timer: Timer,
+    /// The furthest the camera moves from its resting position at the start
+    /// of the shake, in pixels.
+    pub intensity: f32,
+}
+
+/// Resource containing the index of the animation whose action is running.
+/// This is only present while the action runs.
+#[derive(Resource, Deref)]
+pub struct CurrentAnimationRes(pub usize);
 
 /// Resource containing a timer to delay the end of the scenario.
 #[derive(Resource, Deref, DerefMut)]
@@ -172,6 +487,112 @@ pub struct ScenarioExtraEntitiesRes(pub Vec<Entity>);
 #[derive(Resource, Deref, DerefMut)]
 pub struct EndScreenEntityRes(pub Entity);
 
+/// The resource containing the UI entity spawned for the stats screen.
+#[derive(Resource, Deref, DerefMut)]
+pub struct StatsScreenEntityRes(pub Entity);
+
+/// The resource containing the UI entity spawned for the controls screen.
+#[derive(Resource, Deref, DerefMut)]
+pub struct ControlsScreenEntityRes(pub Entity);
+
+/// The resource containing the UI entity spawned for the pause menu.
+#[derive(Resource, Deref, DerefMut)]
+pub struct PauseMenuEntityRes(pub Entity);
+
+/// The resource containing the index of the scenario to restart once the
+/// active scenario has been cleaned up.
+#[derive(Resource, Deref)]
+pub struct RestartScenarioRes(pub usize);
+
+/// The resource containing the UI entity spawned for the settings screen.
+#[derive(Resource, Deref, DerefMut)]
+pub struct SettingsScreenEntityRes(pub Entity);
+
+/// The resource containing how the canvas is scaled and positioned to fit the
+/// window, in logical window pixels.
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct CanvasRes {
+    /// The size of a canvas unit in logical window pixels.
+    pub scale: f32,
+    /// The position of the top left corner of the canvas in the window.
+    pub offset: Vec2,
+}
+
+impl CanvasRes {
+    /// Maps a position in the window, such as the cursor position, to the
+    /// position on the canvas.
+    pub fn canvas_position(&self, window_position: Vec2) -> Vec2 {
+        (window_position - self.offset) / self.scale
+    }
+}
+
+impl Default for CanvasRes {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            offset: Vec2::ZERO,
+        }
+    }
+}
+
+/// The resource containing the audio and display settings in effect.
+#[derive(Resource, Deref, DerefMut)]
+pub struct SettingsRes(pub Settings);
+
+/// The resource containing every locale, and the one chosen by the player.
+#[derive(Resource, Deref, DerefMut)]
+pub struct LocalesRes(pub Locales);
+
+/// The resource containing the action waiting for an input to be bound to it.
+#[derive(Resource, Deref)]
+pub struct RebindingRes(pub InputAction);
+
+/// The resource containing the statistics kept across games.
+#[derive(Resource, Deref, DerefMut)]
+pub struct SaveDataRes(pub SaveData);
+
+/// The resource containing the save data storage.
+#[derive(Resource, Deref)]
+pub struct SaveStorageRes(pub Box<dyn SaveStorage>);
+
+/// The resource tracking how the decision in the active scenario is made.
+#[derive(Resource, Deref, DerefMut)]
+pub struct DecisionMetricsRes(pub DecisionMetrics);
+
+/// The resource containing the path of the replay file.
+#[derive(Resource, Deref)]
+pub struct ReplayFileRes(pub PathBuf);
+
+/// The resource containing the replay of the run in progress.
+#[derive(Resource, Deref, DerefMut)]
+pub struct ReplayRecordingRes(pub Replay);
+
+/// The resource containing the replay being played back.
+#[derive(Resource)]
+pub struct ReplayPlaybackRes {
+    /// The replay.
+    pub replay: Replay,
+    /// The index of the next input to play back.
+    pub next_input: usize,
+}
+
+/// The resource counting the scenarios entered so far in the run in
+/// progress, including the active one and restarted ones.
+#[derive(Resource, Deref, DerefMut, Default)]
+pub struct ScenarioVisitsRes(pub usize);
+
+/// The resource containing the choices made by every recorded player.
+#[derive(Resource, Deref, DerefMut)]
+pub struct ChoiceStatsRes(pub ChoiceStats);
+
+/// The resource containing the choices made in the run in progress.
+#[derive(Resource, Deref, DerefMut)]
+pub struct RunChoicesRes(pub RunChoices);
+
+/// The resource containing the directory of the choice statistics files.
+#[derive(Resource, Deref)]
+pub struct ChoiceStatsDirRes(pub PathBuf);
+
 /// The resource for the track texture.
 #[derive(Resource, Deref, DerefMut)]
 pub struct TracksNormalRes(pub Handle<Image>);
@@ -224,6 +645,11 @@ pub struct NextPersonSwitchRes(pub bool);
 #[derive(Resource)]
 pub struct NextSwitchReachedRes;
 
+/// The marker resource to indicate that the player has locked in the lever
+/// positions in the active scenario.
+#[derive(Resource)]
+pub struct LockedInRes;
+
 /// A resource to time when the other hostages texture swap should happen.
 #[derive(Resource, Deref, DerefMut)]
 pub struct OtherHostagesTextureSwapTimerRes(pub Timer);
@@ -248,63 +674,168 @@ impl SelfJumping {
 }
 
 /// The summary of the game in progress.
-#[derive(Resource)]
+#[derive(Resource, Debug, Clone, Default, Serialize)]
 pub struct GameSummary {
-    /// Total number of people killed.
-    pub people_killed: usize,
-    /// Total number of people saved.
-    pub people_saved: usize,
-    /// Whether Hitler was killed.
-    pub killed_hitler: bool,
-    /// Whether the player got the cool hat.
-    pub got_cool_hat: bool,
-    /// Whether the player caused a preventable tragedy.
-    pub caused_preventable_tragedy: bool,
-    /// Whether the player enforced Darwinism.
-    pub enforced_darwinism: bool,
-    /// Whether the player made the trolley do a sick loop-da-loop.
-    pub did_sick_loop: bool,
-    /// Total number of lobsters killed.
-    pub lobsters_killed: usize,
-    /// Total number of lobsters saved.
-    pub lobsters_saved: usize,
-    /// Whether the player returned the shopping cart.
-    pub returned_shopping_cart: bool,
-    /// Whether the player doubled it and gave it to the next person.
-    pub doubled_it: bool,
-    /// Whether the player watched Thomas the tank engine kill people.
-    pub watched_thomas_kill_people: bool,
-    /// Whether the player contributed to the viral YouTube prank.
-    pub did_viral_prank: bool,
-    /// Whether the player killed themself.
-    pub killed_self: bool,
-    /// Whether the player solved philosophy.
-    pub solved_philosophy: bool,
+    /// The number of entities killed, by kind.
+    pub killed: BTreeMap<EntityKind, usize>,
+    /// The number of entities saved, by kind.
+    pub saved: BTreeMap<EntityKind, usize>,
+    /// The names of the events that have happened, such as killing Hitler.
+    pub events: BTreeSet<String>,
+    /// How each decision was made, in the order the scenarios were played.
+    pub decisions: Vec<DecisionMetrics>,
 }
 
 impl GameSummary {
     /// Create a new game summary with default values.
-    pub const fn new() -> Self {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the number of entities of a kind killed.
+    pub fn killed(&self, kind: EntityKind) -> usize {
+        self.killed.get(&kind).copied().unwrap_or_default()
+    }
+
+    /// Gets the number of entities of a kind saved.
+    pub fn saved(&self, kind: EntityKind) -> usize {
+        self.saved.get(&kind).copied().unwrap_or_default()
+    }
+
+    /// Gets the decision the player agonized over most, which is the one
+    /// with the most lever switches, made late if there is a tie. Decisions
+    /// without any switches are not considered.
+    pub fn most_agonized_decision(&self) -> Option<&DecisionMetrics> {
+        self.decisions
+            .iter()
+            .filter(|decision| decision.switches > 0)
+            .max_by_key(|decision| (decision.switches, decision.late))
+    }
+
+    /// Gets a game summary count by its name, such as `people_killed`.
+    pub fn count(&self, name: &str) -> Option<usize> {
+        EntityKind::ALL.into_iter().find_map(|kind| {
+            if name == format!("{}_killed", kind.name()) {
+                Some(self.killed(kind))
+            } else if name == format!("{}_saved", kind.name()) {
+                Some(self.saved(kind))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// How the player made the decision in a scenario.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DecisionMetrics {
+    /// The ID of the scenario.
+    pub scenario: String,
+    /// The key of the scenario title in the locale files.
+    pub title_key: String,
+    /// The time a lever was first switched, in seconds since the scenario
+    /// began.
+    pub first_switch: Option<f32>,
+    /// The number of times a lever was switched.
+    pub switches: usize,
+    /// The time a lever was last switched, in seconds since the scenario
+    /// began.
+    pub last_switch: Option<f32>,
+    /// Whether a lever was last switched within [`LATE_DECISION_TIME`] of the
+    /// trolley arriving.
+    pub late: bool,
+}
+
+impl DecisionMetrics {
+    /// Creates the metrics for a scenario in which no lever has been
+    /// switched yet.
+    pub fn new(scenario: impl Into<String>, title_key: impl Into<String>) -> Self {
         Self {
-            people_killed: 0,
-            people_saved: 0,
-            killed_hitler: false,
-            got_cool_hat: false,
-            caused_preventable_tragedy: false,
-            enforced_darwinism: false,
-            did_sick_loop: false,
-            lobsters_killed: 0,
-            lobsters_saved: 0,
-            returned_shopping_cart: false,
-            doubled_it: false,
-            watched_thomas_kill_people: false,
-            killed_self: false,
-            did_viral_prank: false,
-            solved_philosophy: true,
+            scenario: scenario.into(),
+            title_key: title_key.into(),
+            ..default()
+        }
+    }
+
+    /// Records a lever being switched at the given time.
+    pub fn record_switch(&mut self, elapsed: f32) {
+        self.first_switch.get_or_insert(elapsed);
+        self.switches += 1;
+        self.last_switch = Some(elapsed);
+    }
+
+    /// Decides whether the decision was late, given the duration of the
+    /// scenario.
+    pub fn finish(&mut self, duration: f32) {
+        self.late = self
+            .last_switch
+            .is_some_and(|elapsed| duration - elapsed <= LATE_DECISION_TIME);
+    }
+}
+
+/// A condition on the game summary at the end of the game.
+#[derive(Debug, Clone, Deserialize)]
+pub enum AchievementCondition {
+    /// The condition always holds.
+    Always,
+    /// The named event has happened during the game.
+    Event(String),
+    /// The named game summary count is at least the given value.
+    CountAtLeast(String, usize),
+    /// All of the conditions hold.
+    All(Vec<AchievementCondition>),
+    /// Any of the conditions hold.
+    Any(Vec<AchievementCondition>),
+    /// The condition does not hold.
+    Not(Box<AchievementCondition>),
+}
+
+impl AchievementCondition {
+    /// Checks whether the condition holds for the game summary.
+    pub fn holds(&self, summary: &GameSummary) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Event(name) => summary.events.contains(name),
+            Self::CountAtLeast(name, count) => {
+                summary.count(name).is_some_and(|value| value >= *count)
+            }
+            Self::All(conditions) => conditions.iter().all(|condition| condition.holds(summary)),
+            Self::Any(conditions) => conditions.iter().any(|condition| condition.holds(summary)),
+            Self::Not(condition) => !condition.holds(summary),
         }
     }
 }
 
+/// Achievement configuration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AchievementConfig {
+    /// The achievement ID.
+    pub id: String,
+    /// The key of the text shown on the end screen when the achievement is
+    /// unlocked, in the locale files.
+    pub text_key: String,
+    /// The name of an optional icon texture shown next to the text.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// The condition under which the achievement is unlocked.
+    pub unlock: AchievementCondition,
+}
+
+/// Resource containing every achievement, in the order they are shown.
+#[derive(Resource, Deref, DerefMut)]
+pub struct AchievementsRes(pub Vec<AchievementConfig>);
+
+impl AchievementsRes {
+    /// Gets the achievements unlocked in the game summary.
+    pub fn unlocked<'a>(
+        &'a self,
+        summary: &'a GameSummary,
+    ) -> impl Iterator<Item = &'a AchievementConfig> {
+        self.iter()
+            .filter(|achievement| achievement.unlock.holds(summary))
+    }
+}
+
 /// A timer for pausing the game music.
 #[derive(Resource, Deref, DerefMut)]
 pub struct GameMusicPauseTimerRes(pub Timer);
@@ -312,3 +843,119 @@ pub struct GameMusicPauseTimerRes(pub Timer);
 /// A marker resource indicating that the player has gotten the cool hat.
 #[derive(Resource)]
 pub struct HatAcquiredRes;
+
+/// The resource containing the script driving a headless simulation.
+#[derive(Resource, Deref)]
+pub struct SimulationScriptRes(pub SimulationScript);
+
+/// The resource containing the random number generator that all game
+/// randomness is drawn from. Given the same seed and the same inputs, a game
+/// plays out exactly the same way.
+#[derive(Resource, Deref, DerefMut)]
+pub struct RngRes(pub StdRng);
+
+impl RngRes {
+    /// Creates a random number generator from a seed.
+    pub fn seeded(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+
+    /// Creates a random number generator with a seed from the operating
+    /// system.
+    pub fn from_entropy() -> Self {
+        Self(StdRng::from_entropy())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates the metrics of a decision with the given number of switches.
+    fn decision(scenario: &str, switches: usize, late: bool) -> DecisionMetrics {
+        DecisionMetrics {
+            switches,
+            late,
+            ..DecisionMetrics::new(scenario, format!("scenario.{}.title", scenario))
+        }
+    }
+
+    #[test]
+    fn flag_conditions_hold_once_the_flag_is_raised() {
+        let condition = OutcomeCondition::All(vec![
+            OutcomeCondition::Track(TrackState(0)),
+            OutcomeCondition::Flag("looped".to_owned()),
+        ]);
+        let summary = GameSummary::default();
+
+        assert!(!condition.holds(TrackState(0), None, &HashSet::new(), &summary));
+        assert!(condition.holds(
+            TrackState(0),
+            None,
+            &HashSet::from(["looped".to_owned()]),
+            &summary
+        ));
+    }
+
+    #[test]
+    fn most_agonized_decision_has_the_most_switches() {
+        let summary = GameSummary {
+            decisions: vec![
+                decision("original", 2, true),
+                decision("age", 3, false),
+                decision("clone", 1, false),
+            ],
+            ..default()
+        };
+
+        assert_eq!(summary.most_agonized_decision().unwrap().scenario, "age");
+    }
+
+    #[test]
+    fn most_agonized_decision_ties_are_broken_by_lateness() {
+        let summary = GameSummary {
+            decisions: vec![
+                decision("original", 2, false),
+                decision("age", 2, true),
+                decision("clone", 2, false),
+            ],
+            ..default()
+        };
+
+        assert_eq!(summary.most_agonized_decision().unwrap().scenario, "age");
+    }
+
+    #[test]
+    fn decisions_without_switches_are_not_agonized_over() {
+        let summary = GameSummary {
+            decisions: vec![decision("original", 0, true), decision("age", 0, false)],
+            ..default()
+        };
+
+        assert!(summary.most_agonized_decision().is_none());
+    }
+
+    #[test]
+    fn decisions_are_late_within_the_late_decision_time() {
+        let mut decision = DecisionMetrics::new("original", "scenario.original.title");
+        decision.record_switch(1.0);
+        decision.record_switch(7.0);
+        decision.finish(10.0);
+
+        assert_eq!(decision.first_switch, Some(1.0));
+        assert_eq!(decision.switches, 2);
+        assert!(decision.late);
+
+        decision.finish(10.5);
+        assert!(!decision.late);
+    }
+
+    #[test]
+    fn decisions_without_switches_are_not_late() {
+        let mut decision = DecisionMetrics::new("original", "scenario.original.title");
+        decision.finish(10.0);
+
+        assert_eq!(decision.switches, 0);
+        assert!(!decision.late);
+    }
+}
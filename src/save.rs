@@ -0,0 +1,536 @@
+//! Persistent save data.
+
+use crate::components::*;
+use crate::constants::*;
+use crate::input::*;
+use crate::locale::*;
+use crate::resources::*;
+use crate::states::*;
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// The statistics and preferences kept across every game played.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SaveData {
+    /// The number of games played through to the end screen.
+    pub runs_completed: usize,
+    /// The total number of entities killed, by kind.
+    pub killed: BTreeMap<EntityKind, usize>,
+    /// The total number of entities saved, by kind.
+    pub saved: BTreeMap<EntityKind, usize>,
+    /// The IDs of the achievements ever unlocked.
+    pub achievements: BTreeSet<String>,
+    /// The number of times each track was taken, by scenario ID and track
+    /// index.
+    pub choices: BTreeMap<String, BTreeMap<usize, usize>>,
+    /// The inputs bound to each action.
+    #[serde(default)]
+    pub bindings: InputBindings,
+    /// The audio and display settings.
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+impl SaveData {
+    /// Gets the total number of entities of a kind killed.
+    pub fn killed(&self, kind: EntityKind) -> usize {
+        self.killed.get(&kind).copied().unwrap_or_default()
+    }
+
+    /// Gets the total number of entities of a kind saved.
+    pub fn saved(&self, kind: EntityKind) -> usize {
+        self.saved.get(&kind).copied().unwrap_or_default()
+    }
+
+    /// Records the track taken in a scenario.
+    pub fn record_choice(&mut self, scenario_id: &str, track: usize) {
+        *self
+            .choices
+            .entry(scenario_id.to_owned())
+            .or_default()
+            .entry(track)
+            .or_default() += 1;
+    }
+
+    /// Records a completed game, given its summary and the IDs of the
+    /// achievements unlocked.
+    pub fn record_run<'a>(
+        &mut self,
+        summary: &GameSummary,
+        achievements: impl IntoIterator<Item = &'a str>,
+    ) {
+        self.runs_completed += 1;
+
+        for (kind, count) in &summary.killed {
+            *self.killed.entry(*kind).or_default() += count;
+        }
+
+        for (kind, count) in &summary.saved {
+            *self.saved.entry(*kind).or_default() += count;
+        }
+
+        self.achievements
+            .extend(achievements.into_iter().map(ToOwned::to_owned));
+    }
+}
+
+/// A setting the player can change on the settings screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Setting {
+    /// The volume of all audio.
+    MasterVolume,
+    /// The volume of the music.
+    MusicVolume,
+    /// The volume of the sound effects.
+    SfxVolume,
+    /// The volume of the screams.
+    ScreamVolume,
+    /// Whether all audio is muted.
+    Muted,
+    /// Whether the game is fullscreen.
+    Fullscreen,
+}
+
+impl Setting {
+    /// The volume settings, in the order they are listed.
+    pub const VOLUMES: [Self; 4] = [
+        Self::MasterVolume,
+        Self::MusicVolume,
+        Self::SfxVolume,
+        Self::ScreamVolume,
+    ];
+
+    /// The settings that are switched on or off, in the order they are listed.
+    pub const TOGGLES: [Self; 2] = [Self::Muted, Self::Fullscreen];
+
+    /// Gets the key of the name of the setting in the locale files.
+    pub const fn name_key(self) -> &'static str {
+        match self {
+            Self::MasterVolume => "setting.master_volume",
+            Self::MusicVolume => "setting.music_volume",
+            Self::SfxVolume => "setting.sfx_volume",
+            Self::ScreamVolume => "setting.scream_volume",
+            Self::Muted => "setting.muted",
+            Self::Fullscreen => "setting.fullscreen",
+        }
+    }
+}
+
+/// The audio and display settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// The volume of all audio, from `0.0` to `1.0`.
+    pub master_volume: f32,
+    /// The volume of the music, from `0.0` to `1.0`.
+    pub music_volume: f32,
+    /// The volume of the sound effects, from `0.0` to `1.0`.
+    pub sfx_volume: f32,
+    /// The volume of the screams, from `0.0` to `1.0`.
+    pub scream_volume: f32,
+    /// Whether all audio is muted.
+    pub muted: bool,
+    /// Whether the game is fullscreen.
+    pub fullscreen: bool,
+    /// The code of the locale the game is shown in.
+    pub language: String,
+}
+
+impl Settings {
+    /// Gets the volume to play audio of a category at.
+    pub fn volume(&self, category: AudioCategory) -> Volume {
+        if self.muted {
+            return Volume::ZERO;
+        }
+
+        let category_volume = match category {
+            AudioCategory::Music => self.music_volume,
+            AudioCategory::Sfx => self.sfx_volume,
+            AudioCategory::Scream => self.scream_volume,
+        };
+
+        Volume::new(GAME_VOLUME * self.master_volume * category_volume)
+    }
+
+    /// Gets the level of a volume setting, from `0.0` to `1.0`, or `None` if
+    /// it is not a volume setting.
+    pub const fn level(&self, setting: Setting) -> Option<f32> {
+        match setting {
+            Setting::MasterVolume => Some(self.master_volume),
+            Setting::MusicVolume => Some(self.music_volume),
+            Setting::SfxVolume => Some(self.sfx_volume),
+            Setting::ScreamVolume => Some(self.scream_volume),
+            Setting::Muted | Setting::Fullscreen => None,
+        }
+    }
+
+    /// Sets the level of a volume setting, clamped from `0.0` to `1.0`. Does
+    /// nothing if it is not a volume setting.
+    pub const fn set_level(&mut self, setting: Setting, level: f32) {
+        let level = level.clamp(0.0, 1.0);
+
+        match setting {
+            Setting::MasterVolume => self.master_volume = level,
+            Setting::MusicVolume => self.music_volume = level,
+            Setting::SfxVolume => self.sfx_volume = level,
+            Setting::ScreamVolume => self.scream_volume = level,
+            Setting::Muted | Setting::Fullscreen => {}
+        }
+    }
+
+    /// Switches a toggle setting on or off. Does nothing if it is not a toggle
+    /// setting.
+    pub const fn toggle(&mut self, setting: Setting) {
+        match setting {
+            Setting::Muted => self.muted = !self.muted,
+            Setting::Fullscreen => self.fullscreen = !self.fullscreen,
+            Setting::MasterVolume
+            | Setting::MusicVolume
+            | Setting::SfxVolume
+            | Setting::ScreamVolume => {}
+        }
+    }
+
+    /// Gets the text describing the current value of a setting.
+    pub fn describe(&self, setting: Setting, locales: &Locales) -> String {
+        let name = locales.text(setting.name_key());
+
+        match setting {
+            Setting::Muted => locales.format(
+                "settings.toggle",
+                &[
+                    ("name", &name),
+                    ("value", &locales.text(on_off(self.muted))),
+                ],
+            ),
+            Setting::Fullscreen => locales.format(
+                "settings.toggle",
+                &[
+                    ("name", &name),
+                    ("value", &locales.text(on_off(self.fullscreen))),
+                ],
+            ),
+            _ => locales.format(
+                "settings.volume",
+                &[
+                    ("name", &name),
+                    (
+                        "level",
+                        &format!("{:.0}", self.level(setting).unwrap_or_default() * 100.0),
+                    ),
+                ],
+            ),
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            scream_volume: 1.0,
+            muted: false,
+            fullscreen: false,
+            language: DEFAULT_LOCALE.to_owned(),
+        }
+    }
+}
+
+/// Gets the key of the text for whether a toggle setting is on, in the locale
+/// files.
+const fn on_off(on: bool) -> &'static str {
+    if on {
+        "settings.on"
+    } else {
+        "settings.off"
+    }
+}
+
+/// An error encountered while loading or storing save data.
+#[derive(Debug)]
+pub enum SaveError {
+    /// The save data could not be read or written.
+    Io(std::io::Error),
+    /// The save data could not be parsed.
+    Parse(ron::error::SpannedError),
+    /// The save data could not be serialized.
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to access save data: {}", error),
+            Self::Parse(error) => write!(f, "failed to parse save data: {}", error),
+            Self::Serialize(error) => write!(f, "failed to serialize save data: {}", error),
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+/// A place to keep save data.
+pub trait SaveStorage: Send + Sync {
+    /// Loads the save data, if any has been stored.
+    fn load(&self) -> Result<Option<SaveData>, SaveError>;
+
+    /// Stores the save data, replacing any previously stored.
+    fn store(&self, data: &SaveData) -> Result<(), SaveError>;
+}
+
+/// Save data storage in a RON file.
+pub struct FileStorage {
+    /// The path of the save file.
+    path: PathBuf,
+}
+
+impl FileStorage {
+    /// Creates a storage backed by the file at the given path.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl SaveStorage for FileStorage {
+    fn load(&self) -> Result<Option<SaveData>, SaveError> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(SaveError::Io(error)),
+        };
+
+        ron::from_str(&contents).map(Some).map_err(SaveError::Parse)
+    }
+
+    fn store(&self, data: &SaveData) -> Result<(), SaveError> {
+        let contents = ron::ser::to_string_pretty(data, ron::ser::PrettyConfig::default())
+            .map_err(SaveError::Serialize)?;
+
+        fs::write(&self.path, contents).map_err(SaveError::Io)
+    }
+}
+
+/// Save data storage in memory, which is lost when the game closes.
+#[derive(Default)]
+pub struct MemoryStorage {
+    /// The stored save data.
+    data: Mutex<Option<SaveData>>,
+}
+
+impl SaveStorage for MemoryStorage {
+    fn load(&self) -> Result<Option<SaveData>, SaveError> {
+        Ok(self.data.lock().unwrap().clone())
+    }
+
+    fn store(&self, data: &SaveData) -> Result<(), SaveError> {
+        *self.data.lock().unwrap() = Some(data.clone());
+        Ok(())
+    }
+}
+
+/// Records the track taken in the active scenario.
+fn record_choice(
+    mut save_data: ResMut<SaveDataRes>,
+    scenarios_config: Res<ScenariosConfigRes>,
+    scenario_index_state: Res<State<ScenarioIndexState>>,
+    track: Res<State<TrackState>>,
+) {
+    let scenario_index = scenario_index_state.0.unwrap();
+    let scenario = scenarios_config.get_scenario(scenario_index);
+    let scenario_id = scenario
+        .id
+        .clone()
+        .unwrap_or_else(|| format!("#{}", scenario_index));
+
+    save_data.record_choice(&scenario_id, **track.get());
+}
+
+/// Records the completed game and stores the save data.
+fn record_run(
+    mut save_data: ResMut<SaveDataRes>,
+    storage: Res<SaveStorageRes>,
+    summary: Res<GameSummary>,
+    achievements: Res<AchievementsRes>,
+) {
+    save_data.record_run(
+        &summary,
+        achievements
+            .unlocked(&summary)
+            .map(|achievement| achievement.id.as_str()),
+    );
+
+    if let Err(error) = storage.store(&save_data) {
+        warn!("{}", error);
+    }
+}
+
+/// A plugin that keeps statistics across games in a save data storage.
+pub struct SavePlugin {
+    /// The save data storage. Normally, this could just be a
+    /// `Box<dyn SaveStorage>`, but [`Plugin::build`] takes `&self`.
+    storage: Mutex<Option<Box<dyn SaveStorage>>>,
+}
+
+impl SavePlugin {
+    /// Creates a new save plugin, given the save data storage.
+    pub fn new(storage: impl SaveStorage + 'static) -> Self {
+        Self {
+            storage: Mutex::new(Some(Box::new(storage))),
+        }
+    }
+}
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        let maybe_storage = self.storage.lock().unwrap().take();
+
+        if let Some(storage) = maybe_storage {
+            // Load the save data, starting afresh if it cannot be loaded
+            let save_data = storage.load().unwrap_or_else(|error| {
+                warn!("{}", error);
+                None
+            });
+
+            let save_data = save_data.unwrap_or_default();
+
+            app.insert_resource(SettingsRes(save_data.settings.clone()));
+            app.insert_resource(SaveDataRes(save_data));
+            app.insert_resource(SaveStorageRes(storage));
+
+            // Add systems to record choices and completed games
+            app.add_systems(
+                OnEnter(AnimationState::Complete),
+                record_choice.run_if(in_state(GameState::Playing)),
+            );
+            app.add_systems(OnEnter(GameState::EndScreen), record_run);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::EPSILON;
+
+    #[test]
+    fn save_data_round_trips_through_storage() {
+        let storage = MemoryStorage::default();
+        assert_eq!(storage.load().unwrap(), None);
+
+        let mut summary = GameSummary::new();
+        summary.killed.insert(EntityKind::People, 3);
+        summary.saved.insert(EntityKind::Lobsters, 5);
+
+        let mut data = SaveData::default();
+        data.record_run(&summary, ["killed_hitler"]);
+        data.record_choice("original", 1);
+        data.bindings
+            .rebind(InputAction::Pause, InputBinding::Key(KeyCode::KeyP));
+        data.settings.music_volume = 0.5;
+        storage.store(&data).unwrap();
+
+        assert_eq!(storage.load().unwrap(), Some(data));
+    }
+
+    #[test]
+    fn save_data_round_trips_through_file() {
+        let path = std::env::temp_dir().join(format!("trolley-save-{}.ron", std::process::id()));
+        let storage = FileStorage::new(&path);
+        assert_eq!(storage.load().unwrap(), None);
+
+        let mut data = SaveData::default();
+        data.killed.insert(EntityKind::People, 2);
+        data.achievements.insert("did_sick_loop".to_owned());
+        data.record_choice("loop", 0);
+        storage.store(&data).unwrap();
+        let loaded = storage.load();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap(), Some(data));
+    }
+
+    #[test]
+    fn save_file_contents_are_stable() {
+        let mut first = SaveData::default();
+        let mut second = SaveData::default();
+
+        // Record the same statistics in a different order
+        for kind in EntityKind::ALL {
+            first.killed.insert(kind, 1);
+            first.achievements.insert(kind.name().to_owned());
+        }
+
+        for kind in EntityKind::ALL.into_iter().rev() {
+            second.killed.insert(kind, 1);
+            second.achievements.insert(kind.name().to_owned());
+        }
+
+        for action in InputAction::ALL {
+            first
+                .bindings
+                .rebind(action, InputBinding::Key(KeyCode::KeyP));
+        }
+
+        for action in InputAction::ALL.into_iter().rev() {
+            second
+                .bindings
+                .rebind(action, InputBinding::Key(KeyCode::KeyP));
+        }
+
+        let serialize = |data: &SaveData| {
+            ron::ser::to_string_pretty(data, ron::ser::PrettyConfig::default()).unwrap()
+        };
+        let contents = serialize(&first);
+
+        assert_eq!(contents, serialize(&second));
+        assert_eq!(ron::from_str::<SaveData>(&contents).unwrap(), first);
+    }
+
+    #[test]
+    fn volume_levels_are_clamped_and_toggles_ignored() {
+        let mut settings = Settings::default();
+
+        settings.set_level(Setting::MusicVolume, 1.5);
+        settings.set_level(Setting::SfxVolume, -0.5);
+        settings.set_level(Setting::ScreamVolume, 0.25);
+        settings.set_level(Setting::Muted, 0.0);
+        settings.set_level(Setting::Fullscreen, 0.0);
+
+        assert_eq!(settings.level(Setting::MusicVolume), Some(1.0));
+        assert_eq!(settings.level(Setting::SfxVolume), Some(0.0));
+        assert_eq!(settings.level(Setting::ScreamVolume), Some(0.25));
+        assert_eq!(settings.level(Setting::Muted), None);
+        assert!(!settings.muted);
+        assert!(!settings.fullscreen);
+    }
+
+    #[test]
+    fn category_volumes_are_scaled_by_the_master_volume() {
+        let mut settings = Settings::default();
+        settings.set_level(Setting::MasterVolume, 0.5);
+        settings.set_level(Setting::MusicVolume, 0.4);
+
+        assert!((settings.volume(AudioCategory::Music).get() - GAME_VOLUME * 0.2).abs() < EPSILON);
+        assert!((settings.volume(AudioCategory::Sfx).get() - GAME_VOLUME * 0.5).abs() < EPSILON);
+
+        settings.toggle(Setting::Muted);
+
+        assert_eq!(
+            settings.volume(AudioCategory::Music).get(),
+            Volume::ZERO.get()
+        );
+        assert_eq!(
+            settings.volume(AudioCategory::Scream).get(),
+            Volume::ZERO.get()
+        );
+    }
+}
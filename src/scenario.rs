@@ -3,14 +3,18 @@
 use crate::animation::*;
 use crate::components::*;
 use crate::constants::*;
+use crate::frame_animation::*;
+use crate::input::*;
 use crate::resources::*;
+use crate::save::*;
+use crate::scenario_file::*;
 use crate::states::*;
 use crate::util::*;
-use bevy::audio::{PlaybackMode, Volume};
+use crate::validation::*;
+use bevy::audio::PlaybackMode;
 use bevy::ecs::schedule::SystemConfigs;
-use bevy::input::common_conditions::*;
 use bevy::prelude::*;
-use bevy::window::PrimaryWindow;
+use std::collections::{HashMap, HashSet};
 use std::sync::Mutex;
 use std::time::Duration;
 use typed_builder::TypedBuilder;
@@ -22,21 +26,15 @@ pub fn scenario_setup(
     scenario_index_state: Res<State<ScenarioIndexState>>,
     image_assets: Res<ImageAssetMap>,
     trolley_front_texture: Res<TrolleyFrontRes>,
-    mut next_lever_state: ResMut<NextState<LeverState>>,
+    mut next_track_state: ResMut<NextState<TrackState>>,
     hat_acquired: Option<Res<HatAcquiredRes>>,
+    locales: Res<LocalesRes>,
 ) {
     let scenario_index = scenario_index_state.0.unwrap();
     let scenario = scenarios_config.get_scenario(scenario_index);
-    let tracks_normal_texture = image_assets.get_by_name(&scenario.tracks_normal_texture);
-    let lever_player_normal_texture = image_assets.get_by_name(&scenario.lever_normal_texture);
-    let hostages_track_a_normal_texture = scenario
-        .hostages_track_a_normal_texture
-        .as_ref()
-        .map(|texture| image_assets.get_by_name(texture));
-    let hostages_track_b_normal_texture = scenario
-        .hostages_track_b_normal_texture
-        .as_ref()
-        .map(|texture| image_assets.get_by_name(texture));
+    let lever_states = vec![LeverState::NORMAL; scenario.levers.len()];
+    let track = scenario.default_track().unwrap();
+    let tracks_texture = image_assets.get_by_name(&scenario.get_track(*track).tracks_texture);
     let trolley_texture_override = scenario
         .trolley_texture_override
         .as_ref()
@@ -45,8 +43,20 @@ pub fn scenario_setup(
     let hat_texture = image_assets.get_by_name("hat");
     let duration = Duration::from_secs_f32(scenario.duration);
 
-    // Reset the lever state
-    next_lever_state.set(LeverState::Normal);
+    // Reset the lever states and focus the first lever
+    next_track_state.set(track);
+    commands.insert_resource(LeverStatesRes(lever_states));
+    commands.insert_resource(FocusedLeverRes::default());
+
+    // Start tracking how the decision is made
+    let scenario_id = scenario
+        .id
+        .clone()
+        .unwrap_or_else(|| format!("#{}", scenario_index));
+    commands.insert_resource(DecisionMetricsRes(DecisionMetrics::new(
+        scenario_id,
+        &scenario.title_key,
+    )));
 
     // Keep a collection of all entities spawned
     let mut entities = Vec::new();
@@ -56,7 +66,7 @@ pub fn scenario_setup(
         commands
             .spawn((
                 SpriteBundle {
-                    texture: tracks_normal_texture,
+                    texture: tracks_texture,
                     transform: Transform::from_xyz(0.0, 0.0, -20.0),
                     ..default()
                 },
@@ -65,19 +75,23 @@ pub fn scenario_setup(
             .id(),
     );
 
-    // Spawn the lever/player texture
-    entities.push(
-        commands
-            .spawn((
-                SpriteBundle {
-                    texture: lever_player_normal_texture,
-                    transform: Transform::from_xyz(0.0, 0.0, -10.0),
-                    ..default()
-                },
-                LeverPlayerTexture,
-            ))
-            .id(),
-    );
+    // Spawn the lever/player textures
+    for (lever_index, lever) in scenario.levers.iter().enumerate() {
+        entities.push(
+            commands
+                .spawn((
+                    SpriteBundle {
+                        texture: image_assets.get_by_name(&lever.textures[0]),
+                        transform: Transform::from_translation(
+                            normalize_translation_to_canvas_with_z(lever.pos, -10.0),
+                        ),
+                        ..default()
+                    },
+                    LeverPlayerTexture(lever_index),
+                ))
+                .id(),
+        );
+    }
 
     // Spawn the cool hat texture
     if hat_acquired.is_some() {
@@ -95,43 +109,25 @@ pub fn scenario_setup(
         );
     }
 
-    // Spawn the texture for the hostages on track A
-    if let Some(texture) = hostages_track_a_normal_texture {
-        if let Some(pos) = scenario.hostages_track_a_pos {
-            entities.push(
-                commands
-                    .spawn((
-                        SpriteBundle {
-                            texture,
-                            transform: Transform::from_translation(
-                                normalize_translation_to_canvas_with_z(pos, -10.0),
-                            ),
-                            ..default()
-                        },
-                        HostagesTrackATexture,
-                    ))
-                    .id(),
-            );
-        }
-    }
-
-    // Spawn the texture for the hostages on track B
-    if let Some(texture) = hostages_track_b_normal_texture {
-        if let Some(pos) = scenario.hostages_track_b_pos {
-            entities.push(
-                commands
-                    .spawn((
-                        SpriteBundle {
-                            texture,
-                            transform: Transform::from_translation(
-                                normalize_translation_to_canvas_with_z(pos, -10.0),
-                            ),
-                            ..default()
-                        },
-                        HostagesTrackBTexture,
-                    ))
-                    .id(),
-            );
+    // Spawn the textures for the hostages on each track
+    for (track_index, track) in scenario.tracks.iter().enumerate() {
+        if let Some(texture) = &track.hostages_texture {
+            if let Some(pos) = track.hostages_pos {
+                entities.push(
+                    commands
+                        .spawn((
+                            SpriteBundle {
+                                texture: image_assets.get_by_name(texture),
+                                transform: Transform::from_translation(
+                                    normalize_translation_to_canvas_with_z(pos, -10.0),
+                                ),
+                                ..default()
+                            },
+                            HostagesTexture(track_index),
+                        ))
+                        .id(),
+                );
+            }
         }
     }
 
@@ -172,7 +168,7 @@ pub fn scenario_setup(
             .with_children(|parent| {
                 parent.spawn(
                     TextBundle::from_section(
-                        scenario.text.clone(),
+                        locales.text(&scenario.text_key),
                         TextStyle {
                             font_size: 24.0,
                             color: Color::BLACK,
@@ -216,6 +212,30 @@ pub fn scenario_setup(
             .id(),
     );
 
+    // Spawn the lock-in button
+    entities.push(
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    padding: UiRect::all(Val::Px(8.0)),
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(0.0),
+                    left: Val::Px(0.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                spawn_button(
+                    parent,
+                    locales.text("scenario_screen.lock_in"),
+                    24.0,
+                    LockInButton,
+                );
+            })
+            .id(),
+    );
+
     // Insert a resource containing all entities spawned so we can
     // remove them later
     commands.insert_resource(ScenarioEntitiesRes(entities));
@@ -241,7 +261,9 @@ pub fn scenario_update(
     scenarios_config: Res<ScenariosConfigRes>,
     scenario_index_state: Res<State<ScenarioIndexState>>,
     audio_assets: Res<AudioAssetMap>,
+    settings: Res<SettingsRes>,
     mut scenario_entities: ResMut<ScenarioEntitiesRes>,
+    locked_in: Option<Res<LockedInRes>>,
 ) {
     let scenario_index = scenario_index_state.0.unwrap();
     let scenario = scenarios_config.get_scenario(scenario_index);
@@ -249,8 +271,16 @@ pub fn scenario_update(
 
     let previous_time_remaining = timer.remaining_secs();
 
-    // Advance the state of the timer, checking if time just ran out
-    if timer.tick(time.delta()).just_finished() {
+    // Advance the state of the timer, faster once the player has locked in,
+    // checking if time just ran out. The cues below fire whenever their time
+    // is passed, so they still fire in order however far the timer advances
+    let delta = if locked_in.is_some() {
+        time.delta().mul_f32(LOCKED_IN_SPEED)
+    } else {
+        time.delta()
+    };
+
+    if timer.tick(delta).just_finished() {
         next_animation_state.set(AnimationState::Running);
     }
 
@@ -269,12 +299,13 @@ pub fn scenario_update(
                     source: trolley_approaching_audio,
                     settings: PlaybackSettings {
                         mode: PlaybackMode::Once,
-                        volume: Volume::new(GAME_VOLUME),
+                        volume: settings.volume(AudioCategory::Sfx),
                         speed: 1.5,
                         ..default()
                     },
                 },
                 TrolleyApproachingAudio,
+                AudioCategory::Sfx,
             ))
             .id();
         scenario_entities.push(trolley_approaching_audio_entity);
@@ -321,64 +352,274 @@ pub fn scenario_update(
     }
 }
 
-/// Handles click events in a scenario.
-pub fn scenario_handle_click(
+/// An event sent whenever a lever is switched to a new position, given the
+/// index of the lever.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct LeverSwitched(pub usize);
+
+/// Switches a lever to the given position, updating its lever/player texture
+/// and the tracks texture. Nothing happens if the lever is already in that
+/// position.
+pub fn switch_lever(
+    commands: &mut Commands,
+    scenario: &ScenarioConfig,
+    lever_index: usize,
+    lever_state: LeverState,
+    lever_states: &mut LeverStatesRes,
+    next_track_state: &mut NextState<TrackState>,
+    lever_switched: &mut EventWriter<LeverSwitched>,
+    tracks_texture: &mut Handle<Image>,
+    lever_player_textures: &mut Query<
+        (&mut Handle<Image>, &LeverPlayerTexture),
+        Without<TrackTexture>,
+    >,
+    image_assets: &ImageAssetMap,
+    audio_assets: &AudioAssetMap,
+    settings: &Settings,
+) {
+    if lever_states[lever_index] == lever_state {
+        return;
+    }
+
+    let lever = scenario.get_lever(lever_index);
+    let switch_audio = audio_assets.get_by_name("switch");
+
+    commands.spawn((
+        AudioBundle {
+            source: switch_audio,
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Despawn,
+                volume: settings.volume(AudioCategory::Sfx),
+                ..default()
+            },
+        },
+        AudioCategory::Sfx,
+    ));
+
+    // Update the lever
+    lever_states[lever_index] = lever_state;
+    lever_switched.send(LeverSwitched(lever_index));
+
+    for (mut lever_player_texture, lever_player) in lever_player_textures {
+        if **lever_player == lever_index {
+            *lever_player_texture = image_assets.get_by_name(&lever.textures[*lever_state]);
+        }
+    }
+
+    // Update the track the trolley will take
+    if let Some(track) = scenario.route(lever_states) {
+        next_track_state.set(track);
+        *tracks_texture = image_assets.get_by_name(&scenario.get_track(*track).tracks_texture);
+    }
+}
+
+/// Finds the levers a toggle switches, which are every lever at the position
+/// of the toggle on the canvas, or the focused lever if it has no position.
+fn toggled_levers(
+    levers: &[LeverConfig],
+    toggle_pos: Option<Vec2>,
+    focused_lever: usize,
+) -> Vec<usize> {
+    levers
+        .iter()
+        .enumerate()
+        .filter(|(lever_index, lever)| match toggle_pos {
+            Some(toggle_pos) => lever.hit_rect.contains(toggle_pos),
+            None => *lever_index == focused_lever,
+        })
+        .map(|(lever_index, _)| lever_index)
+        .collect()
+}
+
+/// Handles lever toggles in a scenario, which cycle a lever to its next
+/// position.
+pub fn scenario_handle_lever_toggle(
     mut commands: Commands,
-    windows: Query<&Window, With<PrimaryWindow>>,
-    lever_state: Res<State<LeverState>>,
-    mut next_lever_state: ResMut<NextState<LeverState>>,
-    mut texture_set: ParamSet<(
-        Query<&mut Handle<Image>, With<TrackTexture>>,
-        Query<&mut Handle<Image>, With<LeverPlayerTexture>>,
-    )>,
+    mut inputs: EventReader<PlayerInput>,
+    mut lever_states: ResMut<LeverStatesRes>,
+    focused_lever: Res<FocusedLeverRes>,
+    mut next_track_state: ResMut<NextState<TrackState>>,
+    mut lever_switched: EventWriter<LeverSwitched>,
+    mut tracks_texture: Query<&mut Handle<Image>, With<TrackTexture>>,
+    mut lever_player_textures: Query<
+        (&mut Handle<Image>, &LeverPlayerTexture),
+        Without<TrackTexture>,
+    >,
     scenarios_config: Res<ScenariosConfigRes>,
     scenario_index_state: Res<State<ScenarioIndexState>>,
     image_assets: Res<ImageAssetMap>,
     audio_assets: Res<AudioAssetMap>,
+    settings: Res<SettingsRes>,
 ) {
     let scenario_index = scenario_index_state.0.unwrap();
     let scenario = scenarios_config.get_scenario(scenario_index);
-    let tracks_normal_texture = image_assets.get_by_name(&scenario.tracks_normal_texture);
-    let tracks_switched_texture = scenario
-        .tracks_switched_texture
-        .as_ref()
-        .map(|texture| image_assets.get_by_name(texture));
-    let lever_player_normal_texture = image_assets.get_by_name(&scenario.lever_normal_texture);
-    let lever_player_switched_texture = scenario
-        .lever_switched_texture
-        .as_ref()
-        .map(|texture| image_assets.get_by_name(texture));
-    let switch_audio = audio_assets.get_by_name("switch");
 
-    let lever_rect = Rect::new(346.0, 135.0, 410.0, 202.0);
+    for input in inputs.read() {
+        let PlayerInput::ToggleLever(toggle_pos) = *input else {
+            continue;
+        };
 
-    if let Some(mouse_pos) = windows.single().cursor_position() {
-        if lever_rect.contains(mouse_pos) {
-            commands.spawn(AudioBundle {
-                source: switch_audio,
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Despawn,
-                    volume: Volume::new(GAME_VOLUME),
-                    ..default()
-                },
-            });
-
-            match lever_state.get() {
-                LeverState::Normal => {
-                    if let Some(tracks_texture) = tracks_switched_texture {
-                        if let Some(lever_texture) = lever_player_switched_texture {
-                            next_lever_state.set(LeverState::Pulled);
-                            *texture_set.p0().single_mut() = tracks_texture;
-                            *texture_set.p1().single_mut() = lever_texture;
-                        }
-                    }
-                }
-                LeverState::Pulled => {
-                    next_lever_state.set(LeverState::Normal);
-                    *texture_set.p0().single_mut() = tracks_normal_texture;
-                    *texture_set.p1().single_mut() = lever_player_normal_texture;
-                }
-            }
+        for lever_index in toggled_levers(&scenario.levers, toggle_pos, **focused_lever) {
+            let lever = scenario.get_lever(lever_index);
+            let lever_state = lever_states[lever_index].cycled(lever.textures.len());
+
+            switch_lever(
+                &mut commands,
+                scenario,
+                lever_index,
+                lever_state,
+                &mut lever_states,
+                &mut next_track_state,
+                &mut lever_switched,
+                &mut tracks_texture.single_mut(),
+                &mut lever_player_textures,
+                &image_assets,
+                &audio_assets,
+                &settings,
+            );
+        }
+    }
+}
+
+/// Handles inputs pointing the focused lever directly to a position in a
+/// scenario.
+pub fn scenario_handle_lever_keys(
+    mut commands: Commands,
+    mut inputs: EventReader<PlayerInput>,
+    mut lever_states: ResMut<LeverStatesRes>,
+    focused_lever: Res<FocusedLeverRes>,
+    mut next_track_state: ResMut<NextState<TrackState>>,
+    mut lever_switched: EventWriter<LeverSwitched>,
+    mut tracks_texture: Query<&mut Handle<Image>, With<TrackTexture>>,
+    mut lever_player_textures: Query<
+        (&mut Handle<Image>, &LeverPlayerTexture),
+        Without<TrackTexture>,
+    >,
+    scenarios_config: Res<ScenariosConfigRes>,
+    scenario_index_state: Res<State<ScenarioIndexState>>,
+    image_assets: Res<ImageAssetMap>,
+    audio_assets: Res<AudioAssetMap>,
+    settings: Res<SettingsRes>,
+) {
+    let scenario_index = scenario_index_state.0.unwrap();
+    let scenario = scenarios_config.get_scenario(scenario_index);
+
+    for input in inputs.read() {
+        let PlayerInput::PointLever(lever_state) = *input else {
+            continue;
+        };
+
+        let Some(lever) = scenario.levers.get(**focused_lever) else {
+            continue;
+        };
+
+        if *lever_state < lever.textures.len() {
+            switch_lever(
+                &mut commands,
+                scenario,
+                **focused_lever,
+                lever_state,
+                &mut lever_states,
+                &mut next_track_state,
+                &mut lever_switched,
+                &mut tracks_texture.single_mut(),
+                &mut lever_player_textures,
+                &image_assets,
+                &audio_assets,
+                &settings,
+            );
+        }
+    }
+}
+
+/// Focuses the next lever in a scenario, wrapping around to the first.
+pub fn scenario_handle_next_lever(
+    mut inputs: EventReader<PlayerInput>,
+    mut focused_lever: ResMut<FocusedLeverRes>,
+    scenarios_config: Res<ScenariosConfigRes>,
+    scenario_index_state: Res<State<ScenarioIndexState>>,
+) {
+    let scenario_index = scenario_index_state.0.unwrap();
+    let num_levers = scenarios_config.get_scenario(scenario_index).levers.len();
+
+    for input in inputs.read() {
+        if *input == PlayerInput::NextLever && num_levers > 0 {
+            **focused_lever = (**focused_lever + 1) % num_levers;
+        }
+    }
+}
+
+/// Tints the focused lever so that the player can see which lever keyboard
+/// and gamepad inputs act on. A scenario with a single lever is left as is.
+pub fn scenario_highlight_focused_lever(
+    focused_lever: Res<FocusedLeverRes>,
+    scenarios_config: Res<ScenariosConfigRes>,
+    scenario_index_state: Res<State<ScenarioIndexState>>,
+    mut lever_sprites: Query<(&mut Sprite, &LeverPlayerTexture)>,
+) {
+    if !focused_lever.is_changed() {
+        return;
+    }
+
+    let scenario_index = scenario_index_state.0.unwrap();
+    let highlighted = scenarios_config.get_scenario(scenario_index).levers.len() > 1;
+
+    for (mut sprite, lever_player) in &mut lever_sprites {
+        sprite.color = if highlighted && **lever_player == **focused_lever {
+            FOCUSED_LEVER_COLOR
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+/// Locks in the lever positions when the player locks in, so that the levers
+/// can no longer be switched and the trolley is fast-forwarded.
+pub fn scenario_handle_lock_in(mut commands: Commands, mut inputs: EventReader<PlayerInput>) {
+    if inputs.read().any(|input| *input == PlayerInput::LockIn) {
+        commands.insert_resource(LockedInRes);
+    }
+}
+
+/// Updates the lock-in button, sending a lock-in input when it is pressed and
+/// hiding it once the levers can no longer be switched.
+pub fn scenario_update_lock_in_button(
+    mut button_query: Query<
+        (Ref<Interaction>, &mut BackgroundColor, &mut Visibility),
+        With<LockInButton>,
+    >,
+    mut inputs: EventWriter<PlayerInput>,
+    locked_in: Option<Res<LockedInRes>>,
+    playback: Option<Res<ReplayPlaybackRes>>,
+    animation_state: Res<State<AnimationState>>,
+) {
+    let Ok((interaction, mut color, mut visibility)) = button_query.get_single_mut() else {
+        return;
+    };
+
+    // A replay locks in by itself, so the button is hidden while playing back
+    if locked_in.is_some()
+        || playback.is_some()
+        || *animation_state.get() != AnimationState::Waiting
+    {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+
+    if !interaction.is_changed() {
+        return;
+    }
+
+    match *interaction {
+        Interaction::None => {
+            *color = NORMAL_BUTTON_COLOR.into();
+        }
+        Interaction::Hovered => {
+            *color = HOVERED_BUTTON_COLOR.into();
+        }
+        Interaction::Pressed => {
+            *color = PRESSED_BUTTON_COLOR.into();
+            inputs.send(PlayerInput::LockIn);
         }
     }
 }
@@ -396,6 +637,42 @@ pub fn scenario_cleanup(mut commands: Commands, entities: Res<ScenarioEntitiesRe
 
     // Remove the scenario timer
     commands.remove_resource::<ScenarioTimer>();
+
+    // Remove the lever states and the focused lever
+    commands.remove_resource::<LeverStatesRes>();
+    commands.remove_resource::<FocusedLeverRes>();
+
+    // Remove the decision metrics
+    commands.remove_resource::<DecisionMetricsRes>();
+
+    // Remove the lock-in marker
+    commands.remove_resource::<LockedInRes>();
+}
+
+/// Records the time of each lever switch in the active scenario.
+fn track_decision(
+    mut lever_switched: EventReader<LeverSwitched>,
+    mut decision: ResMut<DecisionMetricsRes>,
+    timer: Res<ScenarioTimer>,
+) {
+    for _ in lever_switched.read() {
+        decision.record_switch(timer.elapsed_secs());
+    }
+}
+
+/// Adds how the decision in the scenario was made to the game summary.
+fn record_decision(
+    scenarios_config: Res<ScenariosConfigRes>,
+    scenario_index_state: Res<State<ScenarioIndexState>>,
+    decision: Res<DecisionMetricsRes>,
+    mut summary: ResMut<GameSummary>,
+) {
+    let scenario_index = scenario_index_state.0.unwrap();
+    let scenario = scenarios_config.get_scenario(scenario_index);
+
+    let mut decision = decision.clone();
+    decision.finish(scenario.duration);
+    summary.decisions.push(decision);
 }
 
 /// Triggers when the animation has completed and prepares to go to the next
@@ -407,6 +684,36 @@ fn animation_complete(mut commands: Commands) {
     )));
 }
 
+/// Applies the outcomes of the scenario whose conditions hold to the game
+/// summary.
+fn apply_outcomes(
+    scenarios_config: Res<ScenariosConfigRes>,
+    scenario_index_state: Res<State<ScenarioIndexState>>,
+    track: Res<State<TrackState>>,
+    self_jumping: Option<Res<SelfJumping>>,
+    flags: Res<AnimationFlagsRes>,
+    mut summary: ResMut<GameSummary>,
+) {
+    let scenario_index = scenario_index_state.0.unwrap();
+    let scenario = scenarios_config.get_scenario(scenario_index);
+
+    // Decide which outcomes apply before changing the summary, so that no
+    // outcome depends on another
+    let outcomes = scenario
+        .outcomes
+        .iter()
+        .filter(|outcome| {
+            outcome.when.as_ref().is_none_or(|condition| {
+                condition.holds(**track, self_jumping.as_deref(), &flags, &summary)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for outcome in outcomes {
+        outcome.apply(&mut summary);
+    }
+}
+
 /// Sets the scenario index state once [`GameState::Playing`] is entered.
 fn set_scenario_index_state(mut next_scenario_index_state: ResMut<NextState<ScenarioIndexState>>) {
     next_scenario_index_state.set(ScenarioIndexState(Some(0)));
@@ -419,7 +726,61 @@ fn unset_scenario_index_state(
     next_scenario_index_state.set(ScenarioIndexState(None));
 }
 
-/// Immediately goes to the next scenario.
+/// Resolves the successors of every scenario into the scenario graph. A
+/// successor with an unknown scenario ID, which validation reports, leads to
+/// the next scenario in order.
+fn resolve_scenario_graph(scenarios: &[ScenarioConfig]) -> Vec<Vec<ResolvedSuccessor>> {
+    let scenario_ids = scenarios
+        .iter()
+        .enumerate()
+        .filter_map(|(index, scenario)| scenario.id.as_deref().map(|id| (id, index)))
+        .collect::<HashMap<_, _>>();
+
+    scenarios
+        .iter()
+        .enumerate()
+        .map(|(scenario_index, scenario)| {
+            scenario
+                .next
+                .iter()
+                .map(|successor| ResolvedSuccessor {
+                    when: successor.when.clone(),
+                    scenario_index: match &successor.goto {
+                        SuccessorTarget::Scenario(id) => scenario_ids
+                            .get(id.as_str())
+                            .copied()
+                            .unwrap_or(scenario_index + 1),
+                        SuccessorTarget::End => scenarios.len(),
+                    },
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Chooses the scenario to go to after a scenario ends, which is the first
+/// successor whose condition holds, or the next scenario in order if none do.
+fn choose_successor(
+    successors: &[ResolvedSuccessor],
+    scenario_index: usize,
+    track: TrackState,
+    self_jumping: Option<&SelfJumping>,
+    flags: &HashSet<String>,
+    summary: &GameSummary,
+) -> usize {
+    successors
+        .iter()
+        .find(|successor| {
+            successor
+                .when
+                .as_ref()
+                .is_none_or(|condition| condition.holds(track, self_jumping, flags, summary))
+        })
+        .map_or(scenario_index + 1, |successor| successor.scenario_index)
+}
+
+/// Goes to the next scenario once the post-animation timer finishes, following
+/// the first successor whose condition holds.
 fn post_animation_wait(
     mut commands: Commands,
     time: Res<Time>,
@@ -427,11 +788,24 @@ fn post_animation_wait(
     scenario_index_state: Res<State<ScenarioIndexState>>,
     mut next_scenario_index_state: ResMut<NextState<ScenarioIndexState>>,
     mut next_animation_state: ResMut<NextState<AnimationState>>,
+    scenario_graph: Res<ScenarioGraphRes>,
+    track: Res<State<TrackState>>,
+    self_jumping: Option<Res<SelfJumping>>,
+    flags: Res<AnimationFlagsRes>,
+    summary: Res<GameSummary>,
 ) {
     if timer.tick(time.delta()).just_finished() {
-        next_scenario_index_state.set(ScenarioIndexState(Some(
-            scenario_index_state.0.unwrap() + 1,
-        )));
+        let scenario_index = scenario_index_state.0.unwrap();
+        let next_scenario_index = choose_successor(
+            &scenario_graph[scenario_index],
+            scenario_index,
+            **track,
+            self_jumping.as_deref(),
+            &flags,
+            &summary,
+        );
+
+        next_scenario_index_state.set(ScenarioIndexState(Some(next_scenario_index)));
         next_animation_state.set(AnimationState::Waiting);
         commands.remove_resource::<PostAnimationTimer>();
     }
@@ -455,14 +829,15 @@ fn goto_end_scenario(mut next_game_state: ResMut<NextState<GameState>>) {
         self.trolley_texture_override = Some(texture.into());
     }
 
-    /// Overrides the track A hostages scream sound.
-    pub fn override_hostages_a_scream_sound(&mut self, sound: impl Into<String>) {
-        self.hostages_a_scream_sound_override = Some(sound.into());
+    /// Adds a lever to the scenario.
+    pub fn lever(&mut self, lever: LeverConfig) {
+        self.levers.push(lever);
     }
 
-    /// Overrides the track B hostages scream sound.
-    pub fn override_hostages_b_scream_sound(&mut self, sound: impl Into<String>) {
-        self.hostages_b_scream_sound_override = Some(sound.into());
+    /// Adds a track to the scenario. The first track whose lever states match
+    /// is taken.
+    pub fn track(&mut self, track: TrackConfig) {
+        self.tracks.push(track);
     }
 
     /// Configures a system to run when the scenario begins.
@@ -475,60 +850,62 @@ fn goto_end_scenario(mut next_game_state: ResMut<NextState<GameState>>) {
         self.on_update = Some(system.into_configs());
     }
 
+    /// Configures a system to run when the trolley animation completes.
+    pub fn on_complete<M>(&mut self, system: impl IntoSystemConfigs<M>) {
+        self.on_complete = Some(system.into_configs());
+    }
+
     /// Configures a system to run when the scenario ends.
     pub fn on_end<M>(&mut self, system: impl IntoSystemConfigs<M>) {
         self.on_end = Some(system.into_configs());
     }
+
+    /// Adds a possible outcome to the scenario.
+    pub fn outcome(&mut self, outcome: OutcomeConfig) {
+        self.outcomes.push(outcome);
+    }
+
+    /// Adds a possible successor to the scenario. Successors are checked in
+    /// the order they are added.
+    pub fn successor(&mut self, successor: SuccessorConfig) {
+        self.next.push(successor);
+    }
+
+    /// Adds a sprite-sheet frame animation to the scenario.
+    pub fn frame_animation(&mut self, frame_animation: FrameAnimationConfig) {
+        self.frame_animations.push(frame_animation);
+    }
 ))]
 pub struct Scenario {
-    /// The scenario text.
+    /// The scenario ID, used to refer to the scenario as a successor.
+    #[builder(default, setter(strip_option, into))]
+    id: Option<String>,
+    /// The key of the scenario text in the locale files.
     #[builder(setter(into))]
-    text: String,
+    text_key: String,
+    /// The key of the scenario title in the locale files.
+    #[builder(setter(into))]
+    title_key: String,
     /// The scenario duration.
     duration: f32,
-    /// The position of hostages on track A.
-    #[builder(default, setter(strip_option))]
-    hostages_track_a_pos: Option<Vec2>,
-    /// The position of hostages on track B.
-    #[builder(default, setter(strip_option))]
-    hostages_track_b_pos: Option<Vec2>,
-    /// The name of the normal track texture.
-    #[builder(setter(into))]
-    tracks_normal_texture: String,
-    /// The name of the switched track texture.
-    #[builder(default, setter(strip_option, into))]
-    tracks_switched_texture: Option<String>,
-    /// The name of the normal lever/player texture.
-    #[builder(setter(into))]
-    lever_normal_texture: String,
-    /// The name of the switched lever/player texture.
-    #[builder(default, setter(strip_option, into))]
-    lever_switched_texture: Option<String>,
-    /// The name of the track A hostages texture.
-    #[builder(default, setter(strip_option, into))]
-    hostages_track_a_normal_texture: Option<String>,
-    /// The name of the track B hostages texture.
-    #[builder(default, setter(strip_option, into))]
-    hostages_track_b_normal_texture: Option<String>,
-    /// The number of hostages on track A.
-    num_hostages_track_a: usize,
-    /// The number of hostages on track B.
-    num_hostages_track_b: usize,
+    /// The levers the player can switch.
+    #[builder(default, via_mutators)]
+    levers: Vec<LeverConfig>,
+    /// The tracks the trolley can take.
+    #[builder(default, via_mutators)]
+    tracks: Vec<TrackConfig>,
     /// An optional override on the trolley texture.
     #[builder(default, via_mutators)]
     trolley_texture_override: Option<String>,
-    /// An optional override on the track A hostages scream sound.
+    /// The possible outcomes of the scenario.
     #[builder(default, via_mutators)]
-    hostages_a_scream_sound_override: Option<String>,
-    /// An optional override on the track B hostages scream sound.
+    outcomes: Vec<OutcomeConfig>,
+    /// The possible successors of the scenario.
     #[builder(default, via_mutators)]
-    hostages_b_scream_sound_override: Option<String>,
-    /// Whether to pause the music while the track A hostage scream plays.
-    #[builder(default, setter(strip_option))]
-    pause_music_during_hostages_a_scream: Option<f32>,
-    /// Whether to pause the music while the track B hostage scream plays.
-    #[builder(default, setter(strip_option))]
-    pause_music_during_hostages_b_scream: Option<f32>,
+    next: Vec<SuccessorConfig>,
+    /// The sprite-sheet frame animations of the scenario.
+    #[builder(default, via_mutators)]
+    frame_animations: Vec<FrameAnimationConfig>,
     /// The collection of scenario animations.
     #[builder(default, via_mutators)]
     animations: Vec<Animation>,
@@ -538,17 +915,53 @@ pub struct Scenario {
     /// An optional system to run while the scenario is running.
     #[builder(default, via_mutators)]
     on_update: Option<SystemConfigs>,
+    /// An optional system to run when the trolley animation completes.
+    #[builder(default, via_mutators)]
+    on_complete: Option<SystemConfigs>,
     /// An optional system to run when the scenario ends.
     #[builder(default, via_mutators)]
     on_end: Option<SystemConfigs>,
 }
 
+impl Scenario {
+    /// Creates a scenario from its configuration, its animations and its
+    /// systems.
+    pub fn from_config(
+        config: ScenarioConfig,
+        animations: Vec<Animation>,
+        on_start: Option<SystemConfigs>,
+        on_update: Option<SystemConfigs>,
+        on_complete: Option<SystemConfigs>,
+        on_end: Option<SystemConfigs>,
+    ) -> Self {
+        Self {
+            id: config.id,
+            text_key: config.text_key,
+            title_key: config.title_key,
+            duration: config.duration,
+            levers: config.levers,
+            tracks: config.tracks,
+            trolley_texture_override: config.trolley_texture_override,
+            outcomes: config.outcomes,
+            next: config.next,
+            frame_animations: config.frame_animations,
+            animations,
+            on_start,
+            on_update,
+            on_complete,
+            on_end,
+        }
+    }
+}
+
 /// A plugin to simplify the configuration of trolley problem scenarios.
 /// Construct this using the builder pattern.
 pub struct ScenarioCollectionPlugin {
     /// The list of scenarios. Normally, this could just be a `Vec<Scenario>`,
     /// but [`Plugin::build`] takes `&self`.
     scenarios: Mutex<Option<Vec<Scenario>>>,
+    /// The list of achievements.
+    achievements: Vec<AchievementConfig>,
 }
 
 impl ScenarioCollectionPlugin {
@@ -560,6 +973,9 @@ impl ScenarioCollectionPlugin {
 
 impl Plugin for ScenarioCollectionPlugin {
     fn build(&self, app: &mut App) {
+        // Add the lever switch event
+        app.add_event::<LeverSwitched>();
+
         // Add scenario enter/exit systems
         app.add_systems(OnEnter(GameState::Playing), set_scenario_index_state);
         app.add_systems(OnExit(GameState::Playing), unset_scenario_index_state);
@@ -575,33 +991,25 @@ impl Plugin for ScenarioCollectionPlugin {
                 .map(|scenario| {
                     (
                         ScenarioConfig {
-                            text: scenario.text,
+                            id: scenario.id,
+                            text_key: scenario.text_key,
+                            title_key: scenario.title_key,
                             duration: scenario.duration,
-                            hostages_track_a_pos: scenario.hostages_track_a_pos,
-                            hostages_track_b_pos: scenario.hostages_track_b_pos,
-                            tracks_normal_texture: scenario.tracks_normal_texture,
-                            tracks_switched_texture: scenario.tracks_switched_texture,
-                            lever_normal_texture: scenario.lever_normal_texture,
-                            lever_switched_texture: scenario.lever_switched_texture,
-                            hostages_track_a_normal_texture: scenario
-                                .hostages_track_a_normal_texture,
-                            hostages_track_b_normal_texture: scenario
-                                .hostages_track_b_normal_texture,
-                            num_hostages_track_a: scenario.num_hostages_track_a,
-                            num_hostages_track_b: scenario.num_hostages_track_b,
+                            levers: scenario.levers,
+                            tracks: scenario.tracks,
                             trolley_texture_override: scenario.trolley_texture_override,
-                            hostages_a_scream_sound_override: scenario
-                                .hostages_a_scream_sound_override,
-                            hostages_b_scream_sound_override: scenario
-                                .hostages_b_scream_sound_override,
-                            pause_music_during_hostages_a_scream: scenario
-                                .pause_music_during_hostages_a_scream,
-                            pause_music_during_hostages_b_scream: scenario
-                                .pause_music_during_hostages_b_scream,
+                            outcomes: scenario.outcomes,
+                            next: scenario.next,
+                            frame_animations: scenario.frame_animations,
                         },
                         (
                             scenario.animations,
-                            (scenario.on_start, scenario.on_update, scenario.on_end),
+                            (
+                                scenario.on_start,
+                                scenario.on_update,
+                                scenario.on_complete,
+                                scenario.on_end,
+                            ),
                         ),
                     )
                 })
@@ -611,22 +1019,60 @@ impl Plugin for ScenarioCollectionPlugin {
                 .into_iter()
                 .unzip::<_, _, Vec<_>, Vec<_>>();
 
+            // Validate the scenario definitions before wiring up any systems
+            let animation_config = animations
+                .iter()
+                .map(|animations| animations.iter().map(Animation::config).collect())
+                .collect::<Vec<Vec<_>>>();
+
+            if let Err(report) = validate_scenarios(
+                &scenario_config,
+                &animation_config,
+                &self.achievements,
+                app.world().resource::<AssetMapPaths>(),
+                app.world().resource::<LocalesRes>(),
+            ) {
+                panic!("{}", report);
+            }
+
+            // Resolve the scenario successors into the scenario graph
+            let scenario_graph = resolve_scenario_graph(&scenario_config);
+
             for (index, scenario_systems) in systems.into_iter().enumerate() {
                 app.add_systems(OnEnter(ScenarioIndexState(Some(index))), scenario_setup)
                     .add_systems(
                         Update,
                         (
                             scenario_update.run_if(in_state(ScenarioIndexState(Some(index)))),
-                            scenario_handle_click.run_if(
+                            (
+                                scenario_handle_lever_toggle,
+                                scenario_handle_lever_keys,
+                                scenario_handle_next_lever,
+                            )
+                                .run_if(
+                                    in_state(ScenarioIndexState(Some(index)))
+                                        .and_then(in_state(AnimationState::Waiting))
+                                        .and_then(not(resource_exists::<LockedInRes>)),
+                                ),
+                            scenario_handle_lock_in.run_if(
                                 in_state(ScenarioIndexState(Some(index)))
                                     .and_then(in_state(AnimationState::Waiting))
-                                    .and_then(input_just_pressed(MouseButton::Left)),
+                                    .and_then(not(resource_exists::<LockedInRes>)),
                             ),
+                            scenario_update_lock_in_button
+                                .run_if(in_state(ScenarioIndexState(Some(index)))),
+                            scenario_highlight_focused_lever
+                                .run_if(in_state(ScenarioIndexState(Some(index)))),
                         ),
                     )
+                    .add_systems(
+                        PostUpdate,
+                        track_decision.run_if(in_state(ScenarioIndexState(Some(index)))),
+                    )
                     .add_systems(
                         OnEnter(AnimationState::Complete),
-                        animation_complete.run_if(in_state(ScenarioIndexState(Some(index)))),
+                        (animation_complete, apply_outcomes, record_decision)
+                            .run_if(in_state(ScenarioIndexState(Some(index)))),
                     )
                     .add_systems(
                         Update,
@@ -648,7 +1094,14 @@ impl Plugin for ScenarioCollectionPlugin {
                     );
                 }
 
-                if let Some(on_end) = scenario_systems.2 {
+                if let Some(on_complete) = scenario_systems.2 {
+                    app.add_systems(
+                        OnEnter(AnimationState::Complete),
+                        on_complete.run_if(in_state(ScenarioIndexState(Some(index)))),
+                    );
+                }
+
+                if let Some(on_end) = scenario_systems.3 {
                     app.add_systems(OnExit(ScenarioIndexState(Some(index))), on_end);
                 }
             }
@@ -658,8 +1111,10 @@ impl Plugin for ScenarioCollectionPlugin {
                 goto_end_scenario,
             );
 
-            // Add all scenario configurations resource
+            // Add all scenario configurations and scenario graph resources
             app.insert_resource(ScenariosConfigRes(scenario_config));
+            app.insert_resource(ScenarioGraphRes(scenario_graph));
+            app.insert_resource(AchievementsRes(self.achievements.clone()));
 
             // Add animations
             app.add_plugins(AnimationCollectionPlugin::new(animations));
@@ -671,6 +1126,8 @@ impl Plugin for ScenarioCollectionPlugin {
 pub struct ScenarioCollectionPluginBuilder {
     /// The currently configured scenarios.
     scenarios: Vec<Scenario>,
+    /// The currently configured achievements.
+    achievements: Vec<AchievementConfig>,
 }
 
 impl ScenarioCollectionPluginBuilder {
@@ -678,20 +1135,129 @@ impl ScenarioCollectionPluginBuilder {
     pub const fn new() -> Self {
         Self {
             scenarios: Vec::new(),
+            achievements: Vec::new(),
         }
     }
 
-    /// Adds a scenario to the plugin. Construct the scenario using the builder
-    /// pattern.
-    pub fn scenario(mut self, scenario: Scenario) -> Self {
-        self.scenarios.push(scenario);
-        self
+    /// Adds the scenarios defined in the scenario files, resolving named
+    /// references with the given hooks.
+    pub fn scenario_files(mut self, hooks: &ScenarioHooks) -> Result<Self, ScenarioFileError> {
+        self.scenarios.extend(load_scenarios(hooks)?);
+        Ok(self)
+    }
+
+    /// Adds the achievements defined in the achievements file.
+    pub fn achievement_file(mut self) -> Result<Self, ScenarioFileError> {
+        self.achievements.extend(load_achievements()?);
+        Ok(self)
     }
 
     /// Finalizes the scenario collection plugin.
     pub fn build(self) -> ScenarioCollectionPlugin {
         ScenarioCollectionPlugin {
             scenarios: Mutex::new(Some(self.scenarios)),
+            achievements: self.achievements,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a scenario configuration with the given ID and successors.
+    fn scenario(id: &str, next: Vec<SuccessorConfig>) -> ScenarioConfig {
+        ScenarioConfig {
+            id: Some(id.to_owned()),
+            text_key: format!("scenario.{}.text", id),
+            title_key: format!("scenario.{}.title", id),
+            duration: 10.0,
+            levers: Vec::new(),
+            tracks: Vec::new(),
+            trolley_texture_override: None,
+            outcomes: Vec::new(),
+            next,
+            frame_animations: Vec::new(),
         }
     }
+
+    /// Creates a successor going to the scenario with the given ID.
+    fn goto(when: Option<OutcomeCondition>, id: &str) -> SuccessorConfig {
+        SuccessorConfig {
+            when,
+            goto: SuccessorTarget::Scenario(id.to_owned()),
+        }
+    }
+
+    #[test]
+    fn toggles_without_a_position_switch_the_focused_lever() {
+        let lever = |min_x: f32| LeverConfig {
+            textures: vec!["lever-normal".to_owned(), "lever-switched".to_owned()],
+            pos: Vec2::new(min_x + 50.0, 300.0),
+            hit_rect: Rect::new(min_x, 250.0, min_x + 100.0, 350.0),
+        };
+        let levers = [lever(100.0), lever(400.0)];
+
+        assert_eq!(toggled_levers(&levers, None, 0), [0]);
+        assert_eq!(toggled_levers(&levers, None, 1), [1]);
+        assert_eq!(
+            toggled_levers(&levers, Some(Vec2::new(450.0, 300.0)), 0),
+            [1]
+        );
+        assert!(toggled_levers(&levers, Some(Vec2::new(50.0, 300.0)), 0).is_empty());
+    }
+
+    #[test]
+    fn successors_branch_on_their_conditions() {
+        let scenarios = [
+            scenario(
+                "fork",
+                vec![
+                    goto(Some(OutcomeCondition::Track(TrackState(1))), "pulled"),
+                    goto(Some(OutcomeCondition::Flag("looped".to_owned())), "looped"),
+                    SuccessorConfig {
+                        when: Some(OutcomeCondition::SelfJumped),
+                        goto: SuccessorTarget::End,
+                    },
+                ],
+            ),
+            scenario("not-pulled", Vec::new()),
+            scenario("pulled", Vec::new()),
+            scenario("looped", Vec::new()),
+        ];
+        let graph = resolve_scenario_graph(&scenarios);
+        let no_flags = HashSet::new();
+        let looped = HashSet::from(["looped".to_owned()]);
+        let summary = GameSummary::new();
+        let choose = |scenario_index: usize,
+                      track,
+                      self_jumping: Option<&SelfJumping>,
+                      flags: &HashSet<String>| {
+            choose_successor(
+                &graph[scenario_index],
+                scenario_index,
+                TrackState(track),
+                self_jumping,
+                flags,
+                &summary,
+            )
+        };
+
+        assert_eq!(choose(0, 1, None, &no_flags), 2);
+        assert_eq!(choose(0, 0, None, &looped), 3);
+        assert_eq!(choose(0, 0, Some(&SelfJumping::Jumping), &no_flags), 4);
+        assert_eq!(choose(0, 0, None, &no_flags), 1);
+        assert_eq!(choose(2, 0, None, &no_flags), 3);
+    }
+
+    #[test]
+    fn unknown_successor_falls_back_to_next_scenario() {
+        let scenarios = [
+            scenario("first", vec![goto(None, "missing")]),
+            scenario("second", Vec::new()),
+        ];
+        let graph = resolve_scenario_graph(&scenarios);
+
+        assert_eq!(graph[0][0].scenario_index, 1);
+    }
 }
@@ -0,0 +1,504 @@
+//! Scenario definition files.
+
+use crate::animation::*;
+use crate::resources::*;
+use crate::scenario::*;
+use bevy::ecs::schedule::{Chain, SystemConfigs};
+use bevy::prelude::*;
+use ron::extensions::Extensions;
+use ron::Options;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+
+/// The directory containing the scenario files. Scenarios are played in the
+/// order of their file names.
+pub const SCENARIO_FILES_DIR: &str = "assets/scenarios";
+
+/// The file containing the achievements. Achievements are shown in the order
+/// they are listed.
+pub const ACHIEVEMENTS_FILE: &str = "assets/achievements.ron";
+
+/// The achievements file embedded in the binary. This is used whenever the
+/// achievements file cannot be read at runtime, such as on the web.
+const EMBEDDED_ACHIEVEMENTS_FILE: &str = include_str!("../assets/achievements.ron");
+
+/// Embeds scenario files into the binary.
+macro_rules! embedded_scenario_files {
+    ( $( $name:literal ),+ $(,)? ) => {
+        &[ $( ($name, include_str!(concat!("../assets/scenarios/", $name))) ),+ ]
+    };
+}
+
+/// The scenario files embedded in the binary. These are used whenever the
+/// scenario directory cannot be read at runtime, such as on the web.
+const EMBEDDED_SCENARIO_FILES: &[(&str, &str)] = embedded_scenario_files!(
+    "01-original.ron",
+    "02-age.ron",
+    "03-clone.ron",
+    "04-cliff.ron",
+    "05-cool-hat.ron",
+    "06-victim.ron",
+    "07-darwinism.ron",
+    "08-loop.ron",
+    "09-professors.ron",
+    "10-loan-forgiveness.ron",
+    "11-lobster.ron",
+    "12-shopping-cart.ron",
+    "13-born-lever-puller.ron",
+    "14-double-it.ron",
+    "15-thomas-the-tank-engine.ron",
+    "16-youtube-prank.ron",
+    "17-self.ron",
+);
+
+/// A system hook run at a point in the timeline of an animation, as written in
+/// a scenario file.
+#[derive(Deserialize)]
+struct TimedActionDef {
+    /// The time in seconds since the start of the animation.
+    time: f32,
+    /// The name of the system hook.
+    action: String,
+}
+
+/// An animation as written in a scenario file.
+#[derive(Deserialize)]
+enum AnimationDef {
+    /// A named standard animation registered in [`ScenarioHooks`].
+    Preset {
+        /// The name of the animation.
+        name: String,
+        /// The optional wounded texture.
+        wounded_texture: Option<String>,
+    },
+    /// A fully specified animation.
+    Custom {
+        /// The animation configuration.
+        config: Box<AnimationConfig>,
+        /// The name of an optional system hook to run at the start of the
+        /// animation.
+        #[serde(default)]
+        start_action: Option<String>,
+        /// The names of system hooks to run at the end of animation nodes,
+        /// keyed by node index.
+        #[serde(default)]
+        end_actions: HashMap<usize, String>,
+        /// The system hooks to run at points in the timeline of the
+        /// animation.
+        #[serde(default)]
+        timed_actions: Vec<TimedActionDef>,
+    },
+}
+
+/// A scenario as written in a scenario file.
+#[derive(Deserialize)]
+struct ScenarioDef {
+    /// The scenario configuration.
+    config: ScenarioConfig,
+    /// The collection of scenario animations.
+    #[serde(default)]
+    animations: Vec<AnimationDef>,
+    /// The names of system hooks to run when the scenario begins.
+    #[serde(default)]
+    on_start: Vec<String>,
+    /// The names of system hooks to run while the scenario is running.
+    #[serde(default)]
+    on_update: Vec<String>,
+    /// The names of system hooks to run when the trolley animation completes.
+    #[serde(default)]
+    on_complete: Vec<String>,
+    /// The names of system hooks to run when the scenario ends.
+    #[serde(default)]
+    on_end: Vec<String>,
+}
+
+/// An error encountered while loading scenario or achievement files.
+#[derive(Debug)]
+pub enum ScenarioFileError {
+    /// A scenario or achievement file could not be read.
+    Read {
+        /// The name of the file.
+        file: String,
+        /// The underlying error.
+        error: std::io::Error,
+    },
+    /// A scenario or achievement file could not be parsed.
+    Parse {
+        /// The name of the file.
+        file: String,
+        /// The underlying error.
+        error: ron::error::SpannedError,
+    },
+    /// A scenario file refers to a system hook that has not been registered.
+    UnknownSystem {
+        /// The name of the scenario file.
+        file: String,
+        /// The name of the system hook.
+        name: String,
+    },
+    /// A scenario file refers to an animation preset that has not been
+    /// registered.
+    UnknownAnimation {
+        /// The name of the scenario file.
+        file: String,
+        /// The name of the animation preset.
+        name: String,
+    },
+    /// A scenario file gives an end action for a node that an animation does
+    /// not have.
+    UnknownNode {
+        /// The name of the scenario file.
+        file: String,
+        /// The index of the node.
+        node: usize,
+    },
+}
+
+impl fmt::Display for ScenarioFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read { file, error } => write!(f, "failed to read `{}`: {}", file, error),
+            Self::Parse { file, error } => write!(f, "failed to parse `{}`: {}", file, error),
+            Self::UnknownSystem { file, name } => {
+                write!(f, "`{}` refers to unknown system hook `{}`", file, name)
+            }
+            Self::UnknownAnimation { file, name } => {
+                write!(f, "`{}` refers to unknown animation `{}`", file, name)
+            }
+            Self::UnknownNode { file, node } => {
+                write!(f, "`{}` has an end action for unknown node {}", file, node)
+            }
+        }
+    }
+}
+
+impl Error for ScenarioFileError {}
+
+/// A factory for the systems behind a named hook.
+type SystemHook = Box<dyn Fn() -> SystemConfigs + Send + Sync>;
+
+/// A factory for a named standard animation, given an optional wounded
+/// texture.
+type AnimationPreset = Box<dyn Fn(Option<&str>) -> Animation + Send + Sync>;
+
+/// The named systems and animations that scenario files can refer to.
+/// Construct this using the builder pattern.
+#[derive(Default)]
+pub struct ScenarioHooks {
+    /// The registered system hooks.
+    systems: HashMap<String, SystemHook>,
+    /// The registered animation presets.
+    animations: HashMap<String, AnimationPreset>,
+}
+
+impl ScenarioHooks {
+    /// Creates an empty collection of hooks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a system under the given name.
+    pub fn system<M, S>(mut self, name: &str, system: S) -> Self
+    where
+        M: 'static,
+        S: IntoSystemConfigs<M> + Clone + Send + Sync + 'static,
+    {
+        self.systems.insert(
+            name.to_owned(),
+            Box::new(move || system.clone().into_configs()),
+        );
+        self
+    }
+
+    /// Registers an animation preset under the given name.
+    pub fn animation(
+        mut self,
+        name: &str,
+        animation: impl Fn(Option<&str>) -> Animation + Send + Sync + 'static,
+    ) -> Self {
+        self.animations.insert(name.to_owned(), Box::new(animation));
+        self
+    }
+
+    /// Resolves a list of system hook names into a chain of systems that run
+    /// in the listed order.
+    fn resolve_systems(
+        &self,
+        file: &str,
+        names: &[String],
+    ) -> Result<Option<SystemConfigs>, ScenarioFileError> {
+        let mut configs = names
+            .iter()
+            .map(|name| {
+                self.systems.get(name).map(|hook| hook()).ok_or_else(|| {
+                    ScenarioFileError::UnknownSystem {
+                        file: file.to_owned(),
+                        name: name.clone(),
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(match configs.len() {
+            0 => None,
+            1 => configs.pop(),
+            _ => Some(SystemConfigs::Configs {
+                configs,
+                collective_conditions: Vec::new(),
+                chained: Chain::Yes,
+            }),
+        })
+    }
+
+    /// Resolves an animation definition into an animation.
+    fn resolve_animation(
+        &self,
+        file: &str,
+        animation: AnimationDef,
+    ) -> Result<Animation, ScenarioFileError> {
+        match animation {
+            AnimationDef::Preset {
+                name,
+                wounded_texture,
+            } => self
+                .animations
+                .get(&name)
+                .map(|preset| preset(wounded_texture.as_deref()))
+                .ok_or_else(|| ScenarioFileError::UnknownAnimation {
+                    file: file.to_owned(),
+                    name,
+                }),
+            AnimationDef::Custom {
+                config,
+                start_action,
+                end_actions,
+                timed_actions,
+            } => {
+                // An end action for a node that does not exist would never run
+                if let Some(&node) = end_actions
+                    .keys()
+                    .filter(|&&node| node >= config.nodes.len())
+                    .min()
+                {
+                    return Err(ScenarioFileError::UnknownNode {
+                        file: file.to_owned(),
+                        node,
+                    });
+                }
+
+                let mut animation = Animation::new(config.start_transform).target(config.target);
+
+                if let Some(cue) = &config.cue {
+                    animation = animation.on_cue(cue);
+                }
+
+                if let Some(track) = config.track_condition {
+                    animation = animation.on_track(track);
+                }
+
+                if !config.lever_states_condition.is_empty() {
+                    animation = animation.on_lever_states(config.lever_states_condition);
+                }
+
+                if let Some(texture) = &config.wounded_texture {
+                    animation = animation.with_wounded_texture(texture);
+                }
+
+                if let Some(start_action) = self.resolve_systems(file, start_action.as_slice())? {
+                    animation = animation.with_start_action(start_action);
+                }
+
+                for timed_event in config.timeline {
+                    animation = animation.event_at(timed_event.time, timed_event.event);
+                }
+
+                for timed_action in timed_actions {
+                    if let Some(action) =
+                        self.resolve_systems(file, std::slice::from_ref(&timed_action.action))?
+                    {
+                        animation = animation.action_at(timed_action.time, action);
+                    }
+                }
+
+                for (node_index, node) in config.nodes.into_iter().enumerate() {
+                    let mut animation_node = AnimationNode::new(node.duration, node.transform)
+                        .animation_fn(node.animation_fn);
+
+                    if let Some(end_action) = end_actions.get(&node_index) {
+                        if let Some(end_action) =
+                            self.resolve_systems(file, std::slice::from_ref(end_action))?
+                        {
+                            animation_node = animation_node.end_action(end_action);
+                        }
+                    }
+
+                    animation = animation.node(animation_node);
+                }
+
+                Ok(animation)
+            }
+        }
+    }
+
+    /// Resolves a scenario definition into a scenario.
+    fn resolve_scenario(
+        &self,
+        file: &str,
+        scenario: ScenarioDef,
+    ) -> Result<Scenario, ScenarioFileError> {
+        let animations = scenario
+            .animations
+            .into_iter()
+            .map(|animation| self.resolve_animation(file, animation))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Scenario::from_config(
+            scenario.config,
+            animations,
+            self.resolve_systems(file, &scenario.on_start)?,
+            self.resolve_systems(file, &scenario.on_update)?,
+            self.resolve_systems(file, &scenario.on_complete)?,
+            self.resolve_systems(file, &scenario.on_end)?,
+        ))
+    }
+}
+
+/// Reads the scenario files in order. Files in [`SCENARIO_FILES_DIR`] are
+/// preferred so that scenarios can be edited without recompiling; the
+/// embedded copies are used if the directory cannot be read.
+fn read_scenario_files() -> Result<Vec<(String, String)>, ScenarioFileError> {
+    let Ok(entries) = fs::read_dir(SCENARIO_FILES_DIR) else {
+        return Ok(EMBEDDED_SCENARIO_FILES
+            .iter()
+            .map(|(file, contents)| ((*file).to_owned(), (*contents).to_owned()))
+            .collect());
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let file = path.display().to_string();
+            fs::read_to_string(&path)
+                .map(|contents| (file.clone(), contents))
+                .map_err(|error| ScenarioFileError::Read { file, error })
+        })
+        .collect()
+}
+
+/// Gets the options used to parse scenario and achievement files.
+pub fn ron_options() -> Options {
+    Options::default().with_default_extension(Extensions::IMPLICIT_SOME)
+}
+
+/// Loads all scenarios from the scenario files, resolving named references
+/// with the given hooks.
+pub fn load_scenarios(hooks: &ScenarioHooks) -> Result<Vec<Scenario>, ScenarioFileError> {
+    let options = ron_options();
+
+    read_scenario_files()?
+        .into_iter()
+        .map(|(file, contents)| {
+            let scenario = options
+                .from_str::<ScenarioDef>(&contents)
+                .map_err(|error| ScenarioFileError::Parse {
+                    file: file.clone(),
+                    error,
+                })?;
+            hooks.resolve_scenario(&file, scenario)
+        })
+        .collect()
+}
+
+/// Loads all achievements from the achievements file. [`ACHIEVEMENTS_FILE`] is
+/// preferred so that achievements can be edited without recompiling; the
+/// embedded copy is used if the file cannot be read.
+pub fn load_achievements() -> Result<Vec<AchievementConfig>, ScenarioFileError> {
+    let contents = fs::read_to_string(ACHIEVEMENTS_FILE)
+        .unwrap_or_else(|_| EMBEDDED_ACHIEVEMENTS_FILE.to_owned());
+
+    ron_options()
+        .from_str(&contents)
+        .map_err(|error| ScenarioFileError::Parse {
+            file: ACHIEVEMENTS_FILE.to_owned(),
+            error,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A system hook that does nothing.
+    const fn noop() {}
+
+    /// Resolves a scenario file with a single animation, given the actions
+    /// of the animation as written in the file.
+    fn resolve(actions: &str) -> Result<Scenario, ScenarioFileError> {
+        let contents = format!(
+            r#"(
+                config: (
+                    text_key: "scenario.text",
+                    title_key: "scenario.title",
+                    duration: 10.0,
+                    levers: [],
+                    tracks: [],
+                ),
+                animations: [
+                    Custom(
+                        config: (
+                            start_transform: (translation: (0.0, 0.0)),
+                            nodes: [(duration: 1.0, transform: (translation: (100.0, 0.0)))],
+                        ),
+                        {}
+                    ),
+                ],
+            )"#,
+            actions
+        );
+        let scenario = ron_options().from_str(&contents).unwrap();
+
+        ScenarioHooks::new()
+            .system("noop", noop)
+            .resolve_scenario("test.ron", scenario)
+    }
+
+    #[test]
+    fn registered_actions_are_resolved() {
+        let scenario = resolve(
+            r#"start_action: "noop", end_actions: {0: "noop"}, timed_actions: [(time: 0.5, action: "noop")],"#,
+        );
+
+        assert!(scenario.is_ok());
+    }
+
+    #[test]
+    fn unregistered_actions_are_rejected() {
+        for actions in [
+            r#"start_action: "missing","#,
+            r#"end_actions: {0: "missing"},"#,
+            r#"timed_actions: [(time: 0.5, action: "missing")],"#,
+        ] {
+            assert!(matches!(
+                resolve(actions),
+                Err(ScenarioFileError::UnknownSystem { name, .. }) if name == "missing"
+            ));
+        }
+    }
+
+    #[test]
+    fn end_actions_for_unknown_nodes_are_rejected() {
+        assert!(matches!(
+            resolve(r#"end_actions: {1: "noop"},"#),
+            Err(ScenarioFileError::UnknownNode { node: 1, .. })
+        ));
+    }
+}
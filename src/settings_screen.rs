@@ -0,0 +1,340 @@
+//! The settings screen.
+
+use crate::components::*;
+use crate::constants::*;
+use crate::input::*;
+use crate::locale::*;
+use crate::resources::*;
+use crate::save::*;
+use crate::states::*;
+use crate::util::*;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+
+/// Spawns the settings screen text, returning its entity.
+fn spawn_settings_screen(
+    commands: &mut Commands,
+    settings: &Settings,
+    locales: &Locales,
+) -> Entity {
+    commands
+        .spawn(NodeBundle {
+            background_color: Color::WHITE.into(),
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(75.0),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(16.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            locales.text("settings.title"),
+                            TextStyle {
+                                color: Color::BLACK,
+                                font_size: 32.0,
+                                ..default()
+                            },
+                        )
+                        .with_text_justify(JustifyText::Center),
+                    );
+
+                    // Show a slider for each volume setting
+                    for setting in Setting::VOLUMES {
+                        let level = settings.level(setting).unwrap_or_default();
+
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    width: Val::Percent(100.0),
+                                    flex_direction: FlexDirection::Column,
+                                    row_gap: Val::Px(4.0),
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    TextBundle::from_section(
+                                        settings.describe(setting, locales),
+                                        TextStyle {
+                                            color: Color::BLACK,
+                                            font_size: 24.0,
+                                            ..default()
+                                        },
+                                    ),
+                                    SettingText(setting),
+                                ));
+
+                                parent
+                                    .spawn((
+                                        ButtonBundle {
+                                            style: Style {
+                                                width: Val::Percent(100.0),
+                                                height: Val::Px(16.0),
+                                                ..default()
+                                            },
+                                            background_color: SLIDER_TRACK_COLOR.into(),
+                                            ..default()
+                                        },
+                                        RelativeCursorPosition::default(),
+                                        SettingSlider(setting),
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            NodeBundle {
+                                                style: Style {
+                                                    width: Val::Percent(level * 100.0),
+                                                    height: Val::Percent(100.0),
+                                                    ..default()
+                                                },
+                                                background_color: NORMAL_BUTTON_COLOR.into(),
+                                                ..default()
+                                            },
+                                            SettingSliderFill(setting),
+                                        ));
+                                    });
+                            });
+                    }
+
+                    // Show a button for each toggle setting
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                column_gap: Val::Px(16.0),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for setting in Setting::TOGGLES {
+                                parent
+                                    .spawn((
+                                        ButtonBundle {
+                                            style: Style {
+                                                padding: UiRect::all(Val::Px(16.0)),
+                                                justify_content: JustifyContent::Center,
+                                                align_items: AlignItems::Center,
+                                                ..default()
+                                            },
+                                            background_color: NORMAL_BUTTON_COLOR.into(),
+                                            ..default()
+                                        },
+                                        SettingToggle(setting),
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            TextBundle::from_section(
+                                                settings.describe(setting, locales),
+                                                TextStyle {
+                                                    font_size: 24.0,
+                                                    color: Color::srgb(1.0, 1.0, 1.0),
+                                                    ..default()
+                                                },
+                                            ),
+                                            SettingText(setting),
+                                        ));
+                                    });
+                            }
+                        });
+
+                    // Show a button for each locale, highlighting the chosen
+                    // one
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                align_items: AlignItems::Center,
+                                column_gap: Val::Px(16.0),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                locales.text("settings.language"),
+                                TextStyle {
+                                    color: Color::BLACK,
+                                    font_size: 24.0,
+                                    ..default()
+                                },
+                            ));
+
+                            for (code, name) in locales.available() {
+                                let text = if code == locales.current() {
+                                    format!("{} {}", BULLET_POINT, name)
+                                } else {
+                                    name.to_owned()
+                                };
+
+                                spawn_button(parent, &text, 24.0, LanguageButton(code.to_owned()));
+                            }
+                        });
+
+                    spawn_button(
+                        parent,
+                        locales.text("menu.back"),
+                        32.0,
+                        ButtonAction::BackToMenu,
+                    );
+                });
+        })
+        .id()
+}
+
+/// Sets up the settings screen.
+pub fn setup_settings_screen(
+    mut commands: Commands,
+    settings: Res<SettingsRes>,
+    locales: Res<LocalesRes>,
+) {
+    let text_entity = spawn_settings_screen(&mut commands, &settings, &locales);
+
+    // Save the entity
+    commands.insert_resource(SettingsScreenEntityRes(text_entity));
+}
+
+/// Updates the settings when a slider is dragged or a toggle is pressed, and
+/// goes back to the menu screen when the "Back to menu" button is pressed or
+/// the player confirms.
+pub fn update_settings_screen(
+    mut interaction_query: Query<
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            Option<&SettingToggle>,
+            Option<&LanguageButton>,
+        ),
+        (Changed<Interaction>, With<Button>, Without<SettingSlider>),
+    >,
+    slider_query: Query<(&Interaction, &RelativeCursorPosition, &SettingSlider)>,
+    mut inputs: EventReader<PlayerInput>,
+    mut settings: ResMut<SettingsRes>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, mut color, toggle, language) in &mut interaction_query {
+        match *interaction {
+            Interaction::None => {
+                *color = NORMAL_BUTTON_COLOR.into();
+            }
+            Interaction::Hovered => {
+                *color = HOVERED_BUTTON_COLOR.into();
+            }
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON_COLOR.into();
+
+                match (toggle, language) {
+                    (Some(toggle), _) => settings.toggle(**toggle),
+                    (_, Some(language)) => {
+                        if settings.language != **language {
+                            settings.language.clone_from(language);
+                        }
+                    }
+                    (None, None) => next_game_state.set(GameState::InMenu),
+                }
+            }
+        }
+    }
+
+    // Set the level of a slider being dragged from where the cursor is,
+    // rounded to the nearest percent
+    for (interaction, cursor_position, slider) in &slider_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let Some(normalized) = cursor_position.normalized else {
+            continue;
+        };
+
+        let level = (normalized.x.clamp(0.0, 1.0) * 100.0).round() / 100.0;
+
+        if settings.level(**slider) != Some(level) {
+            settings.set_level(**slider, level);
+        }
+    }
+
+    if inputs.read().any(|input| *input == PlayerInput::Confirm) {
+        next_game_state.set(GameState::InMenu);
+    }
+}
+
+/// Updates the setting texts and slider fills when the settings change.
+pub fn update_setting_widgets(
+    mut text_query: Query<(&mut Text, &SettingText)>,
+    mut fill_query: Query<(&mut Style, &SettingSliderFill)>,
+    settings: Res<SettingsRes>,
+    locales: Res<LocalesRes>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for (mut text, setting) in &mut text_query {
+        text.sections[0].value = settings.describe(**setting, &locales);
+    }
+
+    for (mut style, setting) in &mut fill_query {
+        style.width = Val::Percent(settings.level(**setting).unwrap_or_default() * 100.0);
+    }
+}
+
+/// Spawns the settings screen again when another locale is chosen, so that it
+/// is shown in the new language.
+pub fn refresh_settings_screen(
+    mut commands: Commands,
+    entity: Res<SettingsScreenEntityRes>,
+    settings: Res<SettingsRes>,
+    locales: Res<LocalesRes>,
+) {
+    // The screen has just been spawned in the chosen locale when the entity
+    // resource is added
+    if !locales.is_changed() || entity.is_added() {
+        return;
+    }
+
+    commands.entity(**entity).despawn_recursive();
+
+    let text_entity = spawn_settings_screen(&mut commands, &settings, &locales);
+    commands.insert_resource(SettingsScreenEntityRes(text_entity));
+}
+
+/// Cleans up the settings screen, storing the settings if they changed.
+pub fn cleanup_settings_screen(
+    mut commands: Commands,
+    entity: Res<SettingsScreenEntityRes>,
+    settings: Res<SettingsRes>,
+    mut save_data: ResMut<SaveDataRes>,
+    storage: Res<SaveStorageRes>,
+) {
+    // Despawn the entity
+    let entity_commands = commands.entity(**entity);
+    entity_commands.despawn_recursive();
+
+    // Remove the entity resource
+    commands.remove_resource::<SettingsScreenEntityRes>();
+
+    // Store the settings
+    if save_data.settings != **settings {
+        save_data.settings = settings.clone();
+
+        if let Err(error) = storage.store(&save_data) {
+            warn!("{}", error);
+        }
+    }
+}
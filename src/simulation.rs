@@ -0,0 +1,281 @@
+//! Headless simulation of full playthroughs.
+
+use crate::components::*;
+use crate::constants::*;
+use crate::game::*;
+use crate::resources::*;
+use crate::scenario::*;
+use crate::scenario_file::*;
+use crate::states::*;
+use bevy::audio::AudioSource;
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// The decision made in a scenario of a simulation.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ScriptedDecision {
+    /// The positions to switch the levers to, by lever index. Levers without
+    /// a position are left alone.
+    #[serde(default)]
+    pub levers: Vec<LeverState>,
+    /// Whether the player jumps onto the track.
+    #[serde(default)]
+    pub jump: bool,
+    /// Whether the player locks in the lever positions, fast-forwarding the
+    /// trolley.
+    #[serde(default)]
+    pub lock_in: bool,
+}
+
+/// The decisions made over the course of a simulation. Scenarios without a
+/// decision are left to play out without touching the levers.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SimulationScript {
+    /// The seed of the random number generator.
+    #[serde(default)]
+    pub seed: u64,
+    /// The decisions, by scenario ID.
+    #[serde(default)]
+    pub decisions: HashMap<String, ScriptedDecision>,
+}
+
+impl SimulationScript {
+    /// Loads a simulation script from a RON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SimulationError> {
+        let contents = fs::read_to_string(path).map_err(SimulationError::Io)?;
+
+        ron_options()
+            .from_str(&contents)
+            .map_err(SimulationError::Parse)
+    }
+}
+
+/// An error encountered while running a simulation.
+#[derive(Debug)]
+pub enum SimulationError {
+    /// The simulation script could not be read.
+    Io(std::io::Error),
+    /// The simulation script could not be parsed.
+    Parse(ron::error::SpannedError),
+    /// The simulation did not reach the end screen in time.
+    TimedOut,
+    /// The game summary could not be serialized.
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to read simulation script: {}", error),
+            Self::Parse(error) => write!(f, "failed to parse simulation script: {}", error),
+            Self::TimedOut => write!(
+                f,
+                "simulation did not finish within {} simulated seconds",
+                SIMULATION_TIME_LIMIT
+            ),
+            Self::Serialize(error) => write!(f, "failed to serialize game summary: {}", error),
+        }
+    }
+}
+
+impl Error for SimulationError {}
+
+/// Starts playing as soon as the game is ready, in place of the menu screen.
+fn start_simulation(mut commands: Commands, mut next_game_state: ResMut<NextState<GameState>>) {
+    commands.insert_resource(GameSummary::new());
+    next_game_state.set(GameState::Playing);
+}
+
+/// Makes the scripted decision for a scenario as soon as it begins.
+fn make_scripted_decision(
+    mut commands: Commands,
+    script: Res<SimulationScriptRes>,
+    scenarios_config: Res<ScenariosConfigRes>,
+    scenario_index_state: Res<State<ScenarioIndexState>>,
+    lever_states: Option<ResMut<LeverStatesRes>>,
+    mut next_track_state: ResMut<NextState<TrackState>>,
+    mut lever_switched: EventWriter<LeverSwitched>,
+    mut tracks_texture: Query<&mut Handle<Image>, With<TrackTexture>>,
+    mut lever_player_textures: Query<
+        (&mut Handle<Image>, &LeverPlayerTexture),
+        Without<TrackTexture>,
+    >,
+    image_assets: Res<ImageAssetMap>,
+    audio_assets: Res<AudioAssetMap>,
+    settings: Res<SettingsRes>,
+    self_jumping: Option<ResMut<SelfJumping>>,
+) {
+    // Only scenarios that have been set up have lever states
+    let (Some(scenario_index), Some(mut lever_states)) = (scenario_index_state.0, lever_states)
+    else {
+        return;
+    };
+    let scenario = scenarios_config.get_scenario(scenario_index);
+
+    let Some(decision) = scenario.id.as_ref().and_then(|id| script.decisions.get(id)) else {
+        return;
+    };
+
+    // Switch the levers
+    for (lever_index, lever_state) in decision.levers.iter().enumerate() {
+        let Some(lever) = scenario.levers.get(lever_index) else {
+            warn!(
+                "scenario `{}` has no lever {}",
+                scenario.id.as_deref().unwrap_or_default(),
+                lever_index
+            );
+            continue;
+        };
+
+        if **lever_state >= lever.textures.len() {
+            warn!(
+                "lever {} of scenario `{}` has no position {}",
+                lever_index,
+                scenario.id.as_deref().unwrap_or_default(),
+                **lever_state
+            );
+            continue;
+        }
+
+        switch_lever(
+            &mut commands,
+            scenario,
+            lever_index,
+            *lever_state,
+            &mut lever_states,
+            &mut next_track_state,
+            &mut lever_switched,
+            &mut tracks_texture.single_mut(),
+            &mut lever_player_textures,
+            &image_assets,
+            &audio_assets,
+            &settings,
+        );
+    }
+
+    // Jump onto the track
+    if decision.jump {
+        if let Some(mut self_jumping) = self_jumping {
+            *self_jumping = SelfJumping::Jumping;
+        }
+    }
+
+    // Lock in the lever positions
+    if decision.lock_in {
+        commands.insert_resource(LockedInRes);
+    }
+}
+
+/// A plugin which runs the game without a window or audio output, making
+/// the decisions in a simulation script. Time only advances when the app is
+/// updated, by [`SIMULATION_TIMESTEP`] each update.
+pub struct HeadlessGamePlugin {
+    /// The simulation script.
+    script: SimulationScript,
+}
+
+impl HeadlessGamePlugin {
+    /// Creates a new headless game plugin, given the simulation script.
+    pub const fn new(script: SimulationScript) -> Self {
+        Self { script }
+    }
+}
+
+impl Plugin for HeadlessGamePlugin {
+    fn build(&self, app: &mut App) {
+        // Add app plugins
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), StatesPlugin));
+
+        // Register the asset types usually registered by the render and audio
+        // plugins. Nothing is ever decoded, as no asset loaders are registered
+        app.init_asset::<Image>().init_asset::<AudioSource>();
+
+        // Advance time by a fixed amount on each update
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            SIMULATION_TIMESTEP,
+        )));
+
+        // Seed the random number generator from the script
+        app.insert_resource(RngRes::seeded(self.script.seed));
+
+        app.add_plugins(GameLogicPlugin);
+
+        // Add simulation systems
+        app.insert_resource(SimulationScriptRes(self.script.clone()));
+        app.add_systems(OnEnter(GameState::InMenu), start_simulation);
+        app.add_systems(
+            Update,
+            make_scripted_decision.run_if(state_changed::<ScenarioIndexState>),
+        );
+    }
+}
+
+/// Plays through every scenario without a window, making the decisions in the
+/// simulation script, and returns the final game summary.
+pub fn run_simulation(script: SimulationScript) -> Result<GameSummary, SimulationError> {
+    let mut app = App::new();
+    app.add_plugins(HeadlessGamePlugin::new(script));
+    app.finish();
+    app.cleanup();
+
+    let max_updates = (SIMULATION_TIME_LIMIT / SIMULATION_TIMESTEP).ceil() as usize;
+
+    for _ in 0..max_updates {
+        app.update();
+
+        if *app.world().resource::<State<GameState>>().get() == GameState::EndScreen {
+            return Ok(app.world().resource::<GameSummary>().clone());
+        }
+    }
+
+    Err(SimulationError::TimedOut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs the simulation script at the given path to the end screen.
+    fn simulate(path: &str) -> GameSummary {
+        let script = SimulationScript::load(path).expect("the script should load");
+
+        run_simulation(script).expect("the simulation should reach the end screen")
+    }
+
+    #[test]
+    fn pulling_every_lever_reaches_the_end_screen() {
+        let summary = simulate("simulations/pull-every-lever.ron");
+
+        assert_eq!(summary.killed(EntityKind::People), 20);
+        assert_eq!(summary.saved(EntityKind::People), 48);
+        assert_eq!(summary.saved(EntityKind::Lobsters), 5);
+        assert!(summary.events.contains("killed_hitler"));
+        assert!(summary.events.contains("killed_self"));
+        assert_eq!(summary.decisions.len(), 17);
+        assert_eq!(summary.decisions[0].scenario, "original");
+        assert_eq!(summary.decisions[0].switches, 1);
+    }
+
+    #[test]
+    fn simulated_summaries_serialize_deterministically() {
+        let config = ron::ser::PrettyConfig::default();
+        let first = ron::ser::to_string_pretty(
+            &simulate("simulations/pull-every-lever.ron"),
+            config.clone(),
+        )
+        .unwrap();
+        let second =
+            ron::ser::to_string_pretty(&simulate("simulations/pull-every-lever.ron"), config)
+                .unwrap();
+
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,352 @@
+//! Curved paths, which animation nodes follow at constant speed.
+
+use crate::util::*;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+
+/// The number of samples taken along each segment of a path to measure its
+/// length.
+const SAMPLES_PER_SEGMENT: usize = 16;
+
+/// The shape of the curve drawn through the points of a path.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum SplineCurve {
+    /// A Catmull-Rom spline, which passes through every point.
+    #[default]
+    CatmullRom,
+    /// A Bézier curve, which is pulled towards the points between the start
+    /// and end but does not pass through them.
+    Bezier,
+}
+
+/// A curved path from the start to the end of an animation node, through a
+/// list of points in between. The path is followed at constant speed along its
+/// length, whatever the spacing of its points.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SplinePath {
+    /// The shape of the curve.
+    #[serde(default)]
+    pub curve: SplineCurve,
+    /// The points between the start and end of the path, from the top left of
+    /// the canvas.
+    pub points: Vec<Vec2>,
+    /// Whether to rotate towards the direction of travel, instead of
+    /// interpolating between the start and end rotations. A rotation of zero
+    /// faces right.
+    #[serde(default)]
+    pub rotate_to_tangent: bool,
+    /// The arc lengths of the path, measured the first time it is followed.
+    #[serde(skip)]
+    arc_lengths: ArcLengthCache,
+}
+
+/// The length of a path up to each of its samples.
+#[derive(Debug)]
+struct ArcLengths {
+    /// The start, points and end of the path.
+    control_points: Vec<Vec2>,
+    /// The length of the path up to each sample, starting at zero.
+    lengths: Vec<f32>,
+}
+
+/// A cache of the arc lengths of a path. A path is normally followed between
+/// the same start and end every time, so it is only measured again if either
+/// changes.
+#[derive(Debug, Default)]
+struct ArcLengthCache(Mutex<Option<Arc<ArcLengths>>>);
+
+impl Clone for ArcLengthCache {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.0.lock().unwrap().clone()))
+    }
+}
+
+impl PartialEq for ArcLengthCache {
+    /// The cache is measured from the path, so it never makes two paths
+    /// different.
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl SplinePath {
+    /// Creates a Catmull-Rom path through the given points.
+    pub fn catmull_rom(points: impl IntoIterator<Item = Vec2>) -> Self {
+        Self {
+            curve: SplineCurve::CatmullRom,
+            points: points.into_iter().collect(),
+            rotate_to_tangent: false,
+            arc_lengths: ArcLengthCache::default(),
+        }
+    }
+
+    /// Gets the arc lengths of the path from `start` to `end`, measuring them
+    /// unless they are cached.
+    fn arc_lengths(&self, start: Vec2, end: Vec2) -> Arc<ArcLengths> {
+        let mut cache = self.arc_lengths.0.lock().unwrap();
+
+        if let Some(arc_lengths) = cache.as_ref().filter(|arc_lengths| {
+            arc_lengths.control_points.first() == Some(&start)
+                && arc_lengths.control_points.last() == Some(&end)
+        }) {
+            return arc_lengths.clone();
+        }
+
+        let control_points = [start]
+            .into_iter()
+            .chain(self.points.iter().copied())
+            .chain([end])
+            .collect::<Vec<_>>();
+
+        let num_samples = SAMPLES_PER_SEGMENT * (control_points.len() - 1);
+        let mut lengths = Vec::with_capacity(num_samples + 1);
+        let mut previous_point = control_points[0];
+        let mut length = 0.0;
+
+        for sample in 0..=num_samples {
+            let point = self.point(&control_points, sample as f32 / num_samples as f32);
+            length += point.distance(previous_point);
+            lengths.push(length);
+            previous_point = point;
+        }
+
+        let arc_lengths = Arc::new(ArcLengths {
+            control_points,
+            lengths,
+        });
+        *cache = Some(arc_lengths.clone());
+
+        arc_lengths
+    }
+
+    /// Gets the transform the `progress` portion of the way along the path
+    /// from `start_transform` to `end_transform`. The z coordinate and the
+    /// scale are interpolated linearly.
+    pub fn transform(
+        &self,
+        start_transform: Transform,
+        end_transform: Transform,
+        progress: f32,
+    ) -> Transform {
+        let progress = progress.clamp(0.0, 1.0);
+
+        // Step 1: Get the length of the path up to each sample
+        let arc_lengths = self.arc_lengths(
+            start_transform.translation.truncate(),
+            end_transform.translation.truncate(),
+        );
+        let ArcLengths {
+            control_points,
+            lengths,
+        } = &*arc_lengths;
+        let num_samples = lengths.len() - 1;
+        let length = lengths[num_samples];
+
+        // Step 2: Find the curve parameter at which the path has covered the
+        // progress portion of its length
+        let distance = progress * length;
+        let sample = lengths
+            .partition_point(|&length| length < distance)
+            .clamp(1, num_samples);
+        let sample_length = lengths[sample] - lengths[sample - 1];
+        let sample_progress = if sample_length > 0.0 {
+            (distance - lengths[sample - 1]) / sample_length
+        } else {
+            0.0
+        };
+        let s = (sample as f32 - 1.0 + sample_progress) / num_samples as f32;
+
+        // Step 3: Place the transform at that point on the path, optionally
+        // rotating it towards the direction of travel
+        let mut transform = interpolate_transform(start_transform, end_transform, progress);
+        let point = self.point(control_points, s);
+        transform.translation = point.extend(transform.translation.z);
+
+        if self.rotate_to_tangent {
+            let step = 1.0 / num_samples as f32;
+            let tangent = self.point(control_points, (s + step).min(1.0))
+                - self.point(control_points, (s - step).max(0.0));
+
+            // The canvas y axis points down, so the angle is flipped
+            transform.rotation = Quat::from_rotation_z((-tangent.y).atan2(tangent.x));
+        }
+
+        transform
+    }
+
+    /// Evaluates the curve through the control points, including the start
+    /// and end, at the curve parameter `s` from `0.0` to `1.0`.
+    fn point(&self, control_points: &[Vec2], s: f32) -> Vec2 {
+        match self.curve {
+            SplineCurve::CatmullRom => catmull_rom(control_points, s),
+            SplineCurve::Bezier => bezier(control_points, s),
+        }
+    }
+}
+
+/// Evaluates a centripetal Catmull-Rom spline through the points at the curve
+/// parameter `s`, with each segment between neighbouring points taking an
+/// equal share of the parameter. Unlike a uniform spline, a centripetal spline
+/// never overshoots into loops or cusps when the points are unevenly spaced.
+/// The first and last points are extended by reflection so the curve passes
+/// through them.
+fn catmull_rom(points: &[Vec2], s: f32) -> Vec2 {
+    let num_segments = points.len() - 1;
+    let position = s * num_segments as f32;
+    let segment = (position as usize).min(num_segments - 1);
+    let t = position - segment as f32;
+
+    let p1 = points[segment];
+    let p2 = points[segment + 1];
+    let p0 = if segment == 0 {
+        2.0 * p1 - p2
+    } else {
+        points[segment - 1]
+    };
+    let p3 = if segment + 2 < points.len() {
+        points[segment + 2]
+    } else {
+        2.0 * p2 - p1
+    };
+
+    // Space the knots by the square root of the distance between points,
+    // keeping them apart where points coincide
+    let knot = |a: Vec2, b: Vec2| a.distance(b).sqrt().max(f32::EPSILON);
+    let t0 = 0.0;
+    let t1 = t0 + knot(p0, p1);
+    let t2 = t1 + knot(p1, p2);
+    let t3 = t2 + knot(p2, p3);
+    let u = t1 + (t2 - t1) * t;
+
+    // Interpolate between the interpolations, by the Barry-Goldman method
+    let blend = |a: Vec2, b: Vec2, ta: f32, tb: f32| a.lerp(b, (u - ta) / (tb - ta));
+    let a1 = blend(p0, p1, t0, t1);
+    let a2 = blend(p1, p2, t1, t2);
+    let a3 = blend(p2, p3, t2, t3);
+    let b1 = blend(a1, a2, t0, t2);
+    let b2 = blend(a2, a3, t1, t3);
+
+    blend(b1, b2, t1, t2)
+}
+
+/// Evaluates a Bézier curve with the points as its control points at the
+/// curve parameter `s`, using De Casteljau's algorithm.
+fn bezier(points: &[Vec2], s: f32) -> Vec2 {
+    let mut points = points.to_vec();
+
+    while points.len() > 1 {
+        points = points
+            .windows(2)
+            .map(|pair| pair[0].lerp(pair[1], s))
+            .collect();
+    }
+
+    points[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    /// The tolerance when comparing positions along a path, which is measured
+    /// from samples.
+    const EPSILON: f32 = 0.05;
+
+    #[test]
+    fn catmull_rom_passes_through_points() {
+        let path = SplinePath::catmull_rom([Vec2::new(50.0, -50.0)]);
+        let start = Transform::from_xyz(0.0, 0.0, 1.0);
+        let end = Transform::from_xyz(100.0, 0.0, 3.0);
+
+        let middle = path.transform(start, end, 0.5);
+
+        assert!(middle
+            .translation
+            .abs_diff_eq(Vec3::new(50.0, -50.0, 2.0), EPSILON));
+        assert!(path
+            .transform(start, end, 1.0)
+            .translation
+            .abs_diff_eq(end.translation, EPSILON));
+    }
+
+    #[test]
+    fn path_is_followed_at_constant_speed() {
+        // The points are bunched up near the start, which would slow down a
+        // path followed at a constant rate of the curve parameter
+        let path = SplinePath::catmull_rom([Vec2::new(5.0, 0.0), Vec2::new(10.0, 0.0)]);
+        let start = Transform::IDENTITY;
+        let end = Transform::from_xyz(100.0, 0.0, 0.0);
+
+        for step in 0..=10 {
+            let progress = step as f32 / 10.0;
+            let transform = path.transform(start, end, progress);
+
+            assert!((transform.translation.x - 100.0 * progress).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn bezier_is_pulled_towards_points() {
+        let path = SplinePath {
+            curve: SplineCurve::Bezier,
+            ..SplinePath::catmull_rom([Vec2::new(0.0, -100.0), Vec2::new(100.0, -100.0)])
+        };
+        let start = Transform::IDENTITY;
+        let end = Transform::from_xyz(100.0, 0.0, 0.0);
+
+        let middle = path.transform(start, end, 0.5);
+
+        assert!(middle
+            .translation
+            .abs_diff_eq(Vec3::new(50.0, -75.0, 0.0), EPSILON));
+    }
+
+    #[test]
+    fn rotate_to_tangent_faces_direction_of_travel() {
+        let path = SplinePath {
+            rotate_to_tangent: true,
+            ..SplinePath::catmull_rom([Vec2::new(50.0, -50.0)])
+        };
+        let start = Transform::IDENTITY;
+        let end = Transform::from_xyz(100.0, 0.0, 0.0);
+
+        // At the top of the curve, the path is level
+        let top = path.transform(start, end, 0.5);
+        assert!(top.rotation.abs_diff_eq(Quat::IDENTITY, 1e-3));
+
+        // On the way up, the path climbs towards the top of the canvas, which
+        // is an anticlockwise rotation
+        let (angle, _, _) = path
+            .transform(start, end, 0.1)
+            .rotation
+            .to_euler(EulerRot::ZYX);
+        assert!(angle > 0.0 && angle < TAU / 4.0);
+    }
+
+    #[test]
+    fn arc_lengths_are_measured_once_per_start_and_end() {
+        let path = SplinePath::catmull_rom([Vec2::new(50.0, -50.0)]);
+        let start = Transform::IDENTITY;
+        let end = Transform::from_xyz(100.0, 0.0, 0.0);
+
+        let first = path.arc_lengths(Vec2::ZERO, Vec2::new(100.0, 0.0));
+        path.transform(start, end, 0.25);
+        assert!(Arc::ptr_eq(
+            &first,
+            &path.arc_lengths(Vec2::ZERO, Vec2::new(100.0, 0.0))
+        ));
+
+        // Following the path to a different end measures it again
+        let moved_end = Transform::from_xyz(200.0, 0.0, 0.0);
+        assert!(path
+            .transform(start, moved_end, 1.0)
+            .translation
+            .abs_diff_eq(moved_end.translation, EPSILON));
+        assert!(!Arc::ptr_eq(
+            &first,
+            &path.arc_lengths(Vec2::ZERO, Vec2::new(200.0, 0.0))
+        ));
+    }
+}
@@ -1,6 +1,7 @@
 //! Game states.
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 /// The state of the game.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, States)]
@@ -10,33 +11,71 @@ pub enum GameState {
     Initializing,
     /// The player is in the menu screen.
     InMenu,
+    /// The player is looking at their stats.
+    StatsScreen,
+    /// The player is looking at the controls.
+    ControlsScreen,
+    /// The player is changing the settings.
+    SettingsScreen,
     /// The player is playing the game scenarios.
     Playing,
     /// The player is at the end screen.
     EndScreen,
 }
 
+/// Whether the game is paused, while playing the game scenarios.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, SubStates)]
+#[source(GameState = GameState::Playing)]
+pub enum PauseState {
+    /// The scenarios are running.
+    #[default]
+    Running,
+    /// The scenarios are paused, and the pause menu is shown.
+    Paused,
+}
+
 /// The index of the active scenario.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, States, Deref, DerefMut)]
 pub struct ScenarioIndexState(pub Option<usize>);
 
-/// The state of the lever.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, States)]
-pub enum LeverState {
-    /// The lever has not been pulled.
-    #[default]
-    Normal,
-    /// The lever has been pulled.
-    Pulled,
-}
+/// The state of a lever, as the index of the position it points to. Position
+/// `0` is the position of a lever that has not been pulled.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deref, DerefMut, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct LeverState(pub usize);
 
 impl LeverState {
-    /// Returns whether the lever state is pulled.
-    pub const fn pulled(&self) -> bool {
-        matches!(self, Self::Pulled)
+    /// The lever has not been pulled.
+    pub const NORMAL: Self = Self(0);
+
+    /// Returns the lever state after the lever is pulled once more, given the
+    /// number of lever positions.
+    pub const fn cycled(&self, num_positions: usize) -> Self {
+        Self((self.0 + 1) % num_positions)
     }
 }
 
+/// The index of the track the trolley will take, as decided by the
+/// combination of every lever's state.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    States,
+    Deref,
+    DerefMut,
+    Serialize,
+    Deserialize,
+)]
+#[serde(transparent)]
+pub struct TrackState(pub usize);
+
 /// The state of a scheduled animation.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, States)]
 pub enum AnimationState {
@@ -48,11 +87,3 @@ pub enum AnimationState {
     /// The animation has completed.
     Complete,
 }
-
-/// The index of the active animation.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, States, Deref, DerefMut)]
-pub struct AnimationIndexState(pub Option<usize>);
-
-/// The index of the active animation node.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, States, Deref, DerefMut)]
-pub struct AnimationNodeIndexState(pub Option<usize>);
@@ -0,0 +1,216 @@
+//! The stats screen.
+
+use crate::components::*;
+use crate::constants::*;
+use crate::input::*;
+use crate::resources::*;
+use crate::states::*;
+use bevy::prelude::*;
+
+/// Sets up the stats screen.
+pub fn setup_stats_screen(
+    mut commands: Commands,
+    save_data: Res<SaveDataRes>,
+    scenarios_config: Res<ScenariosConfigRes>,
+    achievements: Res<AchievementsRes>,
+    locales: Res<LocalesRes>,
+) {
+    let mut stats_text_sections = Vec::new();
+
+    stats_text_sections.push(locales.plural("stats.runs", save_data.runs_completed, &[]));
+
+    // Show the lifetime number of people killed and saved, and the number of
+    // any other entities if there were some
+    for kind in EntityKind::ALL {
+        let killed = save_data.killed(kind);
+        let saved = save_data.saved(kind);
+
+        if kind == EntityKind::People || killed > 0 {
+            let key = format!("summary.killed.{}", kind.name());
+            stats_text_sections.push(locales.plural(&key, killed, &[]));
+        }
+
+        if kind == EntityKind::People || saved > 0 {
+            let key = format!("summary.saved.{}", kind.name());
+            stats_text_sections.push(locales.plural(&key, saved, &[]));
+        }
+    }
+
+    let unlocked = achievements
+        .iter()
+        .filter(|achievement| save_data.achievements.contains(&achievement.id))
+        .count();
+    stats_text_sections.push(locales.plural(
+        "stats.achievements",
+        achievements.len(),
+        &[("unlocked", &unlocked)],
+    ));
+
+    stats_text_sections
+        .iter_mut()
+        .for_each(|line| *line = format!("{} {}", BULLET_POINT, line));
+
+    // Show the tracks taken in each scenario played
+    let choice_text_sections = scenarios_config
+        .iter()
+        .filter_map(|scenario| {
+            let choices = save_data.choices.get(scenario.id.as_ref()?)?;
+            let choices_text = choices
+                .iter()
+                .map(|(track, count)| {
+                    locales.format("stats.choice", &[("track", &(track + 1)), ("count", count)])
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            Some(format!(
+                "{} {}: {}",
+                BULLET_POINT,
+                scenario.id.as_ref()?,
+                choices_text
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    // Spawn the stats screen text
+    let text_entity = commands
+        .spawn(NodeBundle {
+            background_color: Color::WHITE.into(),
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(75.0),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(16.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            locales.text("stats.title"),
+                            TextStyle {
+                                color: Color::BLACK,
+                                font_size: 32.0,
+                                ..default()
+                            },
+                        )
+                        .with_text_justify(JustifyText::Center),
+                    );
+
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for stats_line in stats_text_sections {
+                                parent.spawn(TextBundle::from_section(
+                                    stats_line,
+                                    TextStyle {
+                                        color: Color::BLACK,
+                                        font_size: 24.0,
+                                        ..default()
+                                    },
+                                ));
+                            }
+
+                            for choice_line in choice_text_sections {
+                                parent.spawn(TextBundle::from_section(
+                                    choice_line,
+                                    TextStyle {
+                                        color: Color::BLACK,
+                                        font_size: 16.0,
+                                        ..default()
+                                    },
+                                ));
+                            }
+                        });
+
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(16.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: NORMAL_BUTTON_COLOR.into(),
+                                ..default()
+                            },
+                            ButtonAction::BackToMenu,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                locales.text("menu.back"),
+                                TextStyle {
+                                    font_size: 32.0,
+                                    color: Color::srgb(1.0, 1.0, 1.0),
+                                    ..default()
+                                },
+                            ));
+                        });
+                });
+        })
+        .id();
+
+    // Save the entity
+    commands.insert_resource(StatsScreenEntityRes(text_entity));
+}
+
+/// Updates the stats screen when the "Back to menu" button is pressed or the
+/// player confirms.
+pub fn update_stats_screen(
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut inputs: EventReader<PlayerInput>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, mut color) in &mut interaction_query {
+        match *interaction {
+            Interaction::None => {
+                *color = NORMAL_BUTTON_COLOR.into();
+            }
+            Interaction::Hovered => {
+                *color = HOVERED_BUTTON_COLOR.into();
+            }
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON_COLOR.into();
+                next_game_state.set(GameState::InMenu);
+            }
+        }
+    }
+
+    if inputs.read().any(|input| *input == PlayerInput::Confirm) {
+        next_game_state.set(GameState::InMenu);
+    }
+}
+
+/// Cleans up the stats screen.
+pub fn cleanup_stats_screen(mut commands: Commands, entity: Res<StatsScreenEntityRes>) {
+    // Despawn the entity
+    let entity_commands = commands.entity(**entity);
+    entity_commands.despawn_recursive();
+
+    // Remove the entity resource
+    commands.remove_resource::<StatsScreenEntityRes>();
+}
@@ -2,8 +2,38 @@
 
 use crate::constants::*;
 use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Deserializer};
 use std::time::Duration;
 
+/// Spawns a button with a text label on a screen, given the button's bundle.
+pub fn spawn_button(parent: &mut ChildBuilder, text: &str, font_size: f32, bundle: impl Bundle) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    padding: UiRect::all(Val::Px(16.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: NORMAL_BUTTON_COLOR.into(),
+                ..default()
+            },
+            bundle,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font_size,
+                    color: Color::srgb(1.0, 1.0, 1.0),
+                    ..default()
+                },
+            ));
+        });
+}
+
 /// Returns the value between `a` and `b`, such that the value is the `amount`
 /// portion of the way between `a` and `b`.
 pub const fn point_between(a: f32, b: f32, amount: f32) -> f32 {
@@ -33,33 +63,33 @@ pub const fn normalize_transform_to_canvas(transform: Transform) -> Transform {
     ))
 }
 
+/// Returns the transform that is the `amount` portion of the way between `a`
+/// and `b`. The translation and the scale of each axis are interpolated
+/// linearly, and the rotation is interpolated spherically along the shortest
+/// arc.
+pub fn interpolate_transform(a: Transform, b: Transform, amount: f32) -> Transform {
+    Transform {
+        translation: a.translation.lerp(b.translation, amount),
+        rotation: a.rotation.slerp(b.rotation, amount),
+        scale: a.scale.lerp(b.scale, amount),
+    }
+}
+
 /// Calculates the linear transform to perform on an object moving on the
-/// screen.
+/// screen, as in [`interpolate_transform`].
 pub fn movement_transform(
     start_transform: Transform,
     end_transform: Transform,
     duration: Duration,
     time_remaining: Duration,
 ) -> Transform {
-    let start_point_x = start_transform.translation.x;
-    let start_point_y = start_transform.translation.y;
-    let start_scale = start_transform.scale.x;
-    let end_point_x = end_transform.translation.x;
-    let end_point_y = end_transform.translation.y;
-    let end_scale = end_transform.scale.x;
-
     let amount = 1.0 - (time_remaining.as_secs_f32() / duration.as_secs_f32());
 
-    let transformed_x = point_between(start_point_x, end_point_x, amount);
-    let transformed_y = point_between(start_point_y, end_point_y, amount);
-    let transformed_scale = point_between(start_scale, end_scale, amount);
-
-    Transform::IDENTITY
-        .with_translation(normalize_translation_to_canvas(Vec2::new(
-            transformed_x,
-            transformed_y,
-        )))
-        .with_scale(Vec3::new(transformed_scale, transformed_scale, 1.0))
+    normalize_transform_to_canvas(interpolate_transform(
+        start_transform,
+        end_transform,
+        amount,
+    ))
 }
 
 /// Calculates the transform to perform on an object on the horizon approaching
@@ -117,6 +147,144 @@ pub const fn time_remaining_reached(
 }
 
 /// Returns a random amount of time to wait before switching the track.
-pub fn random_switch_delay() -> Duration {
-    Duration::from_secs_f32(rand::random::<f32>() % 2.0)
+pub fn random_switch_delay(rng: &mut impl Rng) -> Duration {
+    Duration::from_secs_f32(rng.gen::<f32>() % 2.0)
+}
+
+/// The on-disk representation of a transform in scenario files.
+#[derive(Deserialize)]
+struct TransformDef {
+    /// The screen position, from the top left of the canvas.
+    translation: Vec2,
+    /// The z coordinate.
+    #[serde(default)]
+    z: f32,
+    /// The rotation around the z axis, in turns.
+    #[serde(default)]
+    rotation: f32,
+    /// The scale.
+    #[serde(default = "TransformDef::default_scale")]
+    scale: Vec3,
+}
+
+impl TransformDef {
+    /// The default scale of a transform.
+    const fn default_scale() -> Vec3 {
+        Vec3::ONE
+    }
+}
+
+/// Deserializes a transform from its scenario file representation.
+pub fn deserialize_transform<'de, D>(deserializer: D) -> Result<Transform, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let transform = TransformDef::deserialize(deserializer)?;
+
+    Ok(Transform::IDENTITY
+        .with_translation(transform.translation.extend(transform.z))
+        .with_rotation(Quat::from_rotation_z(
+            transform.rotation * std::f32::consts::TAU,
+        ))
+        .with_scale(transform.scale))
+}
+
+/// A rectangle as written in a scenario file.
+#[derive(Deserialize)]
+struct RectDef {
+    /// The top-left corner of the rectangle.
+    min: Vec2,
+    /// The bottom-right corner of the rectangle.
+    max: Vec2,
+}
+
+/// Deserializes a rectangle from its scenario file representation.
+pub fn deserialize_rect<'de, D>(deserializer: D) -> Result<Rect, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let rect = RectDef::deserialize(deserializer)?;
+
+    Ok(Rect::from_corners(rect.min, rect.max))
+}
+
+/// The tolerance when comparing interpolated values in tests.
+#[cfg(test)]
+pub const EPSILON: f32 = 1e-5;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    #[test]
+    fn interpolate_transform_translation_and_scale_per_axis() {
+        let start = Transform::from_xyz(100.0, 200.0, 1.0).with_scale(Vec3::new(1.0, 2.0, 1.0));
+        let end = Transform::from_xyz(300.0, 100.0, 5.0).with_scale(Vec3::new(3.0, 4.0, 0.0));
+
+        let transform = interpolate_transform(start, end, 0.25);
+
+        assert!(transform
+            .translation
+            .abs_diff_eq(Vec3::new(150.0, 175.0, 2.0), EPSILON));
+        assert!(transform
+            .scale
+            .abs_diff_eq(Vec3::new(1.5, 2.5, 0.75), EPSILON));
+    }
+
+    #[test]
+    fn interpolate_transform_slerps_rotation() {
+        let start = Transform::IDENTITY;
+        let end = Transform::from_rotation(Quat::from_rotation_z(-0.375 * TAU));
+
+        let transform = interpolate_transform(start, end, 0.5);
+
+        assert!(transform
+            .rotation
+            .abs_diff_eq(Quat::from_rotation_z(-0.1875 * TAU), EPSILON));
+    }
+
+    #[test]
+    fn interpolate_transform_endpoints() {
+        let start = Transform::from_xyz(10.0, 20.0, 3.0)
+            .with_rotation(Quat::from_rotation_z(0.1 * TAU))
+            .with_scale(Vec3::new(0.5, 0.5, 1.0));
+        let end = Transform::from_xyz(40.0, 80.0, -3.0)
+            .with_rotation(Quat::from_rotation_z(-0.2 * TAU))
+            .with_scale(Vec3::new(2.0, 1.0, 1.0));
+
+        let at_start = interpolate_transform(start, end, 0.0);
+        let at_end = interpolate_transform(start, end, 1.0);
+
+        assert!(at_start.translation.abs_diff_eq(start.translation, EPSILON));
+        assert!(at_start.rotation.abs_diff_eq(start.rotation, EPSILON));
+        assert!(at_start.scale.abs_diff_eq(start.scale, EPSILON));
+        assert!(at_end.translation.abs_diff_eq(end.translation, EPSILON));
+        assert!(at_end.rotation.abs_diff_eq(end.rotation, EPSILON));
+        assert!(at_end.scale.abs_diff_eq(end.scale, EPSILON));
+    }
+
+    #[test]
+    fn movement_transform_normalizes_to_canvas() {
+        let start = Transform::from_xyz(100.0, 100.0, 2.0)
+            .with_rotation(Quat::from_rotation_z(0.25 * TAU))
+            .with_scale(Vec3::new(1.0, 1.0, 1.0));
+        let end = Transform::from_xyz(500.0, 300.0, 6.0)
+            .with_rotation(Quat::IDENTITY)
+            .with_scale(Vec3::new(3.0, 2.0, 1.0));
+
+        let transform =
+            movement_transform(start, end, Duration::from_secs(4), Duration::from_secs(1));
+
+        // Three quarters of the way to the end, relative to the canvas center
+        assert!(transform
+            .translation
+            .abs_diff_eq(Vec3::new(0.0, 50.0, 5.0), EPSILON));
+        assert!(transform
+            .rotation
+            .abs_diff_eq(Quat::from_rotation_z(0.0625 * TAU), EPSILON));
+        assert!(transform
+            .scale
+            .abs_diff_eq(Vec3::new(2.5, 1.75, 1.0), EPSILON));
+    }
 }
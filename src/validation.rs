@@ -0,0 +1,1045 @@
+//! Scenario definition validation.
+
+use crate::animation::*;
+use crate::locale::*;
+use crate::resources::*;
+use crate::states::*;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+/// A problem found in a scenario definition.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScenarioValidationError {
+    /// A texture name does not correspond to an embedded image.
+    UnknownTexture {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The name of the texture.
+        texture: String,
+    },
+    /// A sound name does not correspond to an embedded sound.
+    UnknownSound {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The name of the sound.
+        sound: String,
+    },
+    /// A hostages texture is set, but the hostages position is not.
+    MissingHostagesPosition {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The index of the track the hostages are on.
+        track: usize,
+    },
+    /// The scenario has no tracks.
+    NoTracks {
+        /// The index of the scenario.
+        scenario: usize,
+    },
+    /// A lever has no textures, and so no positions.
+    LeverWithoutTextures {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The index of the lever.
+        lever: usize,
+    },
+    /// No track is taken for a combination of lever states.
+    UnroutedLeverStates {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The combination of lever states with no track.
+        lever_states: Vec<LeverState>,
+    },
+    /// No animation runs for a combination of lever states.
+    UncoveredLeverStates {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The combination of lever states with no animation.
+        lever_states: Vec<LeverState>,
+    },
+    /// The scenario duration is not positive.
+    NonPositiveScenarioDuration {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The configured duration.
+        duration: f32,
+    },
+    /// More than one scenario has the same ID.
+    DuplicateId {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The duplicated ID.
+        id: String,
+    },
+    /// The scenario text or title key is missing from the default locale.
+    UnknownTextKey {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The unknown key.
+        key: String,
+    },
+    /// A successor refers to a scenario ID that does not exist.
+    UnknownSuccessor {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The unknown ID.
+        id: String,
+    },
+    /// A condition refers to a game summary value or event that does not
+    /// exist.
+    UnknownSummaryValue {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The name of the unknown value.
+        name: String,
+    },
+    /// A condition refers to a flag that no animation in the scenario raises.
+    UnknownFlag {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The name of the unknown flag.
+        flag: String,
+    },
+    /// More than one achievement has the same ID.
+    DuplicateAchievementId {
+        /// The duplicated ID.
+        id: String,
+    },
+    /// An achievement icon does not correspond to an embedded image.
+    UnknownAchievementIcon {
+        /// The ID of the achievement.
+        achievement: String,
+        /// The name of the texture.
+        texture: String,
+    },
+    /// An achievement text key is missing from the default locale.
+    UnknownAchievementTextKey {
+        /// The ID of the achievement.
+        achievement: String,
+        /// The unknown key.
+        key: String,
+    },
+    /// An achievement unlock condition refers to a game summary value or
+    /// event that does not exist.
+    UnknownAchievementValue {
+        /// The ID of the achievement.
+        achievement: String,
+        /// The name of the unknown value.
+        name: String,
+    },
+    /// The duration of an animation node is not positive.
+    NonPositiveNodeDuration {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The index of the animation within the scenario.
+        animation: usize,
+        /// The index of the node within the animation.
+        node: usize,
+        /// The configured duration.
+        duration: f32,
+    },
+    /// An event or action in the timeline of an animation is not within the
+    /// duration of the animation.
+    TimelineTimeOutOfRange {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The index of the animation within the scenario.
+        animation: usize,
+        /// The configured time.
+        time: f32,
+    },
+    /// An animation targets a lever or hostages that do not exist.
+    MissingAnimationTarget {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The index of the animation within the scenario.
+        animation: usize,
+    },
+    /// A frame animation targets a lever or hostages that do not exist.
+    MissingFrameAnimationTarget {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The index of the frame animation within the scenario.
+        frame_animation: usize,
+    },
+    /// A frame animation has no frames.
+    NoFrames {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The index of the frame animation within the scenario.
+        frame_animation: usize,
+    },
+    /// A frame of a frame animation is not in its sprite sheet.
+    FrameOutOfRange {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The index of the frame animation within the scenario.
+        frame_animation: usize,
+        /// The index of the frame in the sprite sheet.
+        index: usize,
+    },
+    /// A frame of a frame animation has a duration of zero or less.
+    NonPositiveFrameDuration {
+        /// The index of the scenario.
+        scenario: usize,
+        /// The index of the frame animation within the scenario.
+        frame_animation: usize,
+        /// The configured duration.
+        duration: f32,
+    },
+}
+
+impl fmt::Display for ScenarioValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownTexture { scenario, texture } => {
+                write!(f, "scenario {}: unknown texture `{}`", scenario, texture)
+            }
+            Self::UnknownSound { scenario, sound } => {
+                write!(f, "scenario {}: unknown sound `{}`", scenario, sound)
+            }
+            Self::MissingHostagesPosition { scenario, track } => write!(
+                f,
+                "scenario {}: hostages texture on track {} has no position",
+                scenario, track
+            ),
+            Self::NoTracks { scenario } => write!(f, "scenario {}: no tracks", scenario),
+            Self::LeverWithoutTextures { scenario, lever } => {
+                write!(f, "scenario {}: lever {} has no textures", scenario, lever)
+            }
+            Self::UnroutedLeverStates {
+                scenario,
+                lever_states,
+            } => write!(
+                f,
+                "scenario {}: no track for lever states {:?}",
+                scenario, lever_states
+            ),
+            Self::UncoveredLeverStates {
+                scenario,
+                lever_states,
+            } => write!(
+                f,
+                "scenario {}: no animation for lever states {:?}",
+                scenario, lever_states
+            ),
+            Self::NonPositiveScenarioDuration { scenario, duration } => write!(
+                f,
+                "scenario {}: duration {} is not positive",
+                scenario, duration
+            ),
+            Self::DuplicateId { scenario, id } => {
+                write!(f, "scenario {}: duplicate ID `{}`", scenario, id)
+            }
+            Self::UnknownTextKey { scenario, key } => {
+                write!(f, "scenario {}: unknown text key `{}`", scenario, key)
+            }
+            Self::UnknownSuccessor { scenario, id } => {
+                write!(f, "scenario {}: unknown successor `{}`", scenario, id)
+            }
+            Self::UnknownSummaryValue { scenario, name } => {
+                write!(
+                    f,
+                    "scenario {}: unknown game summary value `{}`",
+                    scenario, name
+                )
+            }
+            Self::UnknownFlag { scenario, flag } => {
+                write!(f, "scenario {}: unknown flag `{}`", scenario, flag)
+            }
+            Self::DuplicateAchievementId { id } => {
+                write!(f, "duplicate achievement ID `{}`", id)
+            }
+            Self::UnknownAchievementIcon {
+                achievement,
+                texture,
+            } => write!(
+                f,
+                "achievement `{}`: unknown icon texture `{}`",
+                achievement, texture
+            ),
+            Self::UnknownAchievementTextKey { achievement, key } => {
+                write!(
+                    f,
+                    "achievement `{}`: unknown text key `{}`",
+                    achievement, key
+                )
+            }
+            Self::UnknownAchievementValue { achievement, name } => write!(
+                f,
+                "achievement `{}`: unknown game summary value `{}`",
+                achievement, name
+            ),
+            Self::NonPositiveNodeDuration {
+                scenario,
+                animation,
+                node,
+                duration,
+            } => write!(
+                f,
+                "scenario {}: node {} of animation {} has non-positive duration {}",
+                scenario, node, animation, duration
+            ),
+            Self::TimelineTimeOutOfRange {
+                scenario,
+                animation,
+                time,
+            } => write!(
+                f,
+                "scenario {}: animation {} has an event or action at {}s, outside the animation",
+                scenario, animation, time
+            ),
+            Self::MissingAnimationTarget {
+                scenario,
+                animation,
+            } => write!(
+                f,
+                "scenario {}: animation {} targets a lever or hostages that do not exist",
+                scenario, animation
+            ),
+            Self::MissingFrameAnimationTarget {
+                scenario,
+                frame_animation,
+            } => write!(
+                f,
+                "scenario {}: frame animation {} targets a lever or hostages that do not exist",
+                scenario, frame_animation
+            ),
+            Self::NoFrames {
+                scenario,
+                frame_animation,
+            } => write!(
+                f,
+                "scenario {}: frame animation {} has no frames",
+                scenario, frame_animation
+            ),
+            Self::FrameOutOfRange {
+                scenario,
+                frame_animation,
+                index,
+            } => write!(
+                f,
+                "scenario {}: frame animation {} shows frame {}, outside its sprite sheet",
+                scenario, frame_animation, index
+            ),
+            Self::NonPositiveFrameDuration {
+                scenario,
+                frame_animation,
+                duration,
+            } => write!(
+                f,
+                "scenario {}: frame animation {} has a frame with non-positive duration {}",
+                scenario, frame_animation, duration
+            ),
+        }
+    }
+}
+
+impl Error for ScenarioValidationError {}
+
+/// The collection of problems found in the scenario definitions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioValidationReport(pub Vec<ScenarioValidationError>);
+
+impl fmt::Display for ScenarioValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} invalid scenario definition(s)", self.0.len())?;
+
+        for error in &self.0 {
+            write!(f, "\n  {}", error)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ScenarioValidationReport {}
+
+/// Checks that an outcome condition only refers to existing game summary
+/// values and events, and to flags raised in the scenario, adding any
+/// problems found to `errors`.
+fn validate_condition(
+    scenario_index: usize,
+    condition: &OutcomeCondition,
+    events: &HashSet<&str>,
+    flags: &HashSet<&str>,
+    errors: &mut Vec<ScenarioValidationError>,
+) {
+    let summary = GameSummary::new();
+
+    match condition {
+        OutcomeCondition::Track(_) | OutcomeCondition::SelfJumped => {}
+        OutcomeCondition::Event(name) => {
+            if !events.contains(name.as_str()) {
+                errors.push(ScenarioValidationError::UnknownSummaryValue {
+                    scenario: scenario_index,
+                    name: name.clone(),
+                });
+            }
+        }
+        OutcomeCondition::Flag(flag) => {
+            if !flags.contains(flag.as_str()) {
+                errors.push(ScenarioValidationError::UnknownFlag {
+                    scenario: scenario_index,
+                    flag: flag.clone(),
+                });
+            }
+        }
+        OutcomeCondition::CountAtLeast(name, _) => {
+            if summary.count(name).is_none() {
+                errors.push(ScenarioValidationError::UnknownSummaryValue {
+                    scenario: scenario_index,
+                    name: name.clone(),
+                });
+            }
+        }
+        OutcomeCondition::All(conditions) | OutcomeCondition::Any(conditions) => {
+            for condition in conditions {
+                validate_condition(scenario_index, condition, events, flags, errors);
+            }
+        }
+        OutcomeCondition::Not(condition) => {
+            validate_condition(scenario_index, condition, events, flags, errors);
+        }
+    }
+}
+
+/// Checks that an achievement unlock condition only refers to existing game
+/// summary values and events, adding any problems found to `errors`.
+fn validate_achievement_condition(
+    achievement: &AchievementConfig,
+    condition: &AchievementCondition,
+    events: &HashSet<&str>,
+    errors: &mut Vec<ScenarioValidationError>,
+) {
+    let summary = GameSummary::new();
+
+    let unknown_name = match condition {
+        AchievementCondition::Always => None,
+        AchievementCondition::Event(name) => (!events.contains(name.as_str())).then_some(name),
+        AchievementCondition::CountAtLeast(name, _) => {
+            summary.count(name).is_none().then_some(name)
+        }
+        AchievementCondition::All(conditions) | AchievementCondition::Any(conditions) => {
+            for condition in conditions {
+                validate_achievement_condition(achievement, condition, events, errors);
+            }
+
+            None
+        }
+        AchievementCondition::Not(condition) => {
+            validate_achievement_condition(achievement, condition, events, errors);
+
+            None
+        }
+    };
+
+    if let Some(name) = unknown_name {
+        errors.push(ScenarioValidationError::UnknownAchievementValue {
+            achievement: achievement.id.clone(),
+            name: name.clone(),
+        });
+    }
+}
+
+/// Lists every combination of lever states, given the number of positions of
+/// each lever.
+fn lever_state_combinations(num_positions: &[usize]) -> Vec<Vec<LeverState>> {
+    num_positions
+        .iter()
+        .fold(vec![Vec::new()], |combinations, &positions| {
+            combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    (0..positions).map(move |position| {
+                        let mut combination = combination.clone();
+                        combination.push(LeverState(position));
+                        combination
+                    })
+                })
+                .collect()
+        })
+}
+
+/// Checks a single scenario and its animations, adding any problems found to
+/// `errors`.
+fn validate_scenario(
+    scenario_index: usize,
+    scenario: &ScenarioConfig,
+    animations: &[AnimationConfig],
+    asset_paths: &AssetMapPaths,
+    scenario_ids: &HashSet<&str>,
+    events: &HashSet<&str>,
+    errors: &mut Vec<ScenarioValidationError>,
+) {
+    let has_texture = |name: &str| {
+        let path = format!("assets/images/{}.png", name);
+        asset_paths.image_asset_paths.contains(&path)
+    };
+    let has_sound = |name: &str| {
+        let path = format!("assets/sounds/{}.mp3", name);
+        asset_paths.audio_asset_paths.contains(&path)
+    };
+
+    // Check that there is at least one track
+    if scenario.tracks.is_empty() {
+        errors.push(ScenarioValidationError::NoTracks {
+            scenario: scenario_index,
+        });
+    }
+
+    // Check that every referenced texture exists
+    let textures = scenario
+        .tracks
+        .iter()
+        .flat_map(|track| [Some(&track.tracks_texture), track.hostages_texture.as_ref()])
+        .chain([scenario.trolley_texture_override.as_ref()])
+        .flatten()
+        .chain(scenario.levers.iter().flat_map(|lever| &lever.textures))
+        .chain(
+            animations
+                .iter()
+                .filter_map(|animation| animation.wounded_texture.as_ref()),
+        )
+        .chain(
+            animations
+                .iter()
+                .flat_map(|animation| &animation.timeline)
+                .filter_map(|timed_event| match &timed_event.event {
+                    TimelineEvent::SetTexture(texture) => Some(texture),
+                    _ => None,
+                }),
+        )
+        .chain(
+            scenario
+                .frame_animations
+                .iter()
+                .map(|frame_animation| &frame_animation.animation.texture),
+        );
+
+    for texture in textures {
+        if !has_texture(texture) {
+            errors.push(ScenarioValidationError::UnknownTexture {
+                scenario: scenario_index,
+                texture: texture.clone(),
+            });
+        }
+    }
+
+    // Check that every referenced sound exists
+    let sounds = scenario
+        .tracks
+        .iter()
+        .filter_map(|track| track.scream_sound_override.as_ref())
+        .chain(
+            animations
+                .iter()
+                .flat_map(|animation| &animation.timeline)
+                .filter_map(|timed_event| match &timed_event.event {
+                    TimelineEvent::PlaySound(sound) => Some(sound),
+                    _ => None,
+                }),
+        );
+
+    for sound in sounds {
+        if !has_sound(sound) {
+            errors.push(ScenarioValidationError::UnknownSound {
+                scenario: scenario_index,
+                sound: sound.clone(),
+            });
+        }
+    }
+
+    // Check that hostages have somewhere to stand
+    for (track_index, track) in scenario.tracks.iter().enumerate() {
+        if track.hostages_texture.is_some() && track.hostages_pos.is_none() {
+            errors.push(ScenarioValidationError::MissingHostagesPosition {
+                scenario: scenario_index,
+                track: track_index,
+            });
+        }
+    }
+
+    // Check that every lever has at least one position
+    for (lever_index, lever) in scenario.levers.iter().enumerate() {
+        if lever.textures.is_empty() {
+            errors.push(ScenarioValidationError::LeverWithoutTextures {
+                scenario: scenario_index,
+                lever: lever_index,
+            });
+        }
+    }
+
+    // Check that a track is taken and a trolley animation runs when the
+    // trolley runs, whatever the lever states
+    let num_positions = scenario
+        .levers
+        .iter()
+        .map(|lever| lever.textures.len())
+        .collect::<Vec<_>>();
+
+    for lever_states in lever_state_combinations(&num_positions) {
+        let Some(track) = scenario.route(&lever_states) else {
+            errors.push(ScenarioValidationError::UnroutedLeverStates {
+                scenario: scenario_index,
+                lever_states,
+            });
+            continue;
+        };
+
+        let covered = animations.iter().any(|animation| {
+            animation.target == AnimationTarget::Trolley
+                && animation.cue.is_none()
+                && animation
+                    .track_condition
+                    .is_none_or(|condition| condition == track)
+                && lever_states_match(&animation.lever_states_condition, &lever_states)
+        });
+
+        if !covered {
+            errors.push(ScenarioValidationError::UncoveredLeverStates {
+                scenario: scenario_index,
+                lever_states,
+            });
+        }
+    }
+
+    // Check that outcomes only refer to existing game summary values and to
+    // flags raised in the scenario
+    let flags = animations
+        .iter()
+        .flat_map(|animation| &animation.timeline)
+        .filter_map(|timed_event| match &timed_event.event {
+            TimelineEvent::SetFlag(flag) => Some(flag.as_str()),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+    let flags = &flags;
+
+    for outcome in &scenario.outcomes {
+        if let Some(condition) = &outcome.when {
+            validate_condition(scenario_index, condition, events, flags, errors);
+        }
+    }
+
+    // Check that successors exist
+    for successor in &scenario.next {
+        if let SuccessorTarget::Scenario(id) = &successor.goto {
+            if !scenario_ids.contains(id.as_str()) {
+                errors.push(ScenarioValidationError::UnknownSuccessor {
+                    scenario: scenario_index,
+                    id: id.clone(),
+                });
+            }
+        }
+
+        if let Some(condition) = &successor.when {
+            validate_condition(scenario_index, condition, events, flags, errors);
+        }
+    }
+
+    // Check that durations are positive
+    if scenario.duration.is_nan() || scenario.duration <= 0.0 {
+        errors.push(ScenarioValidationError::NonPositiveScenarioDuration {
+            scenario: scenario_index,
+            duration: scenario.duration,
+        });
+    }
+
+    // Check that every animation target exists
+    let target_exists = |target: &AnimationTarget| match target {
+        AnimationTarget::Trolley | AnimationTarget::Tagged(_) => true,
+        AnimationTarget::Lever(lever_index) => *lever_index < scenario.levers.len(),
+        AnimationTarget::Hostages(track_index) => scenario
+            .tracks
+            .get(*track_index)
+            .is_some_and(|track| track.hostages_texture.is_some()),
+    };
+
+    for (animation_index, animation) in animations.iter().enumerate() {
+        if !target_exists(&animation.target) {
+            errors.push(ScenarioValidationError::MissingAnimationTarget {
+                scenario: scenario_index,
+                animation: animation_index,
+            });
+        }
+    }
+
+    for (frame_animation_index, frame_animation) in scenario.frame_animations.iter().enumerate() {
+        if !target_exists(&frame_animation.target) {
+            errors.push(ScenarioValidationError::MissingFrameAnimationTarget {
+                scenario: scenario_index,
+                frame_animation: frame_animation_index,
+            });
+        }
+
+        // Check that every frame is in the sprite sheet and is shown for some
+        // time
+        let animation = &frame_animation.animation;
+        let num_cells = (animation.columns * animation.rows) as usize;
+
+        if animation.frames.is_empty() {
+            errors.push(ScenarioValidationError::NoFrames {
+                scenario: scenario_index,
+                frame_animation: frame_animation_index,
+            });
+        }
+
+        for frame in &animation.frames {
+            if frame.index >= num_cells {
+                errors.push(ScenarioValidationError::FrameOutOfRange {
+                    scenario: scenario_index,
+                    frame_animation: frame_animation_index,
+                    index: frame.index,
+                });
+            }
+
+            if frame.duration.is_nan() || frame.duration <= 0.0 {
+                errors.push(ScenarioValidationError::NonPositiveFrameDuration {
+                    scenario: scenario_index,
+                    frame_animation: frame_animation_index,
+                    duration: frame.duration,
+                });
+            }
+        }
+    }
+
+    for (animation_index, animation) in animations.iter().enumerate() {
+        // Check that the timeline fits within the animation
+        let duration = animation
+            .nodes
+            .iter()
+            .map(|node| node.duration)
+            .sum::<f32>();
+        let times = animation
+            .timeline
+            .iter()
+            .map(|timed_event| timed_event.time)
+            .chain(animation.action_times.iter().copied());
+
+        for time in times {
+            if !(0.0..=duration).contains(&time) {
+                errors.push(ScenarioValidationError::TimelineTimeOutOfRange {
+                    scenario: scenario_index,
+                    animation: animation_index,
+                    time,
+                });
+            }
+        }
+
+        for (node_index, node) in animation.nodes.iter().enumerate() {
+            if node.duration.is_nan() || node.duration <= 0.0 {
+                errors.push(ScenarioValidationError::NonPositiveNodeDuration {
+                    scenario: scenario_index,
+                    animation: animation_index,
+                    node: node_index,
+                    duration: node.duration,
+                });
+            }
+        }
+    }
+}
+
+/// Validates the scenario and achievement definitions against the embedded
+/// assets and the locales, returning a report of every problem found.
+pub fn validate_scenarios(
+    scenarios: &[ScenarioConfig],
+    animations: &[Vec<AnimationConfig>],
+    achievements: &[AchievementConfig],
+    asset_paths: &AssetMapPaths,
+    locales: &Locales,
+) -> Result<(), ScenarioValidationReport> {
+    let mut errors = Vec::new();
+    let mut scenario_ids = HashSet::new();
+
+    // Collect every event that can happen during the game
+    let events = scenarios
+        .iter()
+        .flat_map(|scenario| &scenario.outcomes)
+        .flat_map(|outcome| &outcome.events)
+        .map(String::as_str)
+        .collect::<HashSet<_>>();
+
+    for (scenario_index, scenario) in scenarios.iter().enumerate() {
+        for key in [&scenario.text_key, &scenario.title_key] {
+            if !locales.contains(key) {
+                errors.push(ScenarioValidationError::UnknownTextKey {
+                    scenario: scenario_index,
+                    key: key.clone(),
+                });
+            }
+        }
+
+        if let Some(id) = &scenario.id {
+            if !scenario_ids.insert(id.as_str()) {
+                errors.push(ScenarioValidationError::DuplicateId {
+                    scenario: scenario_index,
+                    id: id.clone(),
+                });
+            }
+        }
+    }
+
+    for (scenario_index, (scenario, scenario_animations)) in
+        scenarios.iter().zip(animations).enumerate()
+    {
+        validate_scenario(
+            scenario_index,
+            scenario,
+            scenario_animations,
+            asset_paths,
+            &scenario_ids,
+            &events,
+            &mut errors,
+        );
+    }
+
+    let mut achievement_ids = HashSet::new();
+
+    for achievement in achievements {
+        if !achievement_ids.insert(achievement.id.as_str()) {
+            errors.push(ScenarioValidationError::DuplicateAchievementId {
+                id: achievement.id.clone(),
+            });
+        }
+
+        if !locales.contains(&achievement.text_key) {
+            errors.push(ScenarioValidationError::UnknownAchievementTextKey {
+                achievement: achievement.id.clone(),
+                key: achievement.text_key.clone(),
+            });
+        }
+
+        if let Some(icon) = &achievement.icon {
+            let path = format!("assets/images/{}.png", icon);
+
+            if !asset_paths.image_asset_paths.contains(&path) {
+                errors.push(ScenarioValidationError::UnknownAchievementIcon {
+                    achievement: achievement.id.clone(),
+                    texture: icon.clone(),
+                });
+            }
+        }
+
+        validate_achievement_condition(achievement, &achievement.unlock, &events, &mut errors);
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ScenarioValidationReport(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario_file::ron_options;
+    use bevy::prelude::*;
+    use std::collections::{BTreeMap, HashMap};
+
+    /// A scenario with two tracks switched by a single lever.
+    const SCENARIO: &str = r#"(
+        text_key: "scenario.text",
+        title_key: "scenario.title",
+        duration: 10.0,
+        levers: [(textures: ["lever-normal", "lever-switched"])],
+        tracks: [(tracks_texture: "tracks-normal"), (tracks_texture: "tracks-switched")],
+    )"#;
+
+    /// Parses a scenario configuration as written in a scenario file.
+    fn scenario(contents: &str) -> ScenarioConfig {
+        ron_options().from_str(contents).unwrap()
+    }
+
+    /// Creates an animation of the trolley, whatever the lever states.
+    fn trolley_animation() -> Animation {
+        Animation::new(Transform::IDENTITY).node(AnimationNode::new(1.0, Transform::IDENTITY))
+    }
+
+    /// Gets the paths of the assets the test scenarios refer to.
+    fn asset_paths() -> AssetMapPaths {
+        AssetMapPaths {
+            image_asset_paths: [
+                "lever-normal",
+                "lever-switched",
+                "tracks-normal",
+                "tracks-switched",
+            ]
+            .iter()
+            .map(|name| format!("assets/images/{}.png", name))
+            .collect(),
+            audio_asset_paths: Vec::new(),
+            music_asset_paths: Vec::new(),
+        }
+    }
+
+    /// Creates a default locale with the text of the test scenarios.
+    fn locales() -> Locales {
+        let locale = Locale {
+            name: "English".to_owned(),
+            plural_rule: PluralRule::OneIsSingular,
+            messages: HashMap::from([
+                ("scenario.text".to_owned(), "Text".to_owned()),
+                ("scenario.title".to_owned(), "Title".to_owned()),
+            ]),
+        };
+
+        Locales::new(BTreeMap::from([(DEFAULT_LOCALE.to_owned(), locale)])).unwrap()
+    }
+
+    /// Validates the scenarios, each with the given animations, returning the
+    /// problems found.
+    fn validate(
+        scenarios: &[ScenarioConfig],
+        animations: &[Animation],
+    ) -> Vec<ScenarioValidationError> {
+        let animations = scenarios
+            .iter()
+            .map(|_| animations.iter().map(Animation::config).collect())
+            .collect::<Vec<_>>();
+
+        validate_scenarios(scenarios, &animations, &[], &asset_paths(), &locales())
+            .err()
+            .map(|report| report.0)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn valid_scenario_passes() {
+        assert_eq!(validate(&[scenario(SCENARIO)], &[trolley_animation()]), []);
+    }
+
+    #[test]
+    fn unknown_references_are_reported() {
+        let mut config = scenario(SCENARIO);
+        config.id = Some("first".to_owned());
+        config.text_key = "scenario.missing".to_owned();
+        config.trolley_texture_override = Some("missing-trolley".to_owned());
+        config.next = vec![SuccessorConfig {
+            when: None,
+            goto: SuccessorTarget::Scenario("missing".to_owned()),
+        }];
+
+        assert_eq!(
+            validate(&[config], &[trolley_animation()]),
+            [
+                ScenarioValidationError::UnknownTextKey {
+                    scenario: 0,
+                    key: "scenario.missing".to_owned(),
+                },
+                ScenarioValidationError::UnknownTexture {
+                    scenario: 0,
+                    texture: "missing-trolley".to_owned(),
+                },
+                ScenarioValidationError::UnknownSuccessor {
+                    scenario: 0,
+                    id: "missing".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_ids_are_reported() {
+        let mut first = scenario(SCENARIO);
+        first.id = Some("same".to_owned());
+        let mut second = scenario(SCENARIO);
+        second.id = Some("same".to_owned());
+
+        assert_eq!(
+            validate(&[first, second], &[trolley_animation()]),
+            [ScenarioValidationError::DuplicateId {
+                scenario: 1,
+                id: "same".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn uncovered_lever_states_are_reported() {
+        let animation = trolley_animation().on_track(TrackState(0));
+
+        assert_eq!(
+            validate(&[scenario(SCENARIO)], &[animation]),
+            [ScenarioValidationError::UncoveredLeverStates {
+                scenario: 0,
+                lever_states: vec![LeverState(1)],
+            }]
+        );
+    }
+
+    #[test]
+    fn non_positive_and_nan_durations_are_reported() {
+        let mut zero = scenario(SCENARIO);
+        zero.duration = 0.0;
+        let mut nan = scenario(SCENARIO);
+        nan.duration = f32::NAN;
+
+        assert_eq!(
+            validate(&[zero], &[trolley_animation()]),
+            [ScenarioValidationError::NonPositiveScenarioDuration {
+                scenario: 0,
+                duration: 0.0,
+            }]
+        );
+
+        // NaN is never equal to itself, so the variant is matched instead
+        let errors = validate(&[nan], &[trolley_animation()]);
+        assert!(matches!(
+            errors.as_slice(),
+            [ScenarioValidationError::NonPositiveScenarioDuration { scenario: 0, duration }]
+                if duration.is_nan()
+        ));
+    }
+
+    #[test]
+    fn nan_node_duration_is_reported() {
+        let animation = Animation::new(Transform::IDENTITY)
+            .node(AnimationNode::new(f32::NAN, Transform::IDENTITY));
+
+        let errors = validate(&[scenario(SCENARIO)], &[animation]);
+        assert!(matches!(
+            errors.as_slice(),
+            [ScenarioValidationError::NonPositiveNodeDuration {
+                scenario: 0,
+                animation: 0,
+                node: 0,
+                duration,
+            }] if duration.is_nan()
+        ));
+    }
+
+    #[test]
+    fn timeline_outside_animation_is_reported() {
+        let animation = trolley_animation().event_at(1.5, TimelineEvent::Cue("late".to_owned()));
+
+        assert_eq!(
+            validate(&[scenario(SCENARIO)], &[animation]),
+            [ScenarioValidationError::TimelineTimeOutOfRange {
+                scenario: 0,
+                animation: 0,
+                time: 1.5,
+            }]
+        );
+    }
+
+    #[test]
+    fn flags_no_animation_raises_are_reported() {
+        let mut config = scenario(SCENARIO);
+        config.outcomes = ron_options()
+            .from_str(r#"[(when: Flag("raised")), (when: Not(Flag("missing")))]"#)
+            .unwrap();
+        let animation =
+            trolley_animation().event_at(0.5, TimelineEvent::SetFlag("raised".to_owned()));
+
+        assert_eq!(
+            validate(&[config], &[animation]),
+            [ScenarioValidationError::UnknownFlag {
+                scenario: 0,
+                flag: "missing".to_owned(),
+            }]
+        );
+    }
+}